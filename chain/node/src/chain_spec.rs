@@ -196,7 +196,7 @@ fn testnet_genesis(
 				map
 			},
 		},
-		ethereum: EthereumConfig {},
+		ethereum: EthereumConfig { state_trie: true },
 		dynamic_fee: Default::default(),
 		base_fee: Default::default(),
 	}
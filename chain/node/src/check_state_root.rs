@@ -0,0 +1,61 @@
+//! Consistency check of the Ethereum-compatible state root.
+
+use std::sync::Arc;
+
+use fp_rpc::EthereumStateRootApi;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, SharedParams};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+
+/// Recompute the Ethereum state root from scratch and compare it with the maintained one.
+#[derive(Debug, clap::Parser)]
+pub struct CheckStateRootCmd {
+	/// Block hash or number to check. Defaults to the best block.
+	#[clap(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl CheckStateRootCmd {
+	/// Run the check against `client`.
+	pub fn run<B, C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+		C::Api: EthereumStateRootApi<B>,
+		<NumberFor<B> as std::str::FromStr>::Err: std::fmt::Debug,
+	{
+		let id = match &self.input {
+			Some(input) => input.parse::<B>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		let (maintained, recomputed) = client
+			.runtime_api()
+			.check_state_root(&id)
+			.map_err(|e| format!("Runtime API call failed: {:?}", e))?
+			.ok_or_else(|| format!("The Ethereum state trie is not maintained at {}", id))?;
+
+		if maintained != recomputed {
+			return Err(format!(
+				"State root mismatch at {}: maintained {:?}, recomputed {:?}",
+				id, maintained, recomputed
+			)
+			.into());
+		}
+		println!("State root at {} is consistent: {:?}", id, maintained);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CheckStateRootCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
@@ -15,6 +15,29 @@ impl Default for Sealing {
 	}
 }
 
+/// How imported blocks are checked against the Ethereum block built by the runtime.
+#[derive(Debug, Copy, Clone, clap::ArgEnum)]
+pub enum PostLogValidation {
+	/// Only check that blocks have exactly one Frontier log.
+	Disabled,
+	/// Log and count mismatching blocks, and blocks that could not be checked. The import of
+	/// these blocks still succeeds.
+	Flag,
+	/// Execute blocks before importing them, and fail the import of mismatching ones. Every
+	/// imported block is executed twice, doubling the import cost.
+	Reject,
+}
+
+impl From<PostLogValidation> for fc_consensus::PostLogValidation {
+	fn from(validation: PostLogValidation) -> Self {
+		match validation {
+			PostLogValidation::Disabled => fc_consensus::PostLogValidation::Disabled,
+			PostLogValidation::Flag => fc_consensus::PostLogValidation::Flag,
+			PostLogValidation::Reject => fc_consensus::PostLogValidation::Reject,
+		}
+	}
+}
+
 #[allow(missing_docs)]
 #[derive(Debug, clap::Parser)]
 pub struct RunCmd {
@@ -41,6 +64,119 @@ pub struct RunCmd {
 	/// The dynamic-fee pallet target gas price set by block author
 	#[clap(long, default_value = "1")]
 	pub target_gas_price: u64,
+
+	/// Number of threads mapping finalized history in parallel. 0 disables the backfill and
+	/// leaves history to the tip-following mapping sync.
+	#[clap(long, default_value = "4")]
+	pub mapping_backfill_threads: u32,
+
+	/// Number of blocks each backfill thread maps and writes at once.
+	#[clap(long, default_value = "1000")]
+	pub mapping_backfill_batch_size: u32,
+
+	/// Keep the Frontier mappings of only this many blocks below the last finalized one, deleting
+	/// older ones. Set it to the `--pruning` value to prune mappings in step with state. Keeps
+	/// every mapping by default.
+	#[clap(long)]
+	pub mapping_retention: Option<u32>,
+
+	/// Compare the Frontier log of imported blocks with the Ethereum block the runtime built
+	/// once executed. Mismatches are counted by `frontier_post_log_mismatches_total`, and blocks
+	/// that could not be checked by `frontier_post_log_check_failures_total`. `reject` executes
+	/// every imported block twice.
+	#[clap(long, arg_enum, ignore_case = true, default_value = "disabled")]
+	pub post_log_validation: PostLogValidation,
+
+	/// Index Ethereum transactions by sender and nonce, address and created contract while
+	/// mapping blocks, and serve the Otterscan `ots_*` RPC namespace from these indices. Blocks
+	/// mapped before it was enabled are indexed by the mapping backfill, which has to be enabled
+	/// with `--mapping-backfill-threads`.
+	#[clap(long)]
+	pub enable_ots: bool,
+
+	/// Serve the EIP-1767 GraphQL API on this port. Disabled by default.
+	#[clap(long)]
+	pub graphql_port: Option<u16>,
+
+	/// Listen for GraphQL queries on all interfaces instead of localhost only.
+	#[clap(long)]
+	pub graphql_external: bool,
+
+	/// Maximum nesting depth of a GraphQL query.
+	#[clap(long, default_value = "10")]
+	pub graphql_max_depth: usize,
+
+	/// Maximum complexity of a GraphQL query. Each field counts for one, times the number of
+	/// blocks, transactions or logs it may return, and fields returning an account for five.
+	#[clap(long, default_value = "1000")]
+	pub graphql_max_complexity: usize,
+
+	/// Serve JSON-RPC over HTTP on this port with the per-IP rate limit, the maximum batch size
+	/// and the maximum payload enforced, which the standard RPC servers cannot do. Unsafe methods
+	/// are denied. Also answers `GET /health`, and `GET /ready` while the node can serve up to
+	/// date Ethereum data. Without it, no server answers these paths. The standard RPC servers
+	/// only enforce the per-method rate limits, the EVM call limits and the maximum payload.
+	#[clap(long)]
+	pub rpc_guard_port: Option<u16>,
+
+	/// Listen on all interfaces for guarded RPC requests instead of localhost only.
+	#[clap(long)]
+	pub rpc_guard_external: bool,
+
+	/// Maximum size in MiB of a request to the guarded RPC and GraphQL servers, and to the
+	/// standard RPC servers unless `--rpc-max-payload` is set.
+	#[clap(long, default_value = "15")]
+	pub rpc_guard_max_payload: usize,
+
+	/// Calls per second a single IP address may make to the guarded RPC and GraphQL servers, each
+	/// GraphQL query counting as one. Requires `--rpc-guard-port` or `--graphql-port`.
+	#[clap(long)]
+	pub rpc_ip_rate_limit: Option<u32>,
+
+	/// Calls per second of a method across all callers and RPC servers, as `method=rate`. May be
+	/// repeated.
+	#[clap(long, parse(try_from_str = parse_method_rate_limit))]
+	pub rpc_method_rate_limit: Vec<(String, u32)>,
+
+	/// Maximum number of calls in a batch sent to the guarded RPC server. Requires
+	/// `--rpc-guard-port`.
+	#[clap(long)]
+	pub rpc_max_batch_size: Option<usize>,
+
+	/// Milliseconds after which `eth_call`, `eth_estimateGas` and `eth_createAccessList` fail.
+	/// The execution is not interrupted and keeps running in the background until it ends.
+	#[clap(long)]
+	pub rpc_evm_timeout: Option<u64>,
+
+	/// Maximum number of `eth_call`, `eth_estimateGas` and `eth_createAccessList` executing at
+	/// a time, including the ones past `--rpc-evm-timeout` and GraphQL calls. Calls beyond it fail
+	/// right away.
+	#[clap(long)]
+	pub rpc_max_concurrent_evm_calls: Option<usize>,
+
+	/// Maximum gas `eth_call`, `eth_estimateGas` and `eth_createAccessList` execute with.
+	#[clap(long)]
+	pub rpc_gas_cap: Option<u64>,
+
+	/// Number of best chain blocks not yet mapped past which `/ready` of the guarded RPC server
+	/// fails, 16 by default. Requires `--rpc-guard-port`.
+	#[clap(long)]
+	pub ready_max_mapping_lag: Option<u64>,
+
+	/// Number of connected peers below which `/ready` of the guarded RPC server fails, 1 by
+	/// default. Requires `--rpc-guard-port`.
+	#[clap(long)]
+	pub ready_min_peers: Option<usize>,
+}
+
+fn parse_method_rate_limit(limit: &str) -> Result<(String, u32), String> {
+	let (method, rate) = limit
+		.split_once('=')
+		.ok_or_else(|| format!("expected `method=rate`, got `{}`", limit))?;
+	let rate = rate
+		.parse()
+		.map_err(|err| format!("invalid rate `{}`: {}", rate, err))?;
+	Ok((method.to_string(), rate))
 }
 
 #[derive(Debug, clap::Parser)]
@@ -61,6 +197,9 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Build a chain specification with EVM accounts imported from a Geth genesis file.
+	ImportGethGenesis(crate::geth_genesis::ImportGethGenesisCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -70,15 +209,30 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the EVM accounts at a block as a Geth state dump.
+	ExportEvmState(crate::evm_state::ExportEvmStateCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Build a chain specification with EVM accounts imported from a Geth state dump.
+	ImportEvmState(crate::evm_state::ImportEvmStateCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Check the Ethereum state root against one recomputed from scratch.
+	CheckStateRoot(crate::check_state_root::CheckStateRootCmd),
+
+	/// Copy the RocksDB Frontier mapping database into ParityDb.
+	MigrateFrontierDb(crate::frontier_db::MigrateFrontierDbCmd),
+
+	/// Inspect and repair the Frontier mapping database.
+	FrontierDb(crate::frontier_db::FrontierDbCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[clap(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
@@ -0,0 +1,671 @@
+//! Hardhat and Anvil compatible development RPC, served when blocks are sealed manually.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+};
+
+use codec::{Decode, Encode};
+use fc_rpc::{
+	internal_err, EthBlockDataCache, EthImpersonator, EthPendingBlockCache, EthereumTransaction,
+};
+use fc_rpc_core::types::{Bytes, TransactionMessage};
+use frontier_template_runtime::{
+	opaque::Block, AccountId, BlockNumber, Call, EVMCall, EthereumCall, Hash, Index, MinimumPeriod,
+	Runtime, SignedExtra, SignedPayload, SudoCall, UncheckedExtrinsic,
+};
+use futures::{
+	channel::{mpsc, oneshot},
+	future, SinkExt,
+};
+use jsonrpc_core::{BoxFuture, Error, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::backend::{Backend, StateBackend};
+use sc_consensus_manual_seal::rpc::EngineCommand;
+use sc_transaction_pool::FullPool;
+use sc_transaction_pool_api::{
+	ChainEvent, InPoolTransaction, MaintainedTransactionPool, TransactionPool, TransactionSource,
+};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, H160, H256, U256};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::{
+	generic::{BlockId, Era},
+	traits::Header as HeaderT,
+	MultiAddress,
+};
+use substrate_frame_rpc_system::AccountNonceApi;
+
+use crate::{
+	geth_genesis::Quantity,
+	service::{FullClient, MockTimestampInherentDataProvider},
+};
+
+/// Hardhat and Anvil compatible development methods.
+#[rpc(server)]
+pub trait DevApi {
+	/// Seal a new block, optionally with the given timestamp in seconds.
+	#[rpc(name = "evm_mine")]
+	fn mine(&self, timestamp: Option<Quantity>) -> BoxFuture<Result<String>>;
+
+	/// Move the clock forward by the given number of seconds. Returns the total time added.
+	#[rpc(name = "evm_increaseTime", alias("anvil_increaseTime"))]
+	fn increase_time(&self, seconds: Quantity) -> Result<u64>;
+
+	/// Set the timestamp of the next block, in seconds.
+	#[rpc(
+		name = "evm_setNextBlockTimestamp",
+		alias("anvil_setNextBlockTimestamp")
+	)]
+	fn set_next_block_timestamp(&self, timestamp: Quantity) -> Result<()>;
+
+	/// Record the current chain and clock, returning the id to revert to.
+	#[rpc(name = "evm_snapshot", alias("anvil_snapshot"))]
+	fn snapshot(&self) -> Result<U256>;
+
+	/// Revert the chain and clock to a snapshot. Returns false for unknown snapshots.
+	#[rpc(name = "evm_revert", alias("anvil_revert"))]
+	fn revert(&self, id: U256) -> BoxFuture<Result<bool>>;
+
+	/// Set the balance of an account.
+	#[rpc(name = "hardhat_setBalance", alias("anvil_setBalance"))]
+	fn set_balance(&self, address: H160, balance: U256) -> BoxFuture<Result<bool>>;
+
+	/// Set the code of an account.
+	#[rpc(name = "hardhat_setCode", alias("anvil_setCode"))]
+	fn set_code(&self, address: H160, code: Bytes) -> BoxFuture<Result<bool>>;
+
+	/// Set a storage slot of an account.
+	#[rpc(name = "hardhat_setStorageAt", alias("anvil_setStorageAt"))]
+	fn set_storage_at(&self, address: H160, position: U256, value: H256)
+		-> BoxFuture<Result<bool>>;
+
+	/// Accept `eth_sendTransaction` from an account without holding its key.
+	#[rpc(name = "hardhat_impersonateAccount", alias("anvil_impersonateAccount"))]
+	fn impersonate_account(&self, address: H160) -> Result<bool>;
+
+	/// Stop impersonating an account.
+	#[rpc(
+		name = "hardhat_stopImpersonatingAccount",
+		alias("anvil_stopImpersonatingAccount")
+	)]
+	fn stop_impersonating_account(&self, address: H160) -> Result<bool>;
+}
+
+/// Dev RPC state, shared by the handlers of every RPC server. It holds the node's client,
+/// pool, Frontier backend and RPC caches, which `evm_revert` all brings back to the snapshot.
+pub struct DevState {
+	client: Arc<FullClient>,
+	backend: Arc<sc_service::TFullBackend<Block>>,
+	pool: Arc<FullPool<Block, FullClient>>,
+	frontier_backend: Arc<fc_db::Backend<Block>>,
+	block_data_cache: Arc<EthBlockDataCache<Block>>,
+	pending_block: Arc<EthPendingBlockCache<Block>>,
+	/// Total time added through `evm_increaseTime`, in seconds.
+	time_offset: AtomicU64,
+	snapshots: Mutex<Snapshots>,
+	impersonated: Mutex<BTreeSet<H160>>,
+}
+
+impl DevState {
+	/// Create the state of the chain of `client`.
+	pub fn new(
+		client: Arc<FullClient>,
+		backend: Arc<sc_service::TFullBackend<Block>>,
+		pool: Arc<FullPool<Block, FullClient>>,
+		frontier_backend: Arc<fc_db::Backend<Block>>,
+		block_data_cache: Arc<EthBlockDataCache<Block>>,
+		pending_block: Arc<EthPendingBlockCache<Block>>,
+	) -> Self {
+		Self {
+			client,
+			backend,
+			pool,
+			frontier_backend,
+			block_data_cache,
+			pending_block,
+			time_offset: AtomicU64::new(0),
+			snapshots: Mutex::new(Snapshots::default()),
+			impersonated: Mutex::new(BTreeSet::new()),
+		}
+	}
+
+	/// Remove snapshot `id` and every snapshot taken after it, returning snapshot `id`.
+	fn take_snapshot(&self, id: U256) -> Result<Option<Snapshot>> {
+		let mut snapshots = self
+			.snapshots
+			.lock()
+			.map_err(|_| internal_err("snapshots lock poisoned"))?;
+		if id > U256::from(u64::MAX) {
+			return Ok(None);
+		}
+		let id = id.low_u64();
+		let snapshot = snapshots.taken.remove(&id);
+		// Like Hardhat, reverting drops the snapshot and every snapshot taken after it.
+		if snapshot.is_some() {
+			snapshots.taken.retain(|taken, _| *taken < id);
+		}
+
+		Ok(snapshot)
+	}
+
+	/// Whether sealed blocks can be finalized. Blocks sealed while a snapshot is live are left
+	/// unfinalized, as the client only reverts unfinalized blocks.
+	fn finalize_sealed(&self) -> bool {
+		self.snapshots
+			.lock()
+			.map(|snapshots| snapshots.taken.is_empty())
+			.unwrap_or(false)
+	}
+
+	/// Revert the blocks above `number` through the client, then resync the pool and drop
+	/// what the Frontier mapping and the RPC caches hold about the reverted blocks.
+	async fn revert_to(&self, number: BlockNumber) -> Result<()> {
+		let info = self.client.info();
+		let blocks = info.best_number.saturating_sub(number);
+		if blocks == 0 {
+			return Ok(());
+		}
+
+		// The headers are read before the revert, which removes the blocks from the chain.
+		let mut reverted = Vec::with_capacity(blocks as usize);
+		let mut hash = info.best_hash;
+		for _ in 0..blocks {
+			let header = self
+				.client
+				.header(BlockId::Hash(hash))
+				.map_err(|e| internal_err(format!("{:?}", e)))?
+				.ok_or_else(|| internal_err(format!("header {:?} not found", hash)))?;
+			hash = *header.parent_hash();
+			reverted.push(header);
+		}
+
+		let reverted_blocks = self
+			.client
+			.revert(blocks)
+			.map_err(|e| internal_err(format!("failed to revert blocks: {:?}", e)))?;
+		// Blocks are reverted from the best one down.
+		reverted.truncate(reverted_blocks as usize);
+
+		// Revalidate the pool against the state of the new best block.
+		self.pool
+			.maintain(ChainEvent::NewBestBlock {
+				hash: self.client.info().best_hash,
+				tree_route: None,
+			})
+			.await;
+
+		let mut commitments = Vec::new();
+		let mut unmapped = Vec::new();
+		for header in &reverted {
+			match fc_mapping_sync::mapping_commitment::<Block>(header).map_err(internal_err)? {
+				Some(commitment) => commitments.push(commitment),
+				None => unmapped.push(header.hash()),
+			}
+		}
+		{
+			let _lock = self.frontier_backend.pruning_lock().write();
+			self.frontier_backend
+				.mapping()
+				.prune_batch(commitments, unmapped)
+				.map_err(internal_err)?;
+			// The mapping sync worker fails on tips that are no longer in the chain.
+			let meta = self.frontier_backend.meta();
+			let tips = meta
+				.current_syncing_tips()
+				.map_err(internal_err)?
+				.into_iter()
+				.filter(|tip| !reverted.iter().any(|header| header.hash() == *tip))
+				.collect();
+			meta.write_current_syncing_tips(tips)
+				.map_err(internal_err)?;
+		}
+
+		self.block_data_cache.clear().await;
+		self.pending_block.clear();
+
+		if reverted_blocks != blocks {
+			return Err(internal_err(format!(
+				"reverted {} out of {} blocks, the others are finalized",
+				reverted_blocks, blocks
+			)));
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Default)]
+struct Snapshots {
+	last_id: u64,
+	taken: BTreeMap<u64, Snapshot>,
+}
+
+/// Chain and clock position recorded by `evm_snapshot`.
+struct Snapshot {
+	number: BlockNumber,
+	timestamp: u64,
+	time_offset: u64,
+}
+
+/// Dev RPC handler. State setters and impersonated transactions are dispatched as `sudo`
+/// extrinsics, signed with the development key holding the sudo role.
+pub struct Dev<C, P> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	command_sink: mpsc::Sender<EngineCommand<Hash>>,
+	state: Arc<DevState>,
+}
+
+impl<C, P> Clone for Dev<C, P> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			pool: self.pool.clone(),
+			command_sink: self.command_sink.clone(),
+			state: self.state.clone(),
+		}
+	}
+}
+
+impl<C, P> Dev<C, P> {
+	/// Create a handler sealing blocks through `command_sink`.
+	pub fn new(
+		client: Arc<C>,
+		pool: Arc<P>,
+		command_sink: mpsc::Sender<EngineCommand<Hash>>,
+		state: Arc<DevState>,
+	) -> Self {
+		Self {
+			client,
+			pool,
+			command_sink,
+			state,
+		}
+	}
+}
+
+impl<C, P> Dev<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: Core<Block> + AccountNonceApi<Block, AccountId, Index>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	/// Seal a block and wait for it to be imported, and finalized unless a snapshot is live.
+	fn seal(&self, create_empty: bool) -> BoxFuture<Result<()>> {
+		let mut command_sink = self.command_sink.clone();
+		let finalize = self.state.finalize_sealed();
+		Box::pin(async move {
+			let (sender, receiver) = oneshot::channel();
+			command_sink
+				.send(EngineCommand::SealNewBlock {
+					create_empty,
+					finalize,
+					parent_hash: None,
+					sender: Some(sender),
+				})
+				.await
+				.map_err(|e| internal_err(format!("failed to send seal command: {}", e)))?;
+			receiver
+				.await
+				.map_err(|_| internal_err("sealing task dropped the seal command"))?
+				.map_err(|e| internal_err(format!("failed to seal block: {}", e)))?;
+			Ok(())
+		})
+	}
+
+	/// Build a signed `sudo` extrinsic dispatching `call`.
+	fn sudo_extrinsic(&self, call: Call) -> Result<UncheckedExtrinsic> {
+		let info = self.client.info();
+		let at = BlockId::Hash(info.best_hash);
+
+		let sudo_key = self
+			.state
+			.backend
+			.state_at(at)
+			.map_err(|e| internal_err(format!("{:?}", e)))?
+			.storage(&[twox_128(b"Sudo"), twox_128(b"Key")].concat())
+			.map_err(|e| internal_err(format!("{:?}", e)))?
+			.and_then(|raw| AccountId::decode(&mut &raw[..]).ok())
+			.ok_or_else(|| internal_err("sudo key not set"))?;
+		let keyring = Sr25519Keyring::iter()
+			.find(|keyring| keyring.to_account_id() == sudo_key)
+			.ok_or_else(|| internal_err("sudo key is not a development account"))?;
+
+		let api = self.client.runtime_api();
+		let version = api
+			.version(&at)
+			.map_err(|e| internal_err(format!("{:?}", e)))?;
+		let nonce = api
+			.account_nonce(&at, sudo_key.clone())
+			.map_err(|e| internal_err(format!("{:?}", e)))?;
+		let nonce = self.ready_nonce(&sudo_key, nonce);
+
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let payload = SignedPayload::from_raw(
+			SudoCall::<Runtime>::sudo {
+				call: Box::new(call),
+			}
+			.into(),
+			extra,
+			(
+				version.spec_version,
+				version.transaction_version,
+				info.genesis_hash,
+				info.genesis_hash,
+				(),
+				(),
+				(),
+			),
+		);
+		let signature = payload.using_encoded(|payload| keyring.sign(payload));
+		let (call, extra, _) = payload.deconstruct();
+
+		Ok(UncheckedExtrinsic::new_signed(
+			call,
+			MultiAddress::Id(sudo_key),
+			signature.into(),
+			extra,
+		))
+	}
+
+	/// Skip the nonces already used by the account's ready transactions.
+	fn ready_nonce(&self, account: &AccountId, mut nonce: Index) -> Index {
+		let mut tag = (account, nonce).encode();
+		for transaction in self.pool.ready() {
+			if transaction.provides().get(0) == Some(&tag) {
+				nonce += 1;
+				tag = (account, nonce).encode();
+			}
+		}
+		nonce
+	}
+
+	/// Submit `call` to the pool as a `sudo` extrinsic.
+	fn submit_sudo(&self, call: Call) -> BoxFuture<Result<()>> {
+		let extrinsic = match self.sudo_extrinsic(call) {
+			Ok(extrinsic) => extrinsic,
+			Err(e) => return Box::pin(future::err(e)),
+		};
+		let submit = self.pool.submit_one(
+			&BlockId::Hash(self.client.info().best_hash),
+			TransactionSource::Local,
+			extrinsic.into(),
+		);
+
+		Box::pin(async move {
+			submit
+				.await
+				.map(|_| ())
+				.map_err(|e| internal_err(format!("submit sudo extrinsic to pool failed: {:?}", e)))
+		})
+	}
+
+	/// Apply a state setter and seal it, so that the change is visible from the latest block.
+	fn force(&self, call: Call) -> BoxFuture<Result<bool>> {
+		let submit = self.submit_sudo(call);
+		let seal = self.seal(false);
+
+		Box::pin(async move {
+			submit.await?;
+			seal.await?;
+			Ok(true)
+		})
+	}
+}
+
+impl<C, P> DevApi for Dev<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: Core<Block> + AccountNonceApi<Block, AccountId, Index>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn mine(&self, timestamp: Option<Quantity>) -> BoxFuture<Result<String>> {
+		if let Some(timestamp) = timestamp {
+			if let Err(e) = self.set_next_block_timestamp(timestamp) {
+				return Box::pin(future::err(e));
+			}
+		}
+		let seal = self.seal(true);
+
+		Box::pin(async move { seal.await.map(|_| "0x0".to_string()) })
+	}
+
+	fn increase_time(&self, seconds: Quantity) -> Result<u64> {
+		let seconds = parse_seconds(&seconds)?;
+		MockTimestampInherentDataProvider::advance(seconds * 1000);
+
+		Ok(self.state.time_offset.fetch_add(seconds, Ordering::SeqCst) + seconds)
+	}
+
+	fn set_next_block_timestamp(&self, timestamp: Quantity) -> Result<()> {
+		let millis = parse_seconds(&timestamp)? * 1000;
+		// The timestamp pallet requires blocks to be at least `MinimumPeriod` apart.
+		let earliest = MockTimestampInherentDataProvider::current() + MinimumPeriod::get();
+		if millis < earliest {
+			return Err(Error::invalid_params(format!(
+				"timestamp {} is lower than the earliest next block timestamp {}",
+				millis / 1000,
+				(earliest + 999) / 1000
+			)));
+		}
+		MockTimestampInherentDataProvider::set_next(millis);
+
+		Ok(())
+	}
+
+	fn snapshot(&self) -> Result<U256> {
+		let mut snapshots = self
+			.state
+			.snapshots
+			.lock()
+			.map_err(|_| internal_err("snapshots lock poisoned"))?;
+		snapshots.last_id += 1;
+		let id = snapshots.last_id;
+		snapshots.taken.insert(
+			id,
+			Snapshot {
+				number: self.client.info().best_number,
+				timestamp: MockTimestampInherentDataProvider::current(),
+				time_offset: self.state.time_offset.load(Ordering::SeqCst),
+			},
+		);
+
+		Ok(U256::from(id))
+	}
+
+	fn revert(&self, id: U256) -> BoxFuture<Result<bool>> {
+		let snapshot = match self.state.take_snapshot(id) {
+			Ok(Some(snapshot)) => snapshot,
+			Ok(None) => return Box::pin(future::ok(false)),
+			Err(e) => return Box::pin(future::err(e)),
+		};
+		let state = self.state.clone();
+
+		Box::pin(async move {
+			state.revert_to(snapshot.number).await?;
+			MockTimestampInherentDataProvider::reset(snapshot.timestamp);
+			state
+				.time_offset
+				.store(snapshot.time_offset, Ordering::SeqCst);
+
+			Ok(true)
+		})
+	}
+
+	fn set_balance(&self, address: H160, balance: U256) -> BoxFuture<Result<bool>> {
+		if balance > U256::from(u128::MAX) {
+			return Box::pin(future::err(Error::invalid_params(
+				"balance does not fit in the runtime balance type",
+			)));
+		}
+
+		self.force(
+			EVMCall::<Runtime>::force_set_balance {
+				address,
+				balance: balance.low_u128(),
+			}
+			.into(),
+		)
+	}
+
+	fn set_code(&self, address: H160, code: Bytes) -> BoxFuture<Result<bool>> {
+		self.force(
+			EVMCall::<Runtime>::force_set_code {
+				address,
+				code: code.into_vec(),
+			}
+			.into(),
+		)
+	}
+
+	fn set_storage_at(
+		&self,
+		address: H160,
+		position: U256,
+		value: H256,
+	) -> BoxFuture<Result<bool>> {
+		let mut index = [0u8; 32];
+		position.to_big_endian(&mut index);
+
+		self.force(
+			EVMCall::<Runtime>::force_set_storage {
+				address,
+				index: H256(index),
+				value,
+			}
+			.into(),
+		)
+	}
+
+	fn impersonate_account(&self, address: H160) -> Result<bool> {
+		self.state
+			.impersonated
+			.lock()
+			.map_err(|_| internal_err("impersonated accounts lock poisoned"))?
+			.insert(address);
+
+		Ok(true)
+	}
+
+	fn stop_impersonating_account(&self, address: H160) -> Result<bool> {
+		self.state
+			.impersonated
+			.lock()
+			.map_err(|_| internal_err("impersonated accounts lock poisoned"))?
+			.remove(&address);
+
+		Ok(true)
+	}
+}
+
+impl<C, P> EthImpersonator for Dev<C, P>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: Core<Block> + AccountNonceApi<Block, AccountId, Index>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn is_impersonated(&self, address: &H160) -> bool {
+		self.state
+			.impersonated
+			.lock()
+			.map(|impersonated| impersonated.contains(address))
+			.unwrap_or(false)
+	}
+
+	fn submit(&self, message: TransactionMessage, address: &H160) -> BoxFuture<Result<H256>> {
+		let transaction = impersonated_transaction(message, address);
+		let transaction_hash = transaction.hash();
+		let submit = self.submit_sudo(
+			EthereumCall::<Runtime>::force_transact {
+				source: *address,
+				transaction,
+			}
+			.into(),
+		);
+
+		Box::pin(async move { submit.await.map(|_| transaction_hash) })
+	}
+}
+
+/// Parse a number of seconds small enough to be converted to milliseconds.
+fn parse_seconds(quantity: &Quantity) -> Result<u64> {
+	let seconds = quantity.parse().map_err(Error::invalid_params)?;
+	if seconds > U256::from(u64::MAX / 1000) {
+		return Err(Error::invalid_params("number of seconds is too large"));
+	}
+
+	Ok(seconds.low_u64())
+}
+
+/// Build the transaction for `message` with a placeholder signature, which `force_transact`
+/// ignores. The source is embedded in `r` so that impersonated transactions of different
+/// accounts do not share a hash.
+///
+/// No key signs it: anywhere but in `force_transact`, the sender is recovered from the
+/// signature and is never `source`, so the transaction cannot be replayed as `source` through
+/// `transact` or the pool.
+fn impersonated_transaction(message: TransactionMessage, source: &H160) -> EthereumTransaction {
+	let mut r = H256::from(*source);
+	r.0[0] = 1;
+	let s = H256::from_low_u64_be(1);
+
+	match message {
+		TransactionMessage::Legacy(m) => {
+			let v = m.chain_id.map_or(27, |chain_id| 2 * chain_id + 35);
+			EthereumTransaction::Legacy(ethereum::LegacyTransaction {
+				nonce: m.nonce,
+				gas_price: m.gas_price,
+				gas_limit: m.gas_limit,
+				action: m.action,
+				value: m.value,
+				input: m.input,
+				signature: ethereum::TransactionSignature::new(v, r, s)
+					.expect("r and s are non-zero and below the curve order; qed"),
+			})
+		}
+		TransactionMessage::EIP2930(m) => {
+			EthereumTransaction::EIP2930(ethereum::EIP2930Transaction {
+				chain_id: m.chain_id,
+				nonce: m.nonce,
+				gas_price: m.gas_price,
+				gas_limit: m.gas_limit,
+				action: m.action,
+				value: m.value,
+				input: m.input,
+				access_list: m.access_list,
+				odd_y_parity: false,
+				r,
+				s,
+			})
+		}
+		TransactionMessage::EIP1559(m) => {
+			EthereumTransaction::EIP1559(ethereum::EIP1559Transaction {
+				chain_id: m.chain_id,
+				nonce: m.nonce,
+				max_priority_fee_per_gas: m.max_priority_fee_per_gas,
+				max_fee_per_gas: m.max_fee_per_gas,
+				gas_limit: m.gas_limit,
+				action: m.action,
+				value: m.value,
+				input: m.input,
+				access_list: m.access_list,
+				odd_y_parity: false,
+				r,
+				s,
+			})
+		}
+	}
+}
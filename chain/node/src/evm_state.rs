@@ -0,0 +1,260 @@
+//! Export of the EVM state as a Geth state dump, and import of such a dump into a chain spec.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::Debug,
+	fs::File,
+	io::{self, BufReader, BufWriter, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+use codec::Decode;
+use fp_evm::GenesisAccount;
+use fp_rpc::EthereumRuntimeRPCApi;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, SharedParams};
+use sc_client_api::{Backend, StorageProvider};
+use serde::Serialize;
+use serde_json::Value;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	hashing::{blake2_128, keccak_256, twox_128},
+	storage::StorageKey,
+	Bytes, H160, H256,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+
+use crate::geth_genesis::{parse_h160, print_spec_with_accounts, GethAccount};
+
+/// Export the EVM accounts at a block as a Geth `dump --iterative` JSON stream.
+#[derive(Debug, clap::Parser)]
+pub struct ExportEvmStateCmd {
+	/// Block hash or number to export. Defaults to the best block.
+	#[clap(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Output file name, or stdout if unspecified.
+	#[clap(long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// Account line of a Geth `dump --iterative` stream.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpAccount {
+	balance: String,
+	nonce: u64,
+	root: H256,
+	code_hash: H256,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	code: Option<Bytes>,
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	storage: BTreeMap<H256, String>,
+	address: H160,
+	key: H256,
+}
+
+impl ExportEvmStateCmd {
+	/// Write the EVM accounts of `client` at the requested block.
+	///
+	/// Accounts are the contracts and the accounts of the Ethereum state trie maintained by the
+	/// runtime: externally owned accounts never touched by the EVM cannot be enumerated.
+	pub fn run<B, C, BE>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + StorageProvider<B, BE>,
+		C::Api: EthereumRuntimeRPCApi<B>,
+		BE: Backend<B>,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		let id = match &self.input {
+			Some(input) => input.parse::<B>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		let api = client.runtime_api();
+		let api_error = |e: sp_api::ApiError| format!("Runtime API call failed: {:?}", e);
+
+		let mut out: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(io::stdout()),
+		};
+
+		let root = api
+			.current_block(&id)
+			.map_err(api_error)?
+			.map(|block| block.header.state_root)
+			.unwrap_or_default();
+		writeln!(out, "{}", serde_json::json!({ "root": root }))?;
+
+		let mut addresses = BTreeSet::new();
+		for (pallet, item) in [
+			(&b"EVM"[..], &b"AccountCodes"[..]),
+			(&b"Ethereum"[..], &b"StateTrieAccounts"[..]),
+		] {
+			let prefix = StorageKey(storage_prefix(pallet, item));
+			for key in client.storage_keys_iter(&id, Some(&prefix), None)? {
+				// Prefix, then `Blake2_128Concat` of the address.
+				addresses.insert(H160::from_slice(&key.0[48..68]));
+			}
+		}
+
+		// `keccak256(rlp(""))`, the storage root of accounts without storage.
+		let empty_trie_root = H256(keccak_256(&[0x80]));
+		for address in addresses {
+			let account = api.account_basic(&id, address).map_err(api_error)?;
+			let code: Vec<u8> =
+				read_storage(&*client, &id, b"EVM", b"AccountCodes", address)?.unwrap_or_default();
+			let root = read_storage(&*client, &id, b"Ethereum", b"StateTrieAccounts", address)?
+				.unwrap_or(empty_trie_root);
+
+			let mut storage = BTreeMap::new();
+			let prefix = StorageKey(
+				[
+					storage_prefix(b"EVM", b"AccountStorages"),
+					blake2_128_concat(address.as_bytes()),
+				]
+				.concat(),
+			);
+			for key in client.storage_keys_iter(&id, Some(&prefix), None)? {
+				if let Some(value) = client.storage(&id, &key)? {
+					let value = H256::decode(&mut &value.0[..]).map_err(|e| e.to_string())?;
+					// Geth writes the RLP content of the slot: trimmed and without `0x`.
+					let trimmed: String = value
+						.as_bytes()
+						.iter()
+						.skip_while(|b| **b == 0)
+						.map(|b| format!("{:02x}", b))
+						.collect();
+					storage.insert(H256::from_slice(&key.0[84..116]), trimmed);
+				}
+			}
+
+			let account = DumpAccount {
+				balance: account.balance.to_string(),
+				nonce: account.nonce.low_u64(),
+				root,
+				code_hash: H256(keccak_256(&code)),
+				code: (!code.is_empty()).then(|| Bytes(code)),
+				storage,
+				address,
+				key: H256(keccak_256(address.as_bytes())),
+			};
+			writeln!(
+				out,
+				"{}",
+				serde_json::to_string(&account).map_err(|e| e.to_string())?
+			)?;
+		}
+		out.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportEvmStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Build a chain specification with EVM accounts imported from a Geth state dump.
+#[derive(Debug, clap::Parser)]
+pub struct ImportEvmStateCmd {
+	/// Path to a `debug_dumpBlock` JSON object or a `dump --iterative` JSON stream.
+	#[clap(long, value_name = "PATH", parse(from_os_str))]
+	pub dump: PathBuf,
+
+	/// Force raw genesis storage output.
+	#[clap(long)]
+	pub raw: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ImportEvmStateCmd {
+	/// Add the dumped accounts to the EVM genesis accounts of `spec` and print the result.
+	pub fn run(&self, spec: Box<dyn sc_service::ChainSpec>) -> sc_cli::Result<()> {
+		let reader = BufReader::new(File::open(&self.dump)?);
+		let mut accounts = BTreeMap::new();
+
+		// A `debug_dumpBlock` result is one object, an iterative dump has one object per line.
+		for value in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+			let mut value = value.map_err(|e| format!("Invalid state dump: {}", e))?;
+			if let Some(Value::Object(dumped)) = value.get_mut("accounts").map(Value::take) {
+				for (address, account) in dumped {
+					import_account(&mut accounts, &address, account)?;
+				}
+			} else if let Some(address) = value
+				.get("address")
+				.and_then(Value::as_str)
+				.map(str::to_owned)
+			{
+				import_account(&mut accounts, &address, value)?;
+			} else if value.get("key").is_some() {
+				log::warn!("Skipping dumped account without address: {}", value);
+			}
+		}
+
+		print_spec_with_accounts(spec, accounts, self.raw)
+	}
+}
+
+impl CliConfiguration for ImportEvmStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+fn import_account(
+	accounts: &mut BTreeMap<H160, GenesisAccount>,
+	address: &str,
+	account: Value,
+) -> Result<(), String> {
+	let address = parse_h160(address)?;
+	let account = serde_json::from_value::<GethAccount>(account)
+		.map_err(|e| e.to_string())
+		.and_then(GethAccount::into_genesis_account)
+		.map_err(|e| format!("Account {:?}: {}", address, e))?;
+	accounts.insert(address, account);
+	Ok(())
+}
+
+fn read_storage<B: BlockT, C: StorageProvider<B, BE>, BE: Backend<B>, T: Decode>(
+	client: &C,
+	id: &BlockId<B>,
+	pallet: &[u8],
+	item: &[u8],
+	address: H160,
+) -> sc_cli::Result<Option<T>> {
+	let key = StorageKey(
+		[
+			storage_prefix(pallet, item),
+			blake2_128_concat(address.as_bytes()),
+		]
+		.concat(),
+	);
+	match client.storage(id, &key)? {
+		Some(data) => Ok(Some(
+			T::decode(&mut &data.0[..]).map_err(|e| e.to_string())?,
+		)),
+		None => Ok(None),
+	}
+}
+
+fn storage_prefix(pallet: &[u8], item: &[u8]) -> Vec<u8> {
+	[twox_128(pallet), twox_128(item)].concat()
+}
+
+fn blake2_128_concat(data: &[u8]) -> Vec<u8> {
+	[&blake2_128(data)[..], data].concat()
+}
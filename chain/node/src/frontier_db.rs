@@ -0,0 +1,564 @@
+//! Maintenance of the Frontier mapping database.
+
+use std::sync::Arc;
+
+use fp_consensus::FindLogError;
+use sc_cli::{CliConfiguration, SharedParams};
+use sc_service::Configuration;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+
+use crate::service::frontier_database_dir;
+
+/// Copy the RocksDB Frontier mapping database into a new ParityDb one.
+#[derive(Debug, clap::Parser)]
+pub struct MigrateFrontierDbCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl MigrateFrontierDbCmd {
+	/// Run the migration for the chain of `config`.
+	pub fn run(&self, config: &Configuration) -> sc_cli::Result<()> {
+		let from = frontier_database_dir(config, "db");
+		let to = frontier_database_dir(config, "paritydb");
+
+		let copied = fc_db::migrate_rocksdb_to_paritydb(&from, &to)?;
+		println!(
+			"Copied {} entries from {} to {}. Start the node with `--database paritydb` to use it.",
+			copied,
+			from.display(),
+			to.display()
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for MigrateFrontierDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Inspect and repair the Frontier mapping database.
+#[derive(Debug, clap::Parser)]
+pub struct FrontierDbCmd {
+	/// Operation to run.
+	#[clap(subcommand)]
+	pub operation: FrontierDbOperation,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// Operations of the `frontier-db` subcommand.
+#[derive(Debug, clap::Subcommand)]
+pub enum FrontierDbOperation {
+	/// Show the current syncing tips and the cached Ethereum storage schemas.
+	Meta,
+	/// Show the entries stored for a key.
+	Read {
+		/// Kind of key.
+		#[clap(arg_enum)]
+		kind: MappingKey,
+		/// The key, as a hex-encoded hash.
+		key: H256,
+	},
+	/// List every entry of a kind.
+	List {
+		/// Kind of key.
+		#[clap(arg_enum)]
+		kind: MappingKey,
+	},
+	/// Delete the entries stored for a key.
+	Delete {
+		/// Kind of key.
+		#[clap(arg_enum)]
+		kind: MappingKey,
+		/// The key, as a hex-encoded hash.
+		key: H256,
+	},
+	/// Re-derive the mappings of canonical blocks from their post-logs and compare them with
+	/// the stored ones.
+	Verify {
+		/// First block number to check.
+		#[clap(long, default_value = "0")]
+		from: u32,
+		/// Last block number to check. Defaults to the best block.
+		#[clap(long)]
+		to: Option<u32>,
+		/// Rewrite the mappings of mismatched blocks instead of failing.
+		#[clap(long)]
+		fix: bool,
+	},
+}
+
+/// The kinds of key of the mapping database.
+#[derive(Debug, Copy, Clone, clap::ArgEnum)]
+pub enum MappingKey {
+	/// Ethereum block hash, mapped to a Substrate block hash.
+	EthereumBlock,
+	/// Substrate block hash, with its synced flag.
+	SubstrateBlock,
+	/// Ethereum transaction hash, mapped to the blocks including it.
+	Transaction,
+}
+
+impl FrontierDbCmd {
+	/// Run the operation against `client` and its Frontier `backend`.
+	pub fn run<B, C>(&self, client: Arc<C>, backend: Arc<fc_db::Backend<B>>) -> sc_cli::Result<()>
+	where
+		B: BlockT<Hash = H256>,
+		C: HeaderBackend<B>,
+	{
+		match &self.operation {
+			FrontierDbOperation::Meta => {
+				println!("Current syncing tips:");
+				for tip in backend.meta().current_syncing_tips()? {
+					println!("  {:?}", tip);
+				}
+				match backend.meta().ethereum_schema()? {
+					Some(cache) => {
+						println!("Ethereum schema cache:");
+						for (schema, block_hash) in cache {
+							println!("  {:?} since {:?}", schema, block_hash);
+						}
+					}
+					None => println!("No Ethereum schema cache"),
+				}
+			}
+			FrontierDbOperation::Read { kind, key } => read(&backend, *kind, key)?,
+			FrontierDbOperation::List { kind } => match kind {
+				MappingKey::EthereumBlock => {
+					for (ethereum_block_hash, block_hash) in backend.mapping().block_mappings()? {
+						println!("{:?} => {:?}", ethereum_block_hash, block_hash);
+					}
+				}
+				MappingKey::SubstrateBlock => {
+					for block_hash in backend.mapping().synced_blocks()? {
+						println!("{:?} synced", block_hash);
+					}
+				}
+				MappingKey::Transaction => {
+					for (transaction_hash, metadata) in backend.mapping().transaction_mappings()? {
+						print_transaction_metadata(&transaction_hash, &metadata);
+					}
+				}
+			},
+			FrontierDbOperation::Delete { kind, key } => {
+				match kind {
+					MappingKey::EthereumBlock => backend.mapping().remove_block_hash(key)?,
+					MappingKey::SubstrateBlock => backend.mapping().remove_synced(key)?,
+					MappingKey::Transaction => {
+						backend.mapping().remove_transaction_metadata(key)?
+					}
+				}
+				println!("Deleted {:?}", key);
+			}
+			FrontierDbOperation::Verify { from, to, fix } => {
+				verify(&client, &backend, *from, *to, *fix)?
+			}
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for FrontierDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+fn read<B: BlockT<Hash = H256>>(
+	backend: &fc_db::Backend<B>,
+	kind: MappingKey,
+	key: &H256,
+) -> Result<(), String> {
+	match kind {
+		MappingKey::EthereumBlock => match backend.mapping().block_hash(key)? {
+			Some(block_hash) => println!("{:?} => {:?}", key, block_hash),
+			None => println!("{:?} is not mapped", key),
+		},
+		MappingKey::SubstrateBlock => {
+			println!("{:?} synced: {}", key, backend.mapping().is_synced(key)?)
+		}
+		MappingKey::Transaction => {
+			print_transaction_metadata(key, &backend.mapping().transaction_metadata(key)?)
+		}
+	}
+	Ok(())
+}
+
+fn print_transaction_metadata<B: BlockT>(
+	transaction_hash: &H256,
+	metadata: &[fc_db::TransactionMetadata<B>],
+) {
+	if metadata.is_empty() {
+		println!("{:?} is not mapped", transaction_hash);
+	}
+	for m in metadata {
+		println!(
+			"{:?} => index {} of Ethereum block {:?} in {:?}",
+			transaction_hash, m.ethereum_index, m.ethereum_block_hash, m.block_hash
+		);
+	}
+}
+
+/// Compare the stored mappings of the canonical blocks `from..=to` with those derived from their
+/// post-logs, the same way the mapping sync worker writes them.
+fn verify<B, C>(
+	client: &C,
+	backend: &fc_db::Backend<B>,
+	from: u32,
+	to: Option<u32>,
+	fix: bool,
+) -> Result<(), String>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B>,
+{
+	let to = match to {
+		Some(to) => to,
+		None => client
+			.info()
+			.best_number
+			.try_into()
+			.map_err(|_| "Best block number does not fit into u32".to_string())?,
+	};
+
+	let mut mismatches = 0u32;
+	for number in from..=to {
+		let block_hash = client
+			.hash(number.into())
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("No canonical block #{}", number))?;
+		let header = client
+			.header(BlockId::Hash(block_hash))
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Missing header of block #{}", number))?;
+
+		let problems = match fp_consensus::find_log(header.digest()) {
+			Ok(log) => {
+				let hashes = log.into_hashes();
+				let mut problems = Vec::new();
+				let mapped = backend.mapping().block_hash(&hashes.block_hash)?;
+				if mapped != Some(block_hash) {
+					problems.push(format!(
+						"Ethereum block {:?} maps to {:?}",
+						hashes.block_hash, mapped
+					));
+				}
+				for (index, transaction_hash) in hashes.transaction_hashes.iter().enumerate() {
+					let found = backend
+						.mapping()
+						.transaction_metadata(transaction_hash)?
+						.iter()
+						.any(|m| {
+							m.block_hash == block_hash
+								&& m.ethereum_block_hash == hashes.block_hash
+								&& m.ethereum_index == index as u32
+						});
+					if !found {
+						problems.push(format!(
+							"transaction {:?} is not mapped to index {}",
+							transaction_hash, index
+						));
+					}
+				}
+				if !backend.mapping().is_synced(&block_hash)? {
+					problems.push("block is not marked as synced".to_string());
+				}
+				if fix && !problems.is_empty() {
+					backend.mapping().write_hashes(fc_db::MappingCommitment {
+						block_hash,
+						ethereum_block_hash: hashes.block_hash,
+						ethereum_transaction_hashes: hashes.transaction_hashes,
+					})?;
+				}
+				problems
+			}
+			Err(FindLogError::NotFound) => {
+				if backend.mapping().is_synced(&block_hash)? {
+					Vec::new()
+				} else {
+					if fix {
+						backend.mapping().write_none(block_hash)?;
+					}
+					vec!["block is not marked as synced".to_string()]
+				}
+			}
+			Err(FindLogError::MultipleLogs) => vec!["multiple Frontier logs found".to_string()],
+		};
+
+		if !problems.is_empty() {
+			mismatches += 1;
+			println!("#{} {:?}: {}", number, block_hash, problems.join(", "));
+		}
+	}
+
+	match (mismatches, fix) {
+		(0, _) => println!("Mappings of blocks #{}..=#{} are consistent", from, to),
+		(_, true) => println!("Rewrote the mappings of {} blocks", mismatches),
+		(_, false) => {
+			return Err(format!(
+				"{} blocks have mismatched mappings, run again with --fix to rewrite them",
+				mismatches
+			))
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use clap::Parser;
+	use codec::Encode;
+	use frontier_template_runtime::opaque::{Block, Header};
+	use sp_blockchain::{BlockStatus, Info};
+	use sp_runtime::{traits::NumberFor, Digest, DigestItem};
+
+	/// A canonical chain of headers, without forks.
+	struct TestChain(Vec<Header>);
+
+	impl TestChain {
+		/// Genesis, then one block per entry of `logs`, with a post-log if it is `Some`.
+		fn new(logs: Vec<Option<fp_consensus::Hashes>>) -> Self {
+			let mut headers = vec![Header::new(
+				0,
+				Default::default(),
+				Default::default(),
+				Default::default(),
+				Default::default(),
+			)];
+			for log in logs {
+				let mut digest = Digest::default();
+				if let Some(hashes) = log {
+					digest.push(DigestItem::Consensus(
+						fp_consensus::FRONTIER_ENGINE_ID,
+						fp_consensus::PostLog::Hashes(hashes).encode(),
+					));
+				}
+				let parent = headers.last().expect("genesis is pushed first; qed");
+				let header = Header::new(
+					parent.number + 1,
+					Default::default(),
+					Default::default(),
+					parent.hash(),
+					digest,
+				);
+				headers.push(header);
+			}
+			Self(headers)
+		}
+	}
+
+	impl HeaderBackend<Block> for TestChain {
+		fn header(&self, id: BlockId<Block>) -> sp_blockchain::Result<Option<Header>> {
+			Ok(match id {
+				BlockId::Hash(hash) => self.0.iter().find(|h| h.hash() == hash).cloned(),
+				BlockId::Number(number) => self.0.get(number as usize).cloned(),
+			})
+		}
+
+		fn info(&self) -> Info<Block> {
+			let best = self.0.last().expect("genesis is pushed first; qed");
+			Info {
+				best_hash: best.hash(),
+				best_number: best.number,
+				genesis_hash: self.0[0].hash(),
+				finalized_hash: best.hash(),
+				finalized_number: best.number,
+				finalized_state: None,
+				number_leaves: 1,
+				block_gap: None,
+			}
+		}
+
+		fn status(&self, id: BlockId<Block>) -> sp_blockchain::Result<BlockStatus> {
+			Ok(match self.header(id)? {
+				Some(_) => BlockStatus::InChain,
+				None => BlockStatus::Unknown,
+			})
+		}
+
+		fn number(&self, hash: H256) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+			Ok(self.0.iter().find(|h| h.hash() == hash).map(|h| h.number))
+		}
+
+		fn hash(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Option<H256>> {
+			Ok(self.0.get(number as usize).map(|h| h.hash()))
+		}
+	}
+
+	fn open_backend(path: &std::path::Path) -> Arc<fc_db::Backend<Block>> {
+		Arc::new(
+			fc_db::Backend::new(&fc_db::DatabaseSettings {
+				source: fc_db::DatabaseSettingsSrc::ParityDb {
+					path: path.to_path_buf(),
+				},
+				transaction_index: false,
+			})
+			.expect("open Frontier database"),
+		)
+	}
+
+	fn hashes(seed: u64, transactions: u64) -> fp_consensus::Hashes {
+		fp_consensus::Hashes {
+			block_hash: H256::from_low_u64_be(seed),
+			transaction_hashes: (0..transactions)
+				.map(|i| H256::from_low_u64_be(seed * 100 + i))
+				.collect(),
+		}
+	}
+
+	fn run(
+		client: &Arc<TestChain>,
+		backend: &Arc<fc_db::Backend<Block>>,
+		args: &[&str],
+	) -> sc_cli::Result<()> {
+		FrontierDbCmd::parse_from(std::iter::once("frontier-db").chain(args.iter().copied()))
+			.run(client.clone(), backend.clone())
+	}
+
+	#[test]
+	fn verify_detects_and_fixes_missing_mappings() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path());
+		let client = Arc::new(TestChain::new(vec![
+			Some(hashes(1, 2)),
+			None,
+			Some(hashes(3, 1)),
+		]));
+
+		// Only block #1 was synced.
+		let block_1 = client.0[1].hash();
+		let hashes_1 = hashes(1, 2);
+		backend
+			.mapping()
+			.write_hashes(fc_db::MappingCommitment {
+				block_hash: block_1,
+				ethereum_block_hash: hashes_1.block_hash,
+				ethereum_transaction_hashes: hashes_1.transaction_hashes,
+			})
+			.unwrap();
+
+		assert!(run(&client, &backend, &["verify", "--from", "1", "--to", "1"]).is_ok());
+		assert!(run(&client, &backend, &["verify", "--from", "1"]).is_err());
+
+		run(&client, &backend, &["verify", "--from", "1", "--fix"]).unwrap();
+		assert!(run(&client, &backend, &["verify", "--from", "1"]).is_ok());
+
+		let block_2 = client.0[2].hash();
+		let block_3 = client.0[3].hash();
+		assert!(backend.mapping().is_synced(&block_2).unwrap());
+		assert_eq!(
+			backend
+				.mapping()
+				.block_hash(&hashes(3, 1).block_hash)
+				.unwrap(),
+			Some(block_3)
+		);
+		let metadata = backend
+			.mapping()
+			.transaction_metadata(&hashes(3, 1).transaction_hashes[0])
+			.unwrap();
+		assert_eq!(metadata.len(), 1);
+		assert_eq!(metadata[0].block_hash, block_3);
+		assert_eq!(metadata[0].ethereum_index, 0);
+	}
+
+	#[test]
+	fn verify_detects_a_block_mapped_elsewhere() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path());
+		let client = Arc::new(TestChain::new(vec![Some(hashes(1, 0))]));
+
+		backend
+			.mapping()
+			.write_hashes(fc_db::MappingCommitment {
+				block_hash: H256::repeat_byte(0xaa),
+				ethereum_block_hash: hashes(1, 0).block_hash,
+				ethereum_transaction_hashes: Vec::new(),
+			})
+			.unwrap();
+		backend.mapping().write_none(client.0[1].hash()).unwrap();
+
+		assert!(run(&client, &backend, &["verify", "--from", "1"]).is_err());
+		run(&client, &backend, &["verify", "--from", "1", "--fix"]).unwrap();
+		assert_eq!(
+			backend
+				.mapping()
+				.block_hash(&hashes(1, 0).block_hash)
+				.unwrap(),
+			Some(client.0[1].hash())
+		);
+	}
+
+	#[test]
+	fn delete_removes_only_the_given_key() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path());
+		let client = Arc::new(TestChain::new(Vec::new()));
+
+		let block_hash = H256::repeat_byte(1);
+		let hashes = hashes(1, 2);
+		backend
+			.mapping()
+			.write_hashes(fc_db::MappingCommitment {
+				block_hash,
+				ethereum_block_hash: hashes.block_hash,
+				ethereum_transaction_hashes: hashes.transaction_hashes.clone(),
+			})
+			.unwrap();
+
+		let transaction = format!("{:x}", hashes.transaction_hashes[0]);
+		run(&client, &backend, &["delete", "transaction", &transaction]).unwrap();
+		assert!(backend
+			.mapping()
+			.transaction_metadata(&hashes.transaction_hashes[0])
+			.unwrap()
+			.is_empty());
+		assert_eq!(
+			backend
+				.mapping()
+				.transaction_metadata(&hashes.transaction_hashes[1])
+				.unwrap()
+				.len(),
+			1
+		);
+		assert_eq!(
+			backend.mapping().block_hash(&hashes.block_hash).unwrap(),
+			Some(block_hash)
+		);
+
+		let ethereum_block = format!("{:x}", hashes.block_hash);
+		run(
+			&client,
+			&backend,
+			&["delete", "ethereum-block", &ethereum_block],
+		)
+		.unwrap();
+		assert_eq!(
+			backend.mapping().block_hash(&hashes.block_hash).unwrap(),
+			None
+		);
+		assert!(backend.mapping().is_synced(&block_hash).unwrap());
+
+		let substrate_block = format!("{:x}", block_hash);
+		run(
+			&client,
+			&backend,
+			&["delete", "substrate-block", &substrate_block],
+		)
+		.unwrap();
+		assert!(!backend.mapping().is_synced(&block_hash).unwrap());
+	}
+}
@@ -0,0 +1,316 @@
+//! Import of the `alloc` section of a Geth `genesis.json` into a chain specification.
+
+use std::{collections::BTreeMap, fs, io::Write, path::PathBuf};
+
+use fp_evm::GenesisAccount;
+use frontier_template_runtime::{BlockGasLimit, ChainId, Index};
+use sc_cli::{CliConfiguration, SharedParams};
+use serde::Deserialize;
+use sp_core::{H160, H256, U256};
+
+use crate::chain_spec::ChainSpec;
+
+/// Build a chain specification with EVM accounts imported from a Geth genesis file.
+#[derive(Debug, clap::Parser)]
+pub struct ImportGethGenesisCmd {
+	/// Path to the Geth `genesis.json`.
+	#[clap(long, value_name = "PATH", parse(from_os_str))]
+	pub geth_genesis: PathBuf,
+
+	/// Force raw genesis storage output.
+	#[clap(long)]
+	pub raw: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ImportGethGenesisCmd {
+	/// Add the Geth accounts to the EVM genesis accounts of `spec` and print the result.
+	pub fn run(&self, spec: Box<dyn sc_service::ChainSpec>) -> sc_cli::Result<()> {
+		let content = fs::read(&self.geth_genesis)?;
+		let genesis: GethGenesis = serde_json::from_slice(&content)
+			.map_err(|e| format!("Invalid Geth genesis file: {}", e))?;
+		let accounts = genesis.into_accounts()?;
+
+		print_spec_with_accounts(spec, accounts, self.raw)
+	}
+}
+
+impl CliConfiguration for ImportGethGenesisCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Add `accounts` to the EVM genesis accounts of `spec` and print the resulting chain spec.
+pub(crate) fn print_spec_with_accounts(
+	spec: Box<dyn sc_service::ChainSpec>,
+	accounts: BTreeMap<H160, GenesisAccount>,
+	raw: bool,
+) -> sc_cli::Result<()> {
+	let mut json: serde_json::Value =
+		serde_json::from_str(&spec.as_json(false)?).map_err(|e| e.to_string())?;
+	let evm_accounts = json
+		.pointer_mut("/genesis/runtime/evm/accounts")
+		.and_then(serde_json::Value::as_object_mut)
+		.ok_or("The chain spec has no EVM genesis accounts, use a non-raw chain spec")?;
+	for (address, account) in accounts {
+		let account = serde_json::to_value(account).map_err(|e| e.to_string())?;
+		if evm_accounts
+			.insert(format!("{:?}", address), account)
+			.is_some()
+		{
+			log::warn!("Overriding EVM genesis account {:?}", address);
+		}
+	}
+
+	let spec = ChainSpec::from_json_bytes(serde_json::to_vec(&json).map_err(|e| e.to_string())?)?;
+	let output = sc_service::ChainSpec::as_json(&spec, raw)?;
+	std::io::stdout().write_all(output.as_bytes())?;
+	Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GethGenesis {
+	config: GethChainConfig,
+	gas_limit: Quantity,
+	#[serde(default)]
+	alloc: BTreeMap<String, GethAccount>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GethChainConfig {
+	chain_id: u64,
+}
+
+/// Account of a Geth genesis `alloc` or state dump. Other fields, such as the code hash and
+/// storage root of dumps, are ignored.
+#[derive(Deserialize)]
+pub(crate) struct GethAccount {
+	balance: Quantity,
+	#[serde(default)]
+	nonce: Option<Quantity>,
+	#[serde(default)]
+	code: Option<String>,
+	#[serde(default)]
+	storage: BTreeMap<String, String>,
+}
+
+/// Number written either as a JSON number or as a hex or decimal string, like Geth accepts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Quantity {
+	Number(u64),
+	Text(String),
+}
+
+impl Quantity {
+	pub(crate) fn parse(&self) -> Result<U256, String> {
+		match self {
+			Quantity::Number(n) => Ok(U256::from(*n)),
+			Quantity::Text(s) => parse_u256(s),
+		}
+	}
+}
+
+impl GethGenesis {
+	/// Validate the genesis against the runtime and convert its accounts.
+	fn into_accounts(self) -> Result<BTreeMap<H160, GenesisAccount>, String> {
+		if self.config.chain_id != ChainId::get() {
+			return Err(format!(
+				"Geth genesis chainId {} does not match the runtime chain id {}",
+				self.config.chain_id,
+				ChainId::get()
+			));
+		}
+
+		let gas_limit = self.gas_limit.parse()?;
+		if gas_limit > BlockGasLimit::get() {
+			return Err(format!(
+				"Geth genesis gasLimit {} exceeds the runtime block gas limit {}",
+				gas_limit,
+				BlockGasLimit::get()
+			));
+		}
+
+		self.alloc
+			.into_iter()
+			.map(|(address, account)| {
+				let address = parse_h160(&address)?;
+				let account = account
+					.into_genesis_account()
+					.map_err(|e| format!("Account {:?}: {}", address, e))?;
+				Ok((address, account))
+			})
+			.collect()
+	}
+}
+
+impl GethAccount {
+	pub(crate) fn into_genesis_account(self) -> Result<GenesisAccount, String> {
+		// `pallet_evm::GenesisConfig::build` only keeps the low 128 bits of the balance.
+		let balance = self.balance.parse()?;
+		if balance > U256::from(u128::MAX) {
+			return Err(format!(
+				"balance {} does not fit in the runtime balance type",
+				balance
+			));
+		}
+		let nonce = self
+			.nonce
+			.map(|n| n.parse())
+			.transpose()?
+			.unwrap_or_default();
+		if nonce > U256::from(Index::MAX) {
+			return Err(format!(
+				"nonce {} does not fit in the runtime nonce type",
+				nonce
+			));
+		}
+
+		let code = match self.code {
+			Some(code) => {
+				sp_core::bytes::from_hex(&code).map_err(|e| format!("invalid code: {:?}", e))?
+			}
+			None => Vec::new(),
+		};
+		let storage = self
+			.storage
+			.iter()
+			.map(|(key, value)| Ok((parse_h256(key)?, parse_h256(value)?)))
+			.collect::<Result<_, String>>()?;
+
+		Ok(GenesisAccount {
+			nonce,
+			balance,
+			storage,
+			code,
+		})
+	}
+}
+
+pub(crate) fn parse_h160(s: &str) -> Result<H160, String> {
+	match sp_core::bytes::from_hex(s) {
+		Ok(bytes) if bytes.len() == 20 => Ok(H160::from_slice(&bytes)),
+		_ => Err(format!("invalid address {}", s)),
+	}
+}
+
+/// Hex quantities are `0x`-prefixed, anything else is decimal.
+fn parse_u256(s: &str) -> Result<U256, String> {
+	let value = match s.strip_prefix("0x") {
+		Some(hex) => U256::from_str_radix(hex, 16).ok(),
+		None => U256::from_dec_str(s).ok(),
+	};
+	value.ok_or_else(|| format!("invalid number {}", s))
+}
+
+/// Storage keys and values, left-padded to 32 bytes.
+fn parse_h256(s: &str) -> Result<H256, String> {
+	let value = U256::from_str_radix(s.trim_start_matches("0x"), 16)
+		.map_err(|_| format!("invalid storage word {}", s))?;
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	Ok(H256::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn genesis_account(json: &str) -> Result<GenesisAccount, String> {
+		serde_json::from_str::<GethAccount>(json)
+			.map_err(|e| e.to_string())?
+			.into_genesis_account()
+	}
+
+	#[test]
+	fn quantities_are_numbers_hex_or_decimal() {
+		assert_eq!(Quantity::Number(42).parse(), Ok(U256::from(42)));
+		assert_eq!(Quantity::Text("0x2a".into()).parse(), Ok(U256::from(42)));
+		assert_eq!(Quantity::Text("42".into()).parse(), Ok(U256::from(42)));
+		assert!(Quantity::Text("2a".into()).parse().is_err());
+		assert!(Quantity::Text("0xzz".into()).parse().is_err());
+	}
+
+	#[test]
+	fn addresses_are_20_bytes() {
+		let mut bytes = [0u8; 20];
+		bytes[0] = 0x10;
+		bytes[19] = 0x01;
+		assert_eq!(
+			parse_h160("0x1000000000000000000000000000000000000001"),
+			Ok(H160::from(bytes))
+		);
+		assert_eq!(
+			parse_h160("1000000000000000000000000000000000000001"),
+			parse_h160("0x1000000000000000000000000000000000000001")
+		);
+		assert!(parse_h160("0x10").is_err());
+		assert!(parse_h160("0x100000000000000000000000000000000000000001").is_err());
+	}
+
+	#[test]
+	fn storage_words_are_left_padded() {
+		assert_eq!(parse_h256("0x01"), Ok(H256::from_low_u64_be(1)));
+		assert_eq!(parse_h256("1"), Ok(H256::from_low_u64_be(1)));
+		assert_eq!(
+			parse_h256(&format!("0x{}", "ff".repeat(32))),
+			Ok(H256::repeat_byte(0xff))
+		);
+		assert!(parse_h256(&format!("0x{}", "ff".repeat(33))).is_err());
+		assert!(parse_h256("0xzz").is_err());
+	}
+
+	#[test]
+	fn alloc_accounts_are_converted() {
+		let account = genesis_account(
+			r#"{
+				"balance": "0x10",
+				"nonce": 3,
+				"code": "0x6000",
+				"storage": { "0x01": "0x02" }
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(account.balance, U256::from(16));
+		assert_eq!(account.nonce, U256::from(3));
+		assert_eq!(account.code, vec![0x60, 0x00]);
+		assert_eq!(
+			account.storage,
+			BTreeMap::from([(H256::from_low_u64_be(1), H256::from_low_u64_be(2))])
+		);
+
+		let account = genesis_account(r#"{ "balance": "1000" }"#).unwrap();
+		assert_eq!(account.balance, U256::from(1000));
+		assert_eq!(account.nonce, U256::zero());
+		assert!(account.code.is_empty());
+		assert!(account.storage.is_empty());
+	}
+
+	#[test]
+	fn alloc_accounts_out_of_range_are_rejected() {
+		let nonce = U256::from(Index::MAX);
+		assert_eq!(
+			genesis_account(&format!(r#"{{ "balance": "0", "nonce": "{}" }}"#, nonce))
+				.unwrap()
+				.nonce,
+			nonce
+		);
+		assert!(genesis_account(&format!(
+			r#"{{ "balance": "0", "nonce": "{}" }}"#,
+			nonce + 1
+		))
+		.is_err());
+
+		let balance = U256::from(u128::MAX);
+		assert!(genesis_account(&format!(r#"{{ "balance": "{}" }}"#, balance)).is_ok());
+		assert!(genesis_account(&format!(r#"{{ "balance": "{}" }}"#, balance + 1)).is_err());
+
+		assert!(genesis_account(r#"{ "balance": "0", "code": "0xz" }"#).is_err());
+	}
+}
@@ -2,10 +2,16 @@
 #![warn(missing_docs)]
 
 mod chain_spec;
+mod check_state_root;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+#[cfg(feature = "manual-seal")]
+mod dev_rpc;
+mod evm_state;
+mod frontier_db;
+mod geth_genesis;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
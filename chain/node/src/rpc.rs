@@ -2,9 +2,11 @@
 
 use std::{collections::BTreeMap, sync::Arc};
 
+use fc_mapping_sync::BackfillProgress;
 use fc_rpc::{
-	EthBlockDataCache, OverrideHandle, RuntimeApiStorageOverride, SchemaV1Override,
-	SchemaV2Override, SchemaV3Override, StorageOverride,
+	EthBlockDataCache, EthPendingBlockCache, OverrideHandle, RpcGuard, RpcMetrics,
+	RuntimeApiStorageOverride, SchemaV1Override, SchemaV2Override, SchemaV3Override,
+	StorageOverride, SyncProgress,
 };
 use fc_rpc_core::types::{FeeHistoryCache, FilterPool};
 use fp_storage::EthereumStorageSchema;
@@ -58,6 +60,21 @@ pub struct FullDeps<C, P, A: ChainApi> {
 	pub overrides: Arc<OverrideHandle<Block>>,
 	/// Cache for Ethereum block data.
 	pub block_data_cache: Arc<EthBlockDataCache<Block>>,
+	/// Cache for the pending block.
+	pub pending_block: Arc<EthPendingBlockCache<Block>>,
+	/// Progress of the mapping backfill.
+	pub backfill_progress: Arc<BackfillProgress>,
+	/// Progress of the major sync.
+	pub sync_progress: SyncProgress,
+	/// Maximum gas of calls and estimations.
+	pub gas_cap: Option<u64>,
+	/// Rate limits and timeouts the RPC methods are wrapped in.
+	pub rpc_guard: Option<Arc<RpcGuard>>,
+	/// Ethereum RPC metrics.
+	pub metrics: Option<RpcMetrics>,
+	/// Dev RPC state, set when blocks are sealed manually.
+	#[cfg(feature = "manual-seal")]
+	pub dev_state: Option<Arc<crate::dev_rpc::DevState>>,
 }
 
 pub fn overrides_handle<C, BE>(client: Arc<C>) -> Arc<OverrideHandle<Block>>
@@ -114,10 +131,12 @@ where
 	P: TransactionPool<Block = Block> + 'static,
 	A: ChainApi<Block = Block> + 'static,
 {
+	#[cfg(feature = "manual-seal")]
+	use crate::dev_rpc::DevApi;
 	use fc_rpc::{
-		EthApi, EthApiServer, EthDevSigner, EthFilterApi, EthFilterApiServer, EthPubSubApi,
-		EthPubSubApiServer, EthSigner, HexEncodedIdProvider, NetApi, NetApiServer, Web3Api,
-		Web3ApiServer,
+		EthApi, EthApiServer, EthDevSigner, EthFilterApi, EthFilterApiServer, EthImpersonator,
+		EthPubSubApi, EthPubSubApiServer, EthSigner, HexEncodedIdProvider, NetApi, NetApiServer,
+		OtsApi, OtsApiServer, TxPool, TxPoolApiServer, Web3Api, Web3ApiServer,
 	};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
@@ -139,6 +158,14 @@ where
 		enable_dev_signer,
 		overrides,
 		block_data_cache,
+		pending_block,
+		backfill_progress,
+		sync_progress,
+		gas_cap,
+		rpc_guard,
+		metrics,
+		#[cfg(feature = "manual-seal")]
+		dev_state,
 	} = deps;
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(
@@ -155,25 +182,47 @@ where
 		signers.push(Box::new(EthDevSigner::new()) as Box<dyn EthSigner>);
 	}
 
+	#[cfg(feature = "manual-seal")]
+	let dev = match (dev_state, &command_sink) {
+		(Some(state), Some(command_sink)) => Some(crate::dev_rpc::Dev::new(
+			client.clone(),
+			pool.clone(),
+			command_sink.clone(),
+			state,
+		)),
+		_ => None,
+	};
+	#[cfg(feature = "manual-seal")]
+	let impersonator = dev
+		.clone()
+		.map(|dev| Arc::new(dev) as Arc<dyn EthImpersonator>);
+	#[cfg(feature = "aura")]
+	let impersonator: Option<Arc<dyn EthImpersonator>> = None;
+
 	io.extend_with(EthApiServer::to_delegate(EthApi::new(
 		client.clone(),
 		pool.clone(),
-		graph,
+		graph.clone(),
 		Some(frontier_template_runtime::TransactionConverter),
 		network.clone(),
 		signers,
+		impersonator,
 		overrides.clone(),
 		backend.clone(),
 		is_authority,
 		block_data_cache.clone(),
 		fee_history_limit,
 		fee_history_cache,
+		pending_block,
+		backfill_progress,
+		sync_progress.clone(),
+		gas_cap,
 	)));
 
 	if let Some(filter_pool) = filter_pool {
 		io.extend_with(EthFilterApiServer::to_delegate(EthFilterApi::new(
 			client.clone(),
-			backend,
+			backend.clone(),
 			filter_pool.clone(),
 			500 as usize, // max stored filters
 			max_past_logs,
@@ -190,15 +239,31 @@ where
 
 	io.extend_with(Web3ApiServer::to_delegate(Web3Api::new(client.clone())));
 
+	io.extend_with(TxPoolApiServer::to_delegate(TxPool::new(
+		client.clone(),
+		graph,
+	)));
+
+	// The Otterscan namespace is served when the transaction indices are maintained.
+	if backend.index().is_some() {
+		io.extend_with(OtsApiServer::to_delegate(OtsApi::new(
+			client.clone(),
+			backend,
+			overrides.clone(),
+			block_data_cache,
+		)));
+	}
+
 	io.extend_with(EthPubSubApiServer::to_delegate(EthPubSubApi::new(
 		pool.clone(),
 		client.clone(),
-		network.clone(),
 		SubscriptionManager::<HexEncodedIdProvider>::with_id_provider(
 			HexEncodedIdProvider::default(),
 			Arc::new(subscription_task_executor),
 		),
 		overrides,
+		sync_progress,
+		metrics.clone(),
 	)));
 
 	match command_sink {
@@ -212,5 +277,19 @@ where
 		_ => {}
 	}
 
-	io
+	#[cfg(feature = "manual-seal")]
+	if let Some(dev) = dev {
+		io.extend_with(DevApi::to_delegate(dev));
+	}
+
+	// The methods are wrapped rather than the handler given a middleware, since Substrate only
+	// keeps the methods when serving them on the standard RPC servers.
+	let io = match rpc_guard {
+		Some(rpc_guard) => rpc_guard.guard(io),
+		None => io,
+	};
+	match metrics {
+		Some(metrics) => metrics.instrument(io),
+		None => io,
+	}
 }
@@ -1,8 +1,11 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use fc_consensus::FrontierBlockImport;
-use fc_mapping_sync::{MappingSyncWorker, SyncStrategy};
-use fc_rpc::EthTask;
+use fc_consensus::{FrontierBlockImport, ImportMetrics};
+use fc_mapping_sync::{
+	BackfillMetrics, BackfillProgress, MappingBackfillWorker, MappingSyncMetrics,
+	MappingSyncWorker, SyncStrategy,
+};
+use fc_rpc::{EthTask, RpcMetrics};
 use fc_rpc_core::types::{FeeHistoryCache, FilterPool};
 use frontier_template_runtime::{self, opaque::Block, RuntimeApi, SLOT_DURATION};
 use futures::StreamExt;
@@ -15,15 +18,20 @@ pub use sc_executor::NativeElseWasmExecutor;
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
 use sc_network::warp_request_handler::WarpSyncProvider;
-use sc_service::{error::Error as ServiceError, BasePath, Configuration, TaskManager};
+use sc_service::{
+	error::Error as ServiceError, BasePath, Configuration, DatabaseSource, TaskManager,
+};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 use sp_core::U256;
-use sp_inherents::{InherentData, InherentIdentifier};
+use sp_inherents::{InherentData, InherentDataProvider, InherentIdentifier};
+use sp_runtime::{generic::Digest, traits::Block as BlockT};
 use std::{
-	cell::RefCell,
 	collections::BTreeMap,
-	sync::{Arc, Mutex},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
 	time::Duration,
 };
 
@@ -51,7 +59,7 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
-type FullClient =
+pub(crate) type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
@@ -73,12 +81,40 @@ pub type ConsensusResult = (
 );
 
 /// Provide a mock duration starting at 0 in millisecond for timestamp inherent.
-/// Each call will increment timestamp by slot_duration making Aura think time has passed.
+/// Each call will increment timestamp by slot_duration making Aura think time has passed,
+/// unless the next timestamp was scheduled through the dev RPC.
 pub struct MockTimestampInherentDataProvider;
 
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"timstap0";
 
-thread_local!(static TIMESTAMP: RefCell<u64> = RefCell::new(0));
+/// Timestamp of the last mocked block, in milliseconds.
+static TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+/// Timestamp scheduled for the next mocked block, in milliseconds. Zero if none.
+static NEXT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "manual-seal")]
+impl MockTimestampInherentDataProvider {
+	/// Timestamp of the last mocked block, in milliseconds.
+	pub fn current() -> u64 {
+		TIMESTAMP.load(Ordering::SeqCst)
+	}
+
+	/// Move the mocked clock forward.
+	pub fn advance(millis: u64) {
+		TIMESTAMP.fetch_add(millis, Ordering::SeqCst);
+	}
+
+	/// Use the given timestamp for the next mocked block.
+	pub fn set_next(millis: u64) {
+		NEXT_TIMESTAMP.store(millis, Ordering::SeqCst);
+	}
+
+	/// Rewind the mocked clock, dropping any scheduled timestamp.
+	pub fn reset(millis: u64) {
+		NEXT_TIMESTAMP.store(0, Ordering::SeqCst);
+		TIMESTAMP.store(millis, Ordering::SeqCst);
+	}
+}
 
 #[async_trait::async_trait]
 impl sp_inherents::InherentDataProvider for MockTimestampInherentDataProvider {
@@ -86,10 +122,14 @@ impl sp_inherents::InherentDataProvider for MockTimestampInherentDataProvider {
 		&self,
 		inherent_data: &mut InherentData,
 	) -> Result<(), sp_inherents::Error> {
-		TIMESTAMP.with(|x| {
-			*x.borrow_mut() += SLOT_DURATION;
-			inherent_data.put_data(INHERENT_IDENTIFIER, &*x.borrow())
-		})
+		let timestamp = match NEXT_TIMESTAMP.swap(0, Ordering::SeqCst) {
+			0 => TIMESTAMP.fetch_add(SLOT_DURATION, Ordering::SeqCst) + SLOT_DURATION,
+			next => {
+				TIMESTAMP.store(next, Ordering::SeqCst);
+				next
+			}
+		};
+		inherent_data.put_data(INHERENT_IDENTIFIER, &timestamp)
 	}
 
 	async fn try_handle_error(
@@ -102,7 +142,69 @@ impl sp_inherents::InherentDataProvider for MockTimestampInherentDataProvider {
 	}
 }
 
-pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
+/// Provides the inherents of the pending block served over RPC, mirroring the ones the
+/// authoring task would use.
+pub struct PendingBlockInherentsProvider {
+	target_gas_price: u64,
+	#[cfg(feature = "aura")]
+	slot_duration: sp_consensus_aura::SlotDuration,
+}
+
+impl fc_rpc::PendingBlockInherents<Block> for PendingBlockInherentsProvider {
+	fn inherents(
+		&self,
+		#[allow(unused_variables)] parent: &<Block as BlockT>::Header,
+	) -> Result<(InherentData, Digest), String> {
+		#[allow(unused_mut)]
+		let mut digest = Digest::default();
+		let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+		#[cfg(feature = "aura")]
+		let timestamp = {
+			use codec::{Decode, Encode};
+			use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+			use sp_runtime::{traits::Header as HeaderT, DigestItem};
+
+			// Aura requires the slot to increase, even if the parent was authored in the
+			// current slot.
+			let parent_slot = parent
+				.digest()
+				.logs()
+				.iter()
+				.find_map(|log| match log.as_pre_runtime() {
+					Some((id, mut data)) if id == AURA_ENGINE_ID => Slot::decode(&mut data).ok(),
+					_ => None,
+				})
+				.unwrap_or_default();
+			let slot = Slot::from_timestamp(*timestamp, self.slot_duration).max(parent_slot + 1);
+			digest.push(DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode()));
+
+			// The timestamp must match the claimed slot.
+			sp_timestamp::InherentDataProvider::new(sp_timestamp::Timestamp::new(
+				*slot * self.slot_duration.as_millis(),
+			))
+		};
+
+		let mut inherent_data = InherentData::new();
+		timestamp
+			.provide_inherent_data(&mut inherent_data)
+			.map_err(|err| format!("{:?}", err))?;
+		#[cfg(feature = "aura")]
+		sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+			*timestamp,
+			self.slot_duration,
+		)
+		.provide_inherent_data(&mut inherent_data)
+		.map_err(|err| format!("{:?}", err))?;
+		fp_dynamic_fee::InherentDataProvider(U256::from(self.target_gas_price))
+			.provide_inherent_data(&mut inherent_data)
+			.map_err(|err| format!("{:?}", err))?;
+
+		Ok((inherent_data, digest))
+	}
+}
+
+pub fn frontier_database_dir(config: &Configuration, path: &str) -> std::path::PathBuf {
 	let config_dir = config
 		.base_path
 		.as_ref()
@@ -111,16 +213,52 @@ pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
 			BasePath::from_project("", "", &crate::cli::Cli::executable_name())
 				.config_dir(config.chain_spec.id())
 		});
-	config_dir.join("frontier").join("db")
+	config_dir.join("frontier").join(path)
 }
 
-pub fn open_frontier_backend(config: &Configuration) -> Result<Arc<fc_db::Backend<Block>>, String> {
+/// The Frontier database matching the client's `--database` choice.
+pub fn frontier_database_source(config: &Configuration) -> DatabaseSource {
+	let rocksdb = || DatabaseSource::RocksDb {
+		path: frontier_database_dir(config, "db"),
+		cache_size: 0,
+	};
+	let paritydb = || DatabaseSource::ParityDb {
+		path: frontier_database_dir(config, "paritydb"),
+	};
+
+	match &config.database {
+		DatabaseSource::ParityDb { .. } => paritydb(),
+		// The client opens an existing RocksDB database and creates ParityDb otherwise.
+		DatabaseSource::Auto { rocksdb_path, .. } => {
+			if rocksdb_path.exists() {
+				rocksdb()
+			} else {
+				paritydb()
+			}
+		}
+		_ => rocksdb(),
+	}
+}
+
+pub fn open_frontier_backend(
+	config: &Configuration,
+	transaction_index: bool,
+) -> Result<Arc<fc_db::Backend<Block>>, String> {
+	let source = match frontier_database_source(config) {
+		DatabaseSource::ParityDb { path } => fc_db::DatabaseSettingsSrc::ParityDb { path },
+		source => fc_db::DatabaseSettingsSrc::RocksDb {
+			path: source
+				.path()
+				.expect("Frontier databases are on disk; qed")
+				.to_path_buf(),
+			cache_size: 0,
+		},
+	};
+
 	Ok(Arc::new(fc_db::Backend::<Block>::new(
 		&fc_db::DatabaseSettings {
-			source: fc_db::DatabaseSettingsSrc::RocksDb {
-				path: frontier_database_dir(&config),
-				cache_size: 0,
-			},
+			source,
+			transaction_index,
 		},
 	)?))
 }
@@ -197,14 +335,23 @@ pub fn new_partial(
 	let filter_pool: Option<FilterPool> = Some(Arc::new(Mutex::new(BTreeMap::new())));
 	let fee_history_cache: FeeHistoryCache = Arc::new(Mutex::new(BTreeMap::new()));
 
-	let frontier_backend = open_frontier_backend(config)?;
+	let frontier_backend = open_frontier_backend(config, cli.run.enable_ots)?;
+
+	let post_log_validation = cli.run.post_log_validation.into();
+	let import_metrics = match (post_log_validation, config.prometheus_registry()) {
+		(fc_consensus::PostLogValidation::Disabled, _) | (_, None) => None,
+		(_, Some(registry)) => Some(
+			ImportMetrics::register(registry).map_err(|e| ServiceError::Other(format!("{}", e)))?,
+		),
+	};
 
 	#[cfg(feature = "manual-seal")]
 	{
 		let sealing = cli.run.sealing;
 
 		let frontier_block_import =
-			FrontierBlockImport::new(client.clone(), client.clone(), frontier_backend.clone());
+			FrontierBlockImport::new(client.clone(), client.clone(), frontier_backend.clone())
+				.with_post_log_validation(post_log_validation, import_metrics);
 
 		let import_queue = sc_consensus_manual_seal::import_queue(
 			Box::new(frontier_block_import.clone()),
@@ -243,7 +390,8 @@ pub fn new_partial(
 			grandpa_block_import.clone(),
 			client.clone(),
 			frontier_backend.clone(),
-		);
+		)
+		.with_post_log_validation(post_log_validation, import_metrics);
 
 		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 		let target_gas_price = cli.run.target_gas_price;
@@ -370,7 +518,8 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 		})?;
 
 	// Channel for the rpc handler to communicate with the authorship task.
-	let (command_sink, _commands_stream) = futures::channel::mpsc::channel(1000);
+	#[allow(unused_variables)]
+	let (command_sink, commands_stream) = futures::channel::mpsc::channel(1000);
 
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
@@ -394,12 +543,188 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 	let overrides = crate::rpc::overrides_handle(client.clone());
 	let fee_history_limit = cli.run.fee_history_limit;
 
+	let rpc_metrics = prometheus_registry
+		.as_ref()
+		.map(RpcMetrics::register)
+		.transpose()
+		.map_err(|e| ServiceError::Other(format!("{}", e)))?;
+
 	let block_data_cache = Arc::new(fc_rpc::EthBlockDataCache::new(
 		task_manager.spawn_handle(),
 		overrides.clone(),
 		50,
 		50,
+		rpc_metrics.clone(),
+	));
+
+	let pending_block = Arc::new(fc_rpc::EthPendingBlockCache::new(Box::new(
+		PendingBlockInherentsProvider {
+			target_gas_price: cli.run.target_gas_price,
+			#[cfg(feature = "aura")]
+			slot_duration: sc_consensus_aura::slot_duration(&*client)?,
+		},
+	)));
+
+	// Hardhat-style dev RPC state, only served when blocks are sealed on demand.
+	#[cfg(feature = "manual-seal")]
+	let dev_state = match cli.run.sealing {
+		Sealing::Manual => Some(Arc::new(crate::dev_rpc::DevState::new(
+			client.clone(),
+			backend.clone(),
+			transaction_pool.clone(),
+			frontier_backend.clone(),
+			block_data_cache.clone(),
+			pending_block.clone(),
+		))),
+		Sealing::Instant => None,
+	};
+
+	let backfill_progress = Arc::new(BackfillProgress::default());
+	let (sync_progress_sender, sync_progress) = fc_rpc::sync_progress_channel();
+
+	let gas_cap = cli.run.rpc_gas_cap;
+	// The standard RPC servers share the payload cap of the guard unless given their own.
+	if config.rpc_max_payload.is_none() {
+		config.rpc_max_payload = Some(cli.run.rpc_guard_max_payload);
+	}
+	// The standard RPC servers cannot tell callers apart nor see whole requests.
+	if cli.run.rpc_guard_port.is_none() && cli.run.rpc_max_batch_size.is_some() {
+		return Err(ServiceError::Other(
+			"--rpc-max-batch-size requires --rpc-guard-port".into(),
+		));
+	}
+	if cli.run.rpc_guard_port.is_none()
+		&& cli.run.graphql_port.is_none()
+		&& cli.run.rpc_ip_rate_limit.is_some()
+	{
+		return Err(ServiceError::Other(
+			"--rpc-ip-rate-limit requires --rpc-guard-port or --graphql-port".into(),
+		));
+	}
+	if cli.run.rpc_guard_port.is_none()
+		&& (cli.run.ready_max_mapping_lag.is_some() || cli.run.ready_min_peers.is_some())
+	{
+		return Err(ServiceError::Other(
+			"--ready-max-mapping-lag and --ready-min-peers require --rpc-guard-port".into(),
+		));
+	}
+	let rpc_guard = Arc::new(fc_rpc::RpcGuard::new(
+		fc_rpc::RpcGuardConfig {
+			ip_rate_limit: cli.run.rpc_ip_rate_limit,
+			method_rate_limits: cli.run.rpc_method_rate_limit.iter().cloned().collect(),
+			max_batch_size: cli.run.rpc_max_batch_size,
+			max_request_size: Some(cli.run.rpc_guard_max_payload * 1024 * 1024),
+			evm_timeout: cli.run.rpc_evm_timeout.map(Duration::from_millis),
+			max_concurrent_evm_calls: cli.run.rpc_max_concurrent_evm_calls,
+		},
+		task_manager.spawn_handle(),
 	));
+	// Methods are only wrapped when a limit applies to them.
+	let method_guard = if cli.run.rpc_method_rate_limit.is_empty()
+		&& cli.run.rpc_evm_timeout.is_none()
+		&& cli.run.rpc_max_concurrent_evm_calls.is_none()
+	{
+		None
+	} else {
+		Some(rpc_guard.clone())
+	};
+
+	// Spawn the EIP-1767 GraphQL server, resolved by its own `EthApi` over the shared caches.
+	if let Some(port) = cli.run.graphql_port {
+		let eth = fc_rpc::EthApi::new(
+			client.clone(),
+			transaction_pool.clone(),
+			transaction_pool.pool().clone(),
+			Some(frontier_template_runtime::TransactionConverter),
+			network.clone(),
+			Vec::new(),
+			None,
+			overrides.clone(),
+			frontier_backend.clone(),
+			is_authority,
+			block_data_cache.clone(),
+			fee_history_limit,
+			fee_history_cache.clone(),
+			pending_block.clone(),
+			backfill_progress.clone(),
+			sync_progress.clone(),
+			gas_cap,
+		);
+		let filter = fc_rpc::EthFilterApi::new(
+			client.clone(),
+			frontier_backend.clone(),
+			filter_pool.clone().unwrap_or_default(),
+			500,
+			cli.run.max_past_logs,
+			block_data_cache.clone(),
+		);
+		let schema = fc_graphql::schema(
+			Arc::new(fc_graphql::RpcBackend::new(eth, filter)),
+			rpc_guard.clone(),
+			cli.run.graphql_max_depth,
+			cli.run.graphql_max_complexity,
+		);
+		let interface = if cli.run.graphql_external {
+			[0, 0, 0, 0]
+		} else {
+			[127, 0, 0, 1]
+		};
+		let server = fc_graphql::start_server((interface, port).into(), schema, rpc_guard.clone())
+			.map_err(ServiceError::Other)?;
+		task_manager
+			.spawn_handle()
+			.spawn("frontier-graphql", None, server);
+	}
+
+	// Spawn the guarded RPC server, enforcing the limits the standard servers cannot.
+	if let Some(port) = cli.run.rpc_guard_port {
+		let deps = crate::rpc::FullDeps {
+			client: client.clone(),
+			pool: transaction_pool.clone(),
+			graph: transaction_pool.pool().clone(),
+			deny_unsafe: sc_rpc_api::DenyUnsafe::Yes,
+			is_authority,
+			enable_dev_signer,
+			network: network.clone(),
+			filter_pool: filter_pool.clone(),
+			backend: frontier_backend.clone(),
+			max_past_logs: cli.run.max_past_logs,
+			fee_history_limit,
+			fee_history_cache: fee_history_cache.clone(),
+			command_sink: None,
+			overrides: overrides.clone(),
+			block_data_cache: block_data_cache.clone(),
+			pending_block: pending_block.clone(),
+			backfill_progress: backfill_progress.clone(),
+			sync_progress: sync_progress.clone(),
+			gas_cap,
+			rpc_guard: method_guard.clone(),
+			metrics: rpc_metrics.clone(),
+			#[cfg(feature = "manual-seal")]
+			dev_state: None,
+		};
+		let io = crate::rpc::create_full(deps, subscription_task_executor.clone());
+		let interface = if cli.run.rpc_guard_external {
+			[0, 0, 0, 0]
+		} else {
+			[127, 0, 0, 1]
+		};
+		let readiness = Arc::new(fc_rpc::EthReadiness::new(
+			client.clone(),
+			frontier_backend.clone(),
+			network.clone(),
+			fc_rpc::ReadinessThresholds {
+				max_mapping_lag: cli.run.ready_max_mapping_lag.unwrap_or(16),
+				min_peers: cli.run.ready_min_peers.unwrap_or(1),
+			},
+		));
+		let server =
+			fc_rpc::start_guarded_server((interface, port).into(), io, rpc_guard, readiness)
+				.map_err(ServiceError::Other)?;
+		task_manager
+			.spawn_handle()
+			.spawn("frontier-guarded-rpc", None, server);
+	}
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
@@ -410,6 +735,9 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 		let overrides = overrides.clone();
 		let fee_history_cache = fee_history_cache.clone();
 		let max_past_logs = cli.run.max_past_logs;
+		let backfill_progress = backfill_progress.clone();
+		let sync_progress = sync_progress.clone();
+		let rpc_metrics = rpc_metrics.clone();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
@@ -428,6 +756,14 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 				command_sink: Some(command_sink.clone()),
 				overrides: overrides.clone(),
 				block_data_cache: block_data_cache.clone(),
+				pending_block: pending_block.clone(),
+				backfill_progress: backfill_progress.clone(),
+				sync_progress: sync_progress.clone(),
+				gas_cap,
+				rpc_guard: method_guard.clone(),
+				metrics: rpc_metrics.clone(),
+				#[cfg(feature = "manual-seal")]
+				dev_state: dev_state.clone(),
 			};
 
 			Ok(crate::rpc::create_full(
@@ -450,6 +786,11 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	let mapping_sync_metrics = prometheus_registry
+		.as_ref()
+		.map(MappingSyncMetrics::register)
+		.transpose()
+		.map_err(|e| ServiceError::Other(format!("{}", e)))?;
 	task_manager.spawn_essential_handle().spawn(
 		"frontier-mapping-sync-worker",
 		None,
@@ -462,10 +803,51 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 			3,
 			0,
 			SyncStrategy::Normal,
+			mapping_sync_metrics,
 		)
 		.for_each(|()| futures::future::ready(())),
 	);
 
+	// Spawn Frontier mapping backfill, catching up with finalized history next to the worker.
+	if cli.run.mapping_backfill_threads > 0 {
+		let metrics = prometheus_registry
+			.as_ref()
+			.map(BackfillMetrics::register)
+			.transpose()
+			.map_err(|e| ServiceError::Other(format!("{}", e)))?;
+		let worker = MappingBackfillWorker::new(
+			client.clone(),
+			backend.clone(),
+			frontier_backend.clone(),
+			0,
+			cli.run.mapping_backfill_batch_size,
+			cli.run.mapping_backfill_threads,
+			backfill_progress,
+			metrics,
+		);
+		task_manager
+			.spawn_handle()
+			.spawn_blocking(
+				"frontier-mapping-backfill",
+				None,
+				async move { worker.run() },
+			);
+	}
+
+	// Spawn Frontier mapping pruning task, following finality.
+	if let Some(retain_blocks) = cli.run.mapping_retention {
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"frontier-mapping-pruning",
+			None,
+			fc_mapping_sync::pruning_task(
+				client.clone(),
+				backend.clone(),
+				frontier_backend.clone(),
+				retain_blocks,
+			),
+		);
+	}
+
 	// Spawn Frontier EthFilterApi maintenance task.
 	if let Some(filter_pool) = filter_pool {
 		// Each filter is allowed to stay in the pool for 100 blocks.
@@ -473,7 +855,12 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 		task_manager.spawn_essential_handle().spawn(
 			"frontier-filter-pool",
 			None,
-			EthTask::filter_pool_task(Arc::clone(&client), filter_pool, FILTER_RETAIN_THRESHOLD),
+			EthTask::filter_pool_task(
+				Arc::clone(&client),
+				filter_pool,
+				FILTER_RETAIN_THRESHOLD,
+				rpc_metrics.clone(),
+			),
 		);
 	}
 
@@ -486,6 +873,18 @@ pub fn new_full(mut config: Configuration, cli: &Cli) -> Result<TaskManager, Ser
 			Arc::clone(&overrides),
 			fee_history_cache,
 			fee_history_limit,
+			rpc_metrics,
+		),
+	);
+
+	// Spawn Frontier sync progress task, reported by `eth_syncing`.
+	task_manager.spawn_handle().spawn(
+		"frontier-sync-progress",
+		None,
+		EthTask::sync_progress_task(
+			Arc::clone(&client),
+			Arc::clone(&network),
+			sync_progress_sender,
 		),
 	);
 
@@ -45,8 +45,11 @@ pub use frame_support::{
 	ConsensusEngineId, StorageValue,
 };
 pub use pallet_balances::Call as BalancesCall;
+pub use pallet_ethereum::Call as EthereumCall;
 use pallet_ethereum::{Call::transact, Transaction as EthereumTransaction};
+pub use pallet_evm::Call as EVMCall;
 use pallet_evm::{Account as EVMAccount, EnsureAddressTruncated, HashedAddressMapping, Runner};
+pub use pallet_sudo::Call as SudoCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::CurrencyAdapter;
 #[cfg(any(feature = "std", test))]
@@ -110,7 +113,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-frontier-template"),
 	impl_name: create_runtime_str!("node-frontier-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 8,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -264,7 +267,8 @@ impl pallet_balances::Config for Runtime {
 	type Event = Event;
 	type DustRemoval = ();
 	type ExistentialDeposit = ExistentialDeposit;
-	type AccountStore = System;
+	// Reports balance changes made outside the EVM to the Ethereum state trie.
+	type AccountStore = pallet_ethereum::StateTrieAccountStore<Runtime, System>;
 	type WeightInfo = ();
 }
 
@@ -335,11 +339,15 @@ impl pallet_evm::Config for Runtime {
 	type BlockGasLimit = BlockGasLimit;
 	type OnChargeTransaction = ();
 	type FindAuthor = FindAuthorTruncated<Aura>;
+
+	fn on_state_touched(touched: &pallet_evm::TouchedState) {
+		pallet_ethereum::EthereumStateRoot::<Self>::touch(touched)
+	}
 }
 
 impl pallet_ethereum::Config for Runtime {
 	type Event = Event;
-	type StateRoot = pallet_ethereum::IntermediateStateRoot<Self>;
+	type StateRoot = pallet_ethereum::EthereumStateRoot<Self>;
 }
 
 frame_support::parameter_types! {
@@ -446,6 +454,8 @@ pub type SignedExtra = (
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
 	fp_self_contained::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// The payload being signed in transactions.
+pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = fp_self_contained::CheckedExtrinsic<AccountId, Call, SignedExtra, H160>;
 /// Executive: handles dispatch to the various modules.
@@ -644,6 +654,8 @@ impl_runtime_apis! {
 				max_priority_fee_per_gas,
 				nonce,
 				access_list.unwrap_or_default(),
+				false,
+				false,
 				config.as_ref().unwrap_or(<Runtime as pallet_evm::Config>::config()),
 			).map_err(|err| err.into())
 		}
@@ -676,6 +688,8 @@ impl_runtime_apis! {
 				max_priority_fee_per_gas,
 				nonce,
 				access_list.unwrap_or_default(),
+				false,
+				false,
 				config.as_ref().unwrap_or(<Runtime as pallet_evm::Config>::config()),
 			).map_err(|err| err.into())
 		}
@@ -716,6 +730,37 @@ impl_runtime_apis! {
 		fn elasticity() -> Option<Permill> {
 			Some(BaseFee::elasticity())
 		}
+
+		fn current_revert_data() -> Vec<(u32, Vec<u8>)> {
+			Ethereum::current_revert_data()
+		}
+
+		fn create_access_list(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			access_list: Option<Vec<(H160, Vec<H256>)>>,
+		) -> Result<pallet_evm::AccessListInfo, sp_runtime::DispatchError> {
+			pallet_evm::runner::stack::Runner::<Runtime>::create_access_list(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.low_u64(),
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list.unwrap_or_default(),
+				false,
+				false,
+				<Runtime as pallet_evm::Config>::config(),
+			).map_err(|err| err.into())
+		}
 	}
 
 	impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
@@ -726,6 +771,17 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl fp_rpc::EthereumStateRootApi<Block> for Runtime {
+		fn check_state_root() -> Option<(H256, H256)> {
+			pallet_ethereum::EthereumStateRoot::<Runtime>::is_enabled().then(|| {
+				(
+					pallet_ethereum::EthereumStateRoot::<Runtime>::root(),
+					pallet_ethereum::EthereumStateRoot::<Runtime>::recompute(),
+				)
+			})
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<
 		Block,
 		Balance,
@@ -18,15 +18,19 @@
 
 use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 
+use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
 use sc_client_api::{backend::AuxStore, BlockOf};
-use sc_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult};
-use sp_api::ProvideRuntimeApi;
+use sc_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult, StateAction};
+use sp_api::{ApiExt, Core as CoreApi, ProvideRuntimeApi};
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
-use sp_blockchain::{well_known_cache_keys::Id as CacheKeyId, HeaderBackend};
+use sp_blockchain::{well_known_cache_keys::Id as CacheKeyId, BlockStatus, HeaderBackend};
 use sp_consensus::Error as ConsensusError;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
 
-use fp_consensus::{ensure_log, FindLogError};
+use fp_consensus::{ensure_log, find_log, FindLogError, Hashes};
 use fp_rpc::EthereumRuntimeRPCApi;
 
 #[derive(Debug, thiserror::Error)]
@@ -37,6 +41,8 @@ pub enum Error {
 	NoRuntimeLog,
 	#[error("Cannot access the runtime at genesis, rejecting!")]
 	RuntimeApiCallFailed,
+	#[error("Frontier log does not match the runtime Ethereum block, rejecting!")]
+	PostLogMismatch,
 }
 
 impl From<Error> for String {
@@ -60,10 +66,61 @@ impl From<Error> for ConsensusError {
 	}
 }
 
+/// How the Frontier log of imported blocks is checked against the Ethereum block built by the
+/// runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostLogValidation {
+	/// Only check that there is exactly one Frontier log.
+	Disabled,
+	/// Also compare the log with the runtime Ethereum block, and report mismatches and blocks
+	/// that could not be checked. Never fails an import.
+	Flag,
+	/// Also execute blocks before importing them, and fail the import of those whose log does
+	/// not match the runtime Ethereum block, so that they are never applied. Imported blocks
+	/// are executed twice.
+	Reject,
+}
+
+impl Default for PostLogValidation {
+	fn default() -> Self {
+		PostLogValidation::Disabled
+	}
+}
+
+/// Prometheus metrics of the Frontier block import.
+#[derive(Clone)]
+pub struct ImportMetrics {
+	post_log_mismatches: Counter<U64>,
+	post_log_check_failures: Counter<U64>,
+}
+
+impl ImportMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			post_log_mismatches: register(
+				Counter::new(
+					"frontier_post_log_mismatches_total",
+					"Number of imported blocks whose Frontier log does not match the runtime Ethereum block",
+				)?,
+				registry,
+			)?,
+			post_log_check_failures: register(
+				Counter::new(
+					"frontier_post_log_check_failures_total",
+					"Number of imported blocks whose Frontier log could not be checked against the runtime",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
 pub struct FrontierBlockImport<B: BlockT, I, C> {
 	inner: I,
 	client: Arc<C>,
 	backend: Arc<fc_db::Backend<B>>,
+	post_log_validation: PostLogValidation,
+	metrics: Option<ImportMetrics>,
 	_marker: PhantomData<B>,
 }
 
@@ -73,6 +130,8 @@ impl<Block: BlockT, I: Clone + BlockImport<Block>, C> Clone for FrontierBlockImp
 			inner: self.inner.clone(),
 			client: self.client.clone(),
 			backend: self.backend.clone(),
+			post_log_validation: self.post_log_validation,
+			metrics: self.metrics.clone(),
 			_marker: PhantomData,
 		}
 	}
@@ -86,15 +145,140 @@ where
 	C: ProvideRuntimeApi<B> + Send + Sync + HeaderBackend<B> + AuxStore + BlockOf,
 	C::Api: EthereumRuntimeRPCApi<B>,
 	C::Api: BlockBuilderApi<B>,
+	C::Api: CoreApi<B>,
 {
 	pub fn new(inner: I, client: Arc<C>, backend: Arc<fc_db::Backend<B>>) -> Self {
 		Self {
 			inner,
 			client,
 			backend,
+			post_log_validation: PostLogValidation::Disabled,
+			metrics: None,
 			_marker: PhantomData,
 		}
 	}
+
+	/// Check the Frontier log of imported blocks against the Ethereum block built by the runtime.
+	pub fn with_post_log_validation(
+		mut self,
+		post_log_validation: PostLogValidation,
+		metrics: Option<ImportMetrics>,
+	) -> Self {
+		self.post_log_validation = post_log_validation;
+		self.metrics = metrics;
+		self
+	}
+
+	/// Whether the Frontier log of the imported block `hash` matches the Ethereum block the
+	/// runtime built. Runtimes too old to return a `BlockV2` are not checked.
+	fn imported_post_log_matches(
+		&self,
+		hash: B::Hash,
+		post_hashes: &Hashes,
+	) -> Result<bool, Error> {
+		let id = BlockId::Hash(hash);
+		let api = self.client.runtime_api();
+		match api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&id) {
+			Ok(Some(api_version)) if api_version > 1 => (),
+			Ok(_) => return Ok(true),
+			Err(_) => return Err(Error::RuntimeApiCallFailed),
+		}
+
+		let block = api
+			.current_block(&id)
+			.map_err(|_| Error::RuntimeApiCallFailed)?;
+		Ok(post_log_matches(post_hashes, block.map(Hashes::from_block)))
+	}
+
+	/// Whether the Frontier log of `block` matches the Ethereum block the runtime builds when
+	/// executing it on top of its parent, without importing it. Blocks that are already
+	/// imported, come without a body or fail to execute are not checked, and are left to the
+	/// inner import.
+	fn executed_post_log_matches(
+		&self,
+		block: &BlockImportParams<B, sp_api::TransactionFor<C, B>>,
+		post_hashes: &Hashes,
+	) -> Result<bool, Error> {
+		let body = match (&block.state_action, &block.body) {
+			(StateAction::Execute | StateAction::ExecuteIfPossible, Some(body)) => body.clone(),
+			_ => return Ok(true),
+		};
+		if matches!(
+			self.client.status(BlockId::Hash(block.post_hash())),
+			Ok(BlockStatus::InChain)
+		) {
+			return Ok(true);
+		}
+
+		let parent_id = BlockId::Hash(*block.header.parent_hash());
+		let api = self.client.runtime_api();
+		match api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&parent_id) {
+			Ok(Some(api_version)) if api_version > 1 => (),
+			Ok(_) => return Ok(true),
+			Err(_) => return Err(Error::RuntimeApiCallFailed),
+		}
+
+		// The changes of the execution stay in the overlay of `api`, which `current_block` then
+		// reads.
+		if api
+			.execute_block(&parent_id, B::new(block.header.clone(), body))
+			.is_err()
+		{
+			return Ok(true);
+		}
+		let block = api
+			.current_block(&parent_id)
+			.map_err(|_| Error::RuntimeApiCallFailed)?;
+		Ok(post_log_matches(post_hashes, block.map(Hashes::from_block)))
+	}
+}
+
+fn report_mismatch<H: std::fmt::Debug>(
+	hash: H,
+	post_hashes: &Hashes,
+	metrics: Option<&ImportMetrics>,
+) {
+	log::warn!(
+		target: "frontier-consensus",
+		"Frontier log of block {:?} does not match the runtime Ethereum block {:?}",
+		hash,
+		post_hashes.block_hash,
+	);
+	if let Some(metrics) = metrics {
+		metrics.post_log_mismatches.inc();
+	}
+}
+
+/// Finish a `Flag` mode import, which already succeeded. A mismatch, or a failure to check the
+/// log, for example because the state of the block was pruned, is only reported.
+fn flag_imported<H: std::fmt::Debug>(
+	result: ImportResult,
+	hash: H,
+	post_hashes: &Hashes,
+	matches: Result<bool, Error>,
+	metrics: Option<&ImportMetrics>,
+) -> ImportResult {
+	match matches {
+		Ok(true) => (),
+		Ok(false) => report_mismatch(hash, post_hashes, metrics),
+		Err(e) => {
+			log::warn!(
+				target: "frontier-consensus",
+				"Failed to check the Frontier log of block {:?}: {}",
+				hash,
+				e,
+			);
+			if let Some(metrics) = metrics {
+				metrics.post_log_check_failures.inc();
+			}
+		}
+	}
+	result
+}
+
+/// Whether the hashes of a Frontier log are the ones of the Ethereum block built by the runtime.
+fn post_log_matches(post_hashes: &Hashes, runtime_hashes: Option<Hashes>) -> bool {
+	runtime_hashes.as_ref() == Some(post_hashes)
 }
 
 #[async_trait::async_trait]
@@ -106,6 +290,7 @@ where
 	C: ProvideRuntimeApi<B> + Send + Sync + HeaderBackend<B> + AuxStore + BlockOf,
 	C::Api: EthereumRuntimeRPCApi<B>,
 	C::Api: BlockBuilderApi<B>,
+	C::Api: CoreApi<B>,
 {
 	type Error = ConsensusError;
 	type Transaction = sp_api::TransactionFor<C, B>;
@@ -127,9 +312,170 @@ where
 		// worker.
 		ensure_log(&block.header.digest()).map_err(|e| Error::from(e))?;
 
-		self.inner
+		if self.post_log_validation == PostLogValidation::Disabled {
+			return self
+				.inner
+				.import_block(block, new_cache)
+				.await
+				.map_err(Into::into);
+		}
+
+		let hash = block.post_hash();
+		let post_hashes = find_log(&block.header.digest())
+			.map_err(|e| Error::from(e))?
+			.into_hashes();
+
+		if self.post_log_validation == PostLogValidation::Reject {
+			if !self.executed_post_log_matches(&block, &post_hashes)? {
+				report_mismatch(hash, &post_hashes, self.metrics.as_ref());
+				return Err(Error::PostLogMismatch.into());
+			}
+			return self
+				.inner
+				.import_block(block, new_cache)
+				.await
+				.map_err(Into::into);
+		}
+
+		let result = self
+			.inner
 			.import_block(block, new_cache)
 			.await
-			.map_err(Into::into)
+			.map_err(Into::into)?;
+		// Only newly imported blocks have been executed.
+		if !matches!(result, ImportResult::Imported(_)) {
+			return Ok(result);
+		}
+
+		Ok(flag_imported(
+			result,
+			hash,
+			&post_hashes,
+			self.imported_post_log_matches(hash, &post_hashes),
+			self.metrics.as_ref(),
+		))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use codec::Encode;
+	use ethereum_types::{Bloom, H160, H256, H64, U256};
+	use fp_consensus::{PostLog, FRONTIER_ENGINE_ID};
+	use sp_runtime::generic::{Digest, DigestItem};
+
+	fn ethereum_block(number: u64) -> ethereum::BlockV2 {
+		let partial_header = ethereum::PartialHeader {
+			parent_hash: H256::repeat_byte(1),
+			beneficiary: H160::repeat_byte(2),
+			state_root: H256::repeat_byte(3),
+			receipts_root: H256::repeat_byte(4),
+			logs_bloom: Bloom::default(),
+			difficulty: U256::zero(),
+			number: U256::from(number),
+			gas_limit: U256::from(75_000_000),
+			gas_used: U256::zero(),
+			timestamp: 1_650_000_000_000,
+			extra_data: Vec::new(),
+			mix_hash: H256::default(),
+			nonce: H64::default(),
+		};
+		ethereum::Block::new(partial_header, Vec::new(), Vec::new())
+	}
+
+	/// The hashes the import reads from a header carrying `post_log`.
+	fn imported_hashes(post_log: PostLog) -> Hashes {
+		let digest = Digest {
+			logs: vec![DigestItem::Consensus(FRONTIER_ENGINE_ID, post_log.encode())],
+		};
+		find_log(&digest)
+			.expect("a single Frontier log")
+			.into_hashes()
+	}
+
+	#[test]
+	fn post_log_of_the_runtime_block_matches() {
+		let block = ethereum_block(1);
+		let runtime_hashes = Some(Hashes::from_block(block.clone()));
+
+		let post_hashes = imported_hashes(PostLog::Hashes(Hashes::from_block(block.clone())));
+		assert!(post_log_matches(&post_hashes, runtime_hashes.clone()));
+
+		let post_hashes = imported_hashes(PostLog::Block(block));
+		assert!(post_log_matches(&post_hashes, runtime_hashes));
+	}
+
+	#[test]
+	fn tampered_block_hash_does_not_match() {
+		let block = ethereum_block(1);
+		let mut tampered = Hashes::from_block(block.clone());
+		tampered.block_hash = ethereum_block(2).header.hash();
+
+		let post_hashes = imported_hashes(PostLog::Hashes(tampered));
+		assert!(!post_log_matches(
+			&post_hashes,
+			Some(Hashes::from_block(block))
+		));
+	}
+
+	#[test]
+	fn tampered_transaction_hashes_do_not_match() {
+		let block = ethereum_block(1);
+		let mut tampered = Hashes::from_block(block.clone());
+		tampered.transaction_hashes.push(H256::repeat_byte(0xee));
+
+		let post_hashes = imported_hashes(PostLog::Hashes(tampered));
+		assert!(!post_log_matches(
+			&post_hashes,
+			Some(Hashes::from_block(block))
+		));
+	}
+
+	fn imported() -> ImportResult {
+		ImportResult::imported(true)
+	}
+
+	#[test]
+	fn flag_mode_keeps_the_import_when_the_check_fails() {
+		let metrics = ImportMetrics::register(&Registry::new()).unwrap();
+		let post_hashes = imported_hashes(PostLog::Hashes(Hashes::from_block(ethereum_block(1))));
+
+		assert_eq!(
+			flag_imported(
+				imported(),
+				1,
+				&post_hashes,
+				Err(Error::RuntimeApiCallFailed),
+				Some(&metrics)
+			),
+			imported()
+		);
+		assert_eq!(metrics.post_log_check_failures.get(), 1);
+		assert_eq!(metrics.post_log_mismatches.get(), 0);
+	}
+
+	#[test]
+	fn flag_mode_keeps_the_import_of_a_mismatching_block() {
+		let metrics = ImportMetrics::register(&Registry::new()).unwrap();
+		let post_hashes = imported_hashes(PostLog::Hashes(Hashes::from_block(ethereum_block(1))));
+
+		assert_eq!(
+			flag_imported(imported(), 1, &post_hashes, Ok(false), Some(&metrics)),
+			imported()
+		);
+		assert_eq!(
+			flag_imported(imported(), 1, &post_hashes, Ok(true), Some(&metrics)),
+			imported()
+		);
+		assert_eq!(metrics.post_log_mismatches.get(), 1);
+		assert_eq!(metrics.post_log_check_failures.get(), 0);
+	}
+
+	#[test]
+	fn post_log_without_runtime_block_does_not_match() {
+		let post_hashes = imported_hashes(PostLog::Hashes(Hashes::from_block(ethereum_block(1))));
+		assert!(!post_log_matches(&post_hashes, None));
 	}
 }
@@ -16,9 +16,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod parity_db_adapter;
+mod rocksdb_adapter;
 mod utils;
 
 use std::{
+	collections::{hash_map::Entry, HashMap},
 	marker::PhantomData,
 	path::{Path, PathBuf},
 	sync::Arc,
@@ -26,19 +29,26 @@ use std::{
 
 use codec::{Decode, Encode};
 use fp_storage::{EthereumStorageSchema, PALLET_ETHEREUM_SCHEMA_CACHE};
-use parking_lot::Mutex;
-use sp_core::H256;
+use parking_lot::{Mutex, RwLock};
+use sp_core::{H160, H256, U256};
 pub use sp_database::Database;
-use sp_runtime::traits::Block as BlockT;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+pub use utils::migrate_rocksdb_to_paritydb;
 
 const DB_HASH_LEN: usize = 32;
 /// Hash type that this backend uses for the database.
 pub type DbHash = [u8; DB_HASH_LEN];
 
+/// Number of blocks whose indices `IndexDb::prune_until` deletes in a single transaction.
+const PRUNE_BATCH_SIZE: usize = 1000;
+
 /// Database settings.
 pub struct DatabaseSettings {
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
+	/// Whether to maintain the transaction indices of `IndexDb`. They are kept in a separate
+	/// ParityDb database, in an `index` directory next to the mapping database.
+	pub transaction_index: bool,
 }
 
 /// Where to find the database.
@@ -51,6 +61,11 @@ pub enum DatabaseSettingsSrc {
 		/// Cache size in MiB.
 		cache_size: usize,
 	},
+	/// Load a ParityDb database from a given path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
+	},
 }
 
 impl DatabaseSettingsSrc {
@@ -58,10 +73,28 @@ impl DatabaseSettingsSrc {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
 			DatabaseSettingsSrc::RocksDb { path, .. } => Some(path.as_path()),
+			DatabaseSettingsSrc::ParityDb { path } => Some(path.as_path()),
 		}
 	}
 }
 
+/// A `Database` whose columns can also be walked, for maintenance tools and the transaction
+/// indices.
+pub trait IterableDatabase: Database<DbHash> {
+	/// Call `f` with the key and value of every entry of `col`.
+	fn for_each_entry(&self, col: u32, f: &mut dyn FnMut(&[u8], &[u8])) -> Result<(), String>;
+
+	/// Call `f` with the key and value of the entries of `col` whose key starts with `prefix`,
+	/// from the first one not below `start`, in key order, until it returns `false`.
+	fn for_each_entry_from(
+		&self,
+		col: u32,
+		prefix: &[u8],
+		start: &[u8],
+		f: &mut dyn FnMut(&[u8], &[u8]) -> bool,
+	) -> Result<(), String>;
+}
+
 pub(crate) mod columns {
 	pub const NUM_COLUMNS: u32 = 4;
 
@@ -71,13 +104,30 @@ pub(crate) mod columns {
 	pub const SYNCED_MAPPING: u32 = 3;
 }
 
+/// Columns of the transaction indices database.
+pub(crate) mod index_columns {
+	pub const NUM_COLUMNS: u32 = 6;
+
+	pub const META: u32 = 0;
+	pub const SENDER_NONCE: u32 = 1;
+	pub const ADDRESS_APPEARANCES: u32 = 2;
+	pub const ADDRESS_APPEARANCES_REVERSE: u32 = 3;
+	pub const CONTRACT_CREATOR: u32 = 4;
+	pub const BLOCK_TRANSACTIONS: u32 = 5;
+}
+
 pub(crate) mod static_keys {
 	pub const CURRENT_SYNCING_TIPS: &[u8] = b"CURRENT_SYNCING_TIPS";
+	pub const BACKFILL_CURSOR: &[u8] = b"BACKFILL_CURSOR";
+	pub const FIRST_RETAINED_BLOCK: &[u8] = b"FIRST_RETAINED_BLOCK";
+	pub const INDEX_CURSOR: &[u8] = b"INDEX_CURSOR";
 }
 
 pub struct Backend<Block: BlockT> {
 	meta: Arc<MetaDb<Block>>,
 	mapping: Arc<MappingDb<Block>>,
+	index: Option<Arc<IndexDb>>,
+	pruning_lock: RwLock<()>,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -94,6 +144,15 @@ impl<Block: BlockT> Backend<Block> {
 				db: db.clone(),
 				_marker: PhantomData,
 			}),
+			index: if config.transaction_index {
+				Some(Arc::new(IndexDb {
+					db: utils::open_index_database(&config.source)?,
+					write_lock: Arc::new(Mutex::new(())),
+				}))
+			} else {
+				None
+			},
+			pruning_lock: RwLock::new(()),
 		})
 	}
 
@@ -104,10 +163,21 @@ impl<Block: BlockT> Backend<Block> {
 	pub fn meta(&self) -> &Arc<MetaDb<Block>> {
 		&self.meta
 	}
+
+	/// The transaction indices, if they are maintained.
+	pub fn index(&self) -> Option<&Arc<IndexDb>> {
+		self.index.as_ref()
+	}
+
+	/// Held for writing while pruning, and for reading from reading the first retained block
+	/// until the mappings of finalized blocks are written, so that none is written below it.
+	pub fn pruning_lock(&self) -> &RwLock<()> {
+		&self.pruning_lock
+	}
 }
 
 pub struct MetaDb<Block: BlockT> {
-	db: Arc<dyn Database<DbHash>>,
+	db: Arc<dyn IterableDatabase>,
 	_marker: PhantomData<Block>,
 }
 
@@ -140,6 +210,64 @@ impl<Block: BlockT> MetaDb<Block> {
 		Ok(())
 	}
 
+	/// Next block number the mapping backfill has to process, if it was started.
+	pub fn backfill_cursor(&self) -> Result<Option<NumberFor<Block>>, String> {
+		match self
+			.db
+			.get(crate::columns::META, crate::static_keys::BACKFILL_CURSOR)
+		{
+			Some(raw) => Ok(Some(
+				Decode::decode(&mut &raw[..]).map_err(|e| format!("{:?}", e))?,
+			)),
+			None => Ok(None),
+		}
+	}
+
+	pub fn write_backfill_cursor(&self, cursor: NumberFor<Block>) -> Result<(), String> {
+		let mut transaction = sp_database::Transaction::new();
+
+		transaction.set(
+			crate::columns::META,
+			crate::static_keys::BACKFILL_CURSOR,
+			&cursor.encode(),
+		);
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(())
+	}
+
+	/// Lowest block number whose mappings are kept, if older mappings were pruned.
+	pub fn first_retained_block(&self) -> Result<Option<NumberFor<Block>>, String> {
+		match self.db.get(
+			crate::columns::META,
+			crate::static_keys::FIRST_RETAINED_BLOCK,
+		) {
+			Some(raw) => Ok(Some(
+				Decode::decode(&mut &raw[..]).map_err(|e| format!("{:?}", e))?,
+			)),
+			None => Ok(None),
+		}
+	}
+
+	pub fn write_first_retained_block(&self, number: NumberFor<Block>) -> Result<(), String> {
+		let mut transaction = sp_database::Transaction::new();
+
+		transaction.set(
+			crate::columns::META,
+			crate::static_keys::FIRST_RETAINED_BLOCK,
+			&number.encode(),
+		);
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(())
+	}
+
 	pub fn ethereum_schema(&self) -> Result<Option<Vec<(EthereumStorageSchema, H256)>>, String> {
 		match self
 			.db
@@ -186,7 +314,7 @@ pub struct TransactionMetadata<Block: BlockT> {
 }
 
 pub struct MappingDb<Block: BlockT> {
-	db: Arc<dyn Database<DbHash>>,
+	db: Arc<dyn IterableDatabase>,
 	write_lock: Arc<Mutex<()>>,
 	_marker: PhantomData<Block>,
 }
@@ -247,27 +375,56 @@ impl<Block: BlockT> MappingDb<Block> {
 	}
 
 	pub fn write_hashes(&self, commitment: MappingCommitment<Block>) -> Result<(), String> {
+		self.write_batch(vec![commitment], Vec::new())
+	}
+
+	/// Write the mappings of several blocks, and mark blocks without Ethereum data as synced, in
+	/// a single transaction.
+	pub fn write_batch(
+		&self,
+		commitments: Vec<MappingCommitment<Block>>,
+		unmapped: Vec<Block::Hash>,
+	) -> Result<(), String> {
 		let _lock = self.write_lock.lock();
 
 		let mut transaction = sp_database::Transaction::new();
+		let mut metadata_cache = HashMap::<H256, Vec<TransactionMetadata<Block>>>::new();
 
-		transaction.set(
-			crate::columns::BLOCK_MAPPING,
-			&commitment.ethereum_block_hash.encode(),
-			&commitment.block_hash.encode(),
-		);
+		for commitment in commitments {
+			transaction.set(
+				crate::columns::BLOCK_MAPPING,
+				&commitment.ethereum_block_hash.encode(),
+				&commitment.block_hash.encode(),
+			);
 
-		for (i, ethereum_transaction_hash) in commitment
-			.ethereum_transaction_hashes
-			.into_iter()
-			.enumerate()
-		{
-			let mut metadata = self.transaction_metadata(&ethereum_transaction_hash)?;
-			metadata.push(TransactionMetadata::<Block> {
-				block_hash: commitment.block_hash,
-				ethereum_block_hash: commitment.ethereum_block_hash,
-				ethereum_index: i as u32,
-			});
+			for (i, ethereum_transaction_hash) in commitment
+				.ethereum_transaction_hashes
+				.into_iter()
+				.enumerate()
+			{
+				let metadata = match metadata_cache.entry(ethereum_transaction_hash) {
+					Entry::Occupied(entry) => entry.into_mut(),
+					Entry::Vacant(entry) => {
+						entry.insert(self.transaction_metadata(&ethereum_transaction_hash)?)
+					}
+				};
+				// Replace any previous entry for this block, so that re-syncing a block is idempotent.
+				metadata.retain(|m| m.block_hash != commitment.block_hash);
+				metadata.push(TransactionMetadata::<Block> {
+					block_hash: commitment.block_hash,
+					ethereum_block_hash: commitment.ethereum_block_hash,
+					ethereum_index: i as u32,
+				});
+			}
+
+			transaction.set(
+				crate::columns::SYNCED_MAPPING,
+				&commitment.block_hash.encode(),
+				&true.encode(),
+			);
+		}
+
+		for (ethereum_transaction_hash, metadata) in metadata_cache {
 			transaction.set(
 				crate::columns::TRANSACTION_MAPPING,
 				&ethereum_transaction_hash.encode(),
@@ -275,10 +432,480 @@ impl<Block: BlockT> MappingDb<Block> {
 			);
 		}
 
+		for block_hash in unmapped {
+			transaction.set(
+				crate::columns::SYNCED_MAPPING,
+				&block_hash.encode(),
+				&true.encode(),
+			);
+		}
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(())
+	}
+
+	/// Delete the mappings written by `write_batch` for the same blocks, in a single transaction.
+	/// Ethereum block rows are only deleted if they map to one of these blocks, and transaction
+	/// rows once no other block includes the transaction.
+	pub fn prune_batch(
+		&self,
+		commitments: Vec<MappingCommitment<Block>>,
+		unmapped: Vec<Block::Hash>,
+	) -> Result<(), String> {
+		let _lock = self.write_lock.lock();
+
+		let mut transaction = sp_database::Transaction::new();
+		let mut metadata_cache = HashMap::<H256, Vec<TransactionMetadata<Block>>>::new();
+
+		for commitment in commitments {
+			if self.block_hash(&commitment.ethereum_block_hash)? == Some(commitment.block_hash) {
+				transaction.remove(
+					crate::columns::BLOCK_MAPPING,
+					&commitment.ethereum_block_hash.encode(),
+				);
+			}
+
+			for ethereum_transaction_hash in commitment.ethereum_transaction_hashes {
+				let metadata = match metadata_cache.entry(ethereum_transaction_hash) {
+					Entry::Occupied(entry) => entry.into_mut(),
+					Entry::Vacant(entry) => {
+						entry.insert(self.transaction_metadata(&ethereum_transaction_hash)?)
+					}
+				};
+				metadata.retain(|m| m.block_hash != commitment.block_hash);
+			}
+
+			transaction.remove(
+				crate::columns::SYNCED_MAPPING,
+				&commitment.block_hash.encode(),
+			);
+		}
+
+		for (ethereum_transaction_hash, metadata) in metadata_cache {
+			if metadata.is_empty() {
+				transaction.remove(
+					crate::columns::TRANSACTION_MAPPING,
+					&ethereum_transaction_hash.encode(),
+				);
+			} else {
+				transaction.set(
+					crate::columns::TRANSACTION_MAPPING,
+					&ethereum_transaction_hash.encode(),
+					&metadata.encode(),
+				);
+			}
+		}
+
+		for block_hash in unmapped {
+			transaction.remove(crate::columns::SYNCED_MAPPING, &block_hash.encode());
+		}
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(())
+	}
+
+	/// All Ethereum block hashes in the mapping, with the Substrate block hash each maps to.
+	/// Reads the whole column.
+	pub fn block_mappings(&self) -> Result<Vec<(H256, Block::Hash)>, String> {
+		self.entries(crate::columns::BLOCK_MAPPING)
+	}
+
+	/// All Ethereum transaction hashes in the mapping, with their metadata.
+	/// Reads the whole column.
+	pub fn transaction_mappings(
+		&self,
+	) -> Result<Vec<(H256, Vec<TransactionMetadata<Block>>)>, String> {
+		self.entries(crate::columns::TRANSACTION_MAPPING)
+	}
+
+	/// All Substrate block hashes marked as synced. Reads the whole column.
+	pub fn synced_blocks(&self) -> Result<Vec<Block::Hash>, String> {
+		Ok(self
+			.entries::<Block::Hash, bool>(crate::columns::SYNCED_MAPPING)?
+			.into_iter()
+			.filter_map(|(block_hash, synced)| if synced { Some(block_hash) } else { None })
+			.collect())
+	}
+
+	pub fn remove_block_hash(&self, ethereum_block_hash: &H256) -> Result<(), String> {
+		self.remove(crate::columns::BLOCK_MAPPING, &ethereum_block_hash.encode())
+	}
+
+	pub fn remove_transaction_metadata(
+		&self,
+		ethereum_transaction_hash: &H256,
+	) -> Result<(), String> {
+		self.remove(
+			crate::columns::TRANSACTION_MAPPING,
+			&ethereum_transaction_hash.encode(),
+		)
+	}
+
+	/// Clear the synced flag of a block.
+	pub fn remove_synced(&self, block_hash: &Block::Hash) -> Result<(), String> {
+		self.remove(crate::columns::SYNCED_MAPPING, &block_hash.encode())
+	}
+
+	fn remove(&self, col: u32, key: &[u8]) -> Result<(), String> {
+		let _lock = self.write_lock.lock();
+
+		let mut transaction = sp_database::Transaction::new();
+		transaction.remove(col, key);
+
+		self.db.commit(transaction).map_err(|e| format!("{:?}", e))
+	}
+
+	fn entries<K: Decode, V: Decode>(&self, col: u32) -> Result<Vec<(K, V)>, String> {
+		let mut entries = Vec::new();
+		let mut error = None;
+		self.db
+			.for_each_entry(col, &mut |mut key: &[u8], mut value: &[u8]| {
+				if error.is_some() {
+					return;
+				}
+				match (K::decode(&mut key), V::decode(&mut value)) {
+					(Ok(key), Ok(value)) => entries.push((key, value)),
+					(Err(e), _) | (_, Err(e)) => error = Some(format!("{:?}", e)),
+				}
+			})?;
+
+		match error {
+			Some(e) => Err(e),
+			None => Ok(entries),
+		}
+	}
+}
+
+/// What the transaction indices record of one Ethereum transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct IndexedTransaction {
+	pub hash: H256,
+	pub index: u32,
+	pub sender: H160,
+	pub nonce: U256,
+	/// Every address the transaction touched: its sender and recipient, the contract it created
+	/// and the emitters of its logs.
+	pub addresses: Vec<H160>,
+	pub contract_address: Option<H160>,
+}
+
+/// A transaction in which an address appears.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Appearance {
+	pub block_number: u64,
+	pub transaction_index: u32,
+	pub transaction_hash: H256,
+}
+
+impl Appearance {
+	const KEY_LEN: usize = 20 + 8 + 4 + 32;
+
+	/// Appearances are keyed by address, then block number and transaction index in big endian,
+	/// so that the entries of an address are in chain order.
+	fn key(&self, address: &H160) -> Vec<u8> {
+		self.encode_key(address, self.block_number, self.transaction_index)
+	}
+
+	/// The same key with the block number and transaction index inverted, so that the entries of
+	/// an address are in reverse chain order.
+	fn reverse_key(&self, address: &H160) -> Vec<u8> {
+		self.encode_key(address, !self.block_number, !self.transaction_index)
+	}
+
+	fn encode_key(&self, address: &H160, block_number: u64, transaction_index: u32) -> Vec<u8> {
+		let mut key = Vec::with_capacity(Self::KEY_LEN);
+		key.extend_from_slice(&appearances_start(address, block_number));
+		key.extend_from_slice(&transaction_index.to_be_bytes());
+		key.extend_from_slice(self.transaction_hash.as_bytes());
+		key
+	}
+
+	fn from_key(key: &[u8]) -> Result<Self, String> {
+		let (block_number, transaction_index, transaction_hash) = Self::decode_key(key)?;
+		Ok(Self {
+			block_number,
+			transaction_index,
+			transaction_hash,
+		})
+	}
+
+	fn from_reverse_key(key: &[u8]) -> Result<Self, String> {
+		let (block_number, transaction_index, transaction_hash) = Self::decode_key(key)?;
+		Ok(Self {
+			block_number: !block_number,
+			transaction_index: !transaction_index,
+			transaction_hash,
+		})
+	}
+
+	fn decode_key(key: &[u8]) -> Result<(u64, u32, H256), String> {
+		if key.len() != Self::KEY_LEN {
+			return Err(format!("Invalid appearance key length {}", key.len()));
+		}
+		let mut block_number = [0u8; 8];
+		block_number.copy_from_slice(&key[20..28]);
+		let mut transaction_index = [0u8; 4];
+		transaction_index.copy_from_slice(&key[28..32]);
+		Ok((
+			u64::from_be_bytes(block_number),
+			u32::from_be_bytes(transaction_index),
+			H256::from_slice(&key[32..]),
+		))
+	}
+}
+
+/// Transaction indices by sender and nonce, by address appearance and by created contract, as
+/// used by the `ots_*` RPC. Entries of every fork are kept, so readers have to pick the
+/// canonical ones through the mapping.
+pub struct IndexDb {
+	db: Arc<dyn IterableDatabase>,
+	write_lock: Arc<Mutex<()>>,
+}
+
+impl IndexDb {
+	/// Index the transactions of block `block_number`. Indexing a block again is idempotent.
+	pub fn write_block(
+		&self,
+		block_number: u64,
+		transactions: Vec<IndexedTransaction>,
+	) -> Result<(), String> {
+		let _lock = self.write_lock.lock();
+
+		let mut transaction = sp_database::Transaction::new();
+		let mut nonce_cache = HashMap::<Vec<u8>, Vec<(H256, u64)>>::new();
+		let mut creator_cache = HashMap::<H160, Vec<(H256, H160, u64)>>::new();
+		let block_key = block_number.to_be_bytes();
+		let mut block_transactions: Vec<IndexedTransaction> = self
+			.read(crate::index_columns::BLOCK_TRANSACTIONS, &block_key)?
+			.unwrap_or_default();
+
+		for indexed in transactions {
+			let hashes = match nonce_cache.entry(sender_nonce_key(&indexed.sender, indexed.nonce)) {
+				Entry::Occupied(entry) => entry.into_mut(),
+				Entry::Vacant(entry) => {
+					let hashes = self.read(crate::index_columns::SENDER_NONCE, entry.key())?;
+					entry.insert(hashes.unwrap_or_default())
+				}
+			};
+			if !hashes.contains(&(indexed.hash, block_number)) {
+				hashes.push((indexed.hash, block_number));
+			}
+
+			if let Some(contract_address) = indexed.contract_address {
+				let creations = match creator_cache.entry(contract_address) {
+					Entry::Occupied(entry) => entry.into_mut(),
+					Entry::Vacant(entry) => {
+						let creations = self.read(
+							crate::index_columns::CONTRACT_CREATOR,
+							entry.key().as_bytes(),
+						)?;
+						entry.insert(creations.unwrap_or_default())
+					}
+				};
+				let creation = (indexed.hash, indexed.sender, block_number);
+				if !creations.contains(&creation) {
+					creations.push(creation);
+				}
+			}
+
+			let appearance = Appearance {
+				block_number,
+				transaction_index: indexed.index,
+				transaction_hash: indexed.hash,
+			};
+			for address in &indexed.addresses {
+				transaction.set(
+					crate::index_columns::ADDRESS_APPEARANCES,
+					&appearance.key(address),
+					&[],
+				);
+				transaction.set(
+					crate::index_columns::ADDRESS_APPEARANCES_REVERSE,
+					&appearance.reverse_key(address),
+					&[],
+				);
+			}
+
+			// Blocks of other forks at the same height are recorded under the same number.
+			if !block_transactions.contains(&indexed) {
+				block_transactions.push(indexed);
+			}
+		}
+
+		for (key, hashes) in nonce_cache {
+			transaction.set(crate::index_columns::SENDER_NONCE, &key, &hashes.encode());
+		}
+
+		for (contract_address, creations) in creator_cache {
+			transaction.set(
+				crate::index_columns::CONTRACT_CREATOR,
+				contract_address.as_bytes(),
+				&creations.encode(),
+			);
+		}
+
 		transaction.set(
-			crate::columns::SYNCED_MAPPING,
-			&commitment.block_hash.encode(),
-			&true.encode(),
+			crate::index_columns::BLOCK_TRANSACTIONS,
+			&block_key,
+			&block_transactions.encode(),
+		);
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(())
+	}
+
+	/// Delete the indices of every block below `block_number`, on every fork, committing them
+	/// in transactions of at most `PRUNE_BATCH_SIZE` blocks.
+	pub fn prune_until(&self, block_number: u64) -> Result<(), String> {
+		let mut start = 0u64;
+		loop {
+			match self.prune_batch_until(start, block_number)? {
+				Some(last) => start = last + 1,
+				None => return Ok(()),
+			}
+		}
+	}
+
+	/// Delete the indices of the first `PRUNE_BATCH_SIZE` blocks from `start` below
+	/// `block_number`, returning the number of the last one deleted.
+	fn prune_batch_until(&self, start: u64, block_number: u64) -> Result<Option<u64>, String> {
+		let _lock = self.write_lock.lock();
+
+		let mut blocks = Vec::new();
+		let mut error = None;
+		self.db.for_each_entry_from(
+			crate::index_columns::BLOCK_TRANSACTIONS,
+			&[],
+			&start.to_be_bytes(),
+			&mut |key: &[u8], mut value: &[u8]| {
+				let number = match <[u8; 8]>::try_from(key) {
+					Ok(number) => u64::from_be_bytes(number),
+					Err(_) => {
+						error = Some(format!("Invalid block key length {}", key.len()));
+						return false;
+					}
+				};
+				if number >= block_number {
+					return false;
+				}
+				match Vec::<IndexedTransaction>::decode(&mut value) {
+					Ok(transactions) => {
+						blocks.push((number, transactions));
+						blocks.len() < PRUNE_BATCH_SIZE
+					}
+					Err(e) => {
+						error = Some(format!("{:?}", e));
+						false
+					}
+				}
+			},
+		)?;
+		if let Some(e) = error {
+			return Err(e);
+		}
+		let last = match blocks.last() {
+			Some((number, _)) => *number,
+			None => return Ok(None),
+		};
+
+		let mut transaction = sp_database::Transaction::new();
+		let mut nonce_cache = HashMap::<Vec<u8>, Vec<(H256, u64)>>::new();
+		let mut creator_cache = HashMap::<H160, Vec<(H256, H160, u64)>>::new();
+
+		for (number, transactions) in blocks {
+			for indexed in transactions {
+				let key = sender_nonce_key(&indexed.sender, indexed.nonce);
+				if let Entry::Vacant(entry) = nonce_cache.entry(key) {
+					let hashes = self.read(crate::index_columns::SENDER_NONCE, entry.key())?;
+					entry.insert(hashes.unwrap_or_default());
+				}
+
+				if let Some(contract_address) = indexed.contract_address {
+					if let Entry::Vacant(entry) = creator_cache.entry(contract_address) {
+						let creations = self.read(
+							crate::index_columns::CONTRACT_CREATOR,
+							entry.key().as_bytes(),
+						)?;
+						entry.insert(creations.unwrap_or_default());
+					}
+				}
+
+				let appearance = Appearance {
+					block_number: number,
+					transaction_index: indexed.index,
+					transaction_hash: indexed.hash,
+				};
+				for address in &indexed.addresses {
+					transaction.remove(
+						crate::index_columns::ADDRESS_APPEARANCES,
+						&appearance.key(address),
+					);
+					transaction.remove(
+						crate::index_columns::ADDRESS_APPEARANCES_REVERSE,
+						&appearance.reverse_key(address),
+					);
+				}
+			}
+
+			transaction.remove(
+				crate::index_columns::BLOCK_TRANSACTIONS,
+				&number.to_be_bytes(),
+			);
+		}
+
+		for (key, mut hashes) in nonce_cache {
+			hashes.retain(|(_, number)| *number >= block_number);
+			if hashes.is_empty() {
+				transaction.remove(crate::index_columns::SENDER_NONCE, &key);
+			} else {
+				transaction.set(crate::index_columns::SENDER_NONCE, &key, &hashes.encode());
+			}
+		}
+
+		for (contract_address, mut creations) in creator_cache {
+			creations.retain(|(_, _, number)| *number >= block_number);
+			if creations.is_empty() {
+				transaction.remove(
+					crate::index_columns::CONTRACT_CREATOR,
+					contract_address.as_bytes(),
+				);
+			} else {
+				transaction.set(
+					crate::index_columns::CONTRACT_CREATOR,
+					contract_address.as_bytes(),
+					&creations.encode(),
+				);
+			}
+		}
+
+		self.db
+			.commit(transaction)
+			.map_err(|e| format!("{:?}", e))?;
+
+		Ok(Some(last))
+	}
+
+	/// Next block number the mapping backfill has to index, if it was started.
+	pub fn cursor(&self) -> Result<Option<u64>, String> {
+		self.read(crate::index_columns::META, crate::static_keys::INDEX_CURSOR)
+	}
+
+	pub fn write_cursor(&self, cursor: u64) -> Result<(), String> {
+		let mut transaction = sp_database::Transaction::new();
+
+		transaction.set(
+			crate::index_columns::META,
+			crate::static_keys::INDEX_CURSOR,
+			&cursor.encode(),
 		);
 
 		self.db
@@ -287,4 +914,622 @@ impl<Block: BlockT> MappingDb<Block> {
 
 		Ok(())
 	}
+
+	/// Hashes of the transactions sent by `sender` with `nonce`, one per fork that included one.
+	pub fn transactions_by_sender_and_nonce(
+		&self,
+		sender: &H160,
+		nonce: U256,
+	) -> Result<Vec<H256>, String> {
+		let hashes: Vec<(H256, u64)> = self
+			.read(
+				crate::index_columns::SENDER_NONCE,
+				&sender_nonce_key(sender, nonce),
+			)?
+			.unwrap_or_default();
+		Ok(hashes.into_iter().map(|(hash, _)| hash).collect())
+	}
+
+	/// Hashes of the transactions that created the contract at `address`, with their sender, one
+	/// per fork that included one.
+	pub fn contract_creations(&self, address: &H160) -> Result<Vec<(H256, H160)>, String> {
+		let creations: Vec<(H256, H160, u64)> = self
+			.read(crate::index_columns::CONTRACT_CREATOR, address.as_bytes())?
+			.unwrap_or_default();
+		Ok(creations
+			.into_iter()
+			.map(|(hash, sender, _)| (hash, sender))
+			.collect())
+	}
+
+	/// Appearances of `address` in blocks below `block_number`, most recent first, and whether
+	/// older ones remain. Stops at the end of the block in which `limit` appearances are
+	/// reached.
+	pub fn appearances_before(
+		&self,
+		address: &H160,
+		block_number: u64,
+		limit: usize,
+	) -> Result<(Vec<Appearance>, bool), String> {
+		let last = match block_number.checked_sub(1) {
+			Some(last) => last,
+			None => return Ok((Vec::new(), false)),
+		};
+		self.appearances_page(
+			crate::index_columns::ADDRESS_APPEARANCES_REVERSE,
+			address,
+			&appearances_start(address, !last),
+			limit,
+			Appearance::from_reverse_key,
+		)
+	}
+
+	/// Appearances of `address` in blocks above `block_number`, oldest first, and whether newer
+	/// ones remain. Stops at the end of the block in which `limit` appearances are reached.
+	pub fn appearances_after(
+		&self,
+		address: &H160,
+		block_number: u64,
+		limit: usize,
+	) -> Result<(Vec<Appearance>, bool), String> {
+		let first = match block_number.checked_add(1) {
+			Some(first) => first,
+			None => return Ok((Vec::new(), false)),
+		};
+		self.appearances_page(
+			crate::index_columns::ADDRESS_APPEARANCES,
+			address,
+			&appearances_start(address, first),
+			limit,
+			Appearance::from_key,
+		)
+	}
+
+	fn appearances_page(
+		&self,
+		col: u32,
+		address: &H160,
+		start: &[u8],
+		limit: usize,
+		decode: fn(&[u8]) -> Result<Appearance, String>,
+	) -> Result<(Vec<Appearance>, bool), String> {
+		let mut page = WholeBlocks::new(limit);
+		let mut error = None;
+		self.db.for_each_entry_from(
+			col,
+			address.as_bytes(),
+			start,
+			&mut |key: &[u8], _: &[u8]| match decode(key) {
+				Ok(appearance) => page.push(appearance),
+				Err(e) => {
+					error = Some(e);
+					false
+				}
+			},
+		)?;
+
+		match error {
+			Some(e) => Err(e),
+			None => Ok(page.finish()),
+		}
+	}
+
+	fn read<V: Decode>(&self, col: u32, key: &[u8]) -> Result<Option<V>, String> {
+		match self.db.get(col, key) {
+			Some(raw) => Ok(Some(
+				V::decode(&mut &raw[..]).map_err(|e| format!("{:?}", e))?,
+			)),
+			None => Ok(None),
+		}
+	}
+}
+
+fn sender_nonce_key(sender: &H160, nonce: U256) -> Vec<u8> {
+	let mut nonce_bytes = [0u8; 32];
+	nonce.to_big_endian(&mut nonce_bytes);
+
+	let mut key = Vec::with_capacity(20 + 32);
+	key.extend_from_slice(sender.as_bytes());
+	key.extend_from_slice(&nonce_bytes);
+	key
+}
+
+/// Prefix of the appearance keys of `address` with the (possibly inverted) block number
+/// `block_number`, from which a page is read.
+fn appearances_start(address: &H160, block_number: u64) -> Vec<u8> {
+	let mut key = Vec::with_capacity(Appearance::KEY_LEN);
+	key.extend_from_slice(address.as_bytes());
+	key.extend_from_slice(&block_number.to_be_bytes());
+	key
+}
+
+/// A page of appearances: the first `limit` ones, extended to the end of the block of the last
+/// one, so that the transactions of a block are never split across pages.
+struct WholeBlocks {
+	limit: usize,
+	appearances: Vec<Appearance>,
+	more: bool,
+}
+
+impl WholeBlocks {
+	fn new(limit: usize) -> Self {
+		Self {
+			limit,
+			appearances: Vec::new(),
+			more: false,
+		}
+	}
+
+	/// Add the next appearance, in page order. Returns whether the page takes more.
+	fn push(&mut self, appearance: Appearance) -> bool {
+		let new_block = self
+			.appearances
+			.last()
+			.map_or(true, |last| last.block_number != appearance.block_number);
+		if new_block && self.appearances.len() >= self.limit {
+			self.more = true;
+			return false;
+		}
+		self.appearances.push(appearance);
+		true
+	}
+
+	/// The appearances of the page, and whether more were left out.
+	fn finish(self) -> (Vec<Appearance>, bool) {
+		(self.appearances, self.more)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	fn appearance(block_number: u64, transaction_index: u32) -> Appearance {
+		Appearance {
+			block_number,
+			transaction_index,
+			transaction_hash: H256::from_low_u64_be(block_number * 1000 + transaction_index as u64),
+		}
+	}
+
+	fn page(appearances: &[Appearance], limit: usize) -> (Vec<Appearance>, bool) {
+		let mut page = WholeBlocks::new(limit);
+		for appearance in appearances {
+			if !page.push(appearance.clone()) {
+				break;
+			}
+		}
+		page.finish()
+	}
+
+	fn open_backend(path: &Path, transaction_index: bool) -> Backend<Block> {
+		Backend::new(&DatabaseSettings {
+			source: DatabaseSettingsSrc::ParityDb {
+				path: path.join("db"),
+			},
+			transaction_index,
+		})
+		.expect("open Frontier database")
+	}
+
+	fn commitment(block: u8, transactions: &[u8]) -> MappingCommitment<Block> {
+		MappingCommitment {
+			block_hash: H256::repeat_byte(block),
+			ethereum_block_hash: H256::repeat_byte(block.wrapping_add(0x80)),
+			ethereum_transaction_hashes: transactions
+				.iter()
+				.map(|transaction| H256::repeat_byte(*transaction))
+				.collect(),
+		}
+	}
+
+	fn including_blocks(mapping: &MappingDb<Block>, transaction: u8) -> Vec<H256> {
+		mapping
+			.transaction_metadata(&H256::repeat_byte(transaction))
+			.unwrap()
+			.into_iter()
+			.map(|metadata| metadata.block_hash)
+			.collect()
+	}
+
+	fn indexed(
+		block_number: u64,
+		index: u32,
+		sender: H160,
+		addresses: Vec<H160>,
+	) -> IndexedTransaction {
+		IndexedTransaction {
+			hash: appearance(block_number, index).transaction_hash,
+			index,
+			sender,
+			nonce: U256::from(block_number),
+			addresses,
+			contract_address: None,
+		}
+	}
+
+	#[test]
+	fn whole_blocks_stops_at_the_limit() {
+		let appearances = vec![appearance(3, 0), appearance(2, 0), appearance(1, 0)];
+		assert_eq!(page(&appearances, 2), (appearances[..2].to_vec(), true));
+		assert_eq!(page(&appearances, 3), (appearances.clone(), false));
+		assert_eq!(page(&appearances, 10), (appearances, false));
+	}
+
+	#[test]
+	fn whole_blocks_does_not_split_a_block() {
+		let appearances = vec![
+			appearance(3, 0),
+			appearance(2, 0),
+			appearance(2, 1),
+			appearance(2, 2),
+			appearance(1, 0),
+		];
+		assert_eq!(page(&appearances, 2), (appearances[..4].to_vec(), true));
+		assert_eq!(page(&appearances, 4), (appearances[..4].to_vec(), true));
+		assert_eq!(page(&appearances, 5), (appearances, false));
+	}
+
+	#[test]
+	fn whole_blocks_with_no_limit_is_empty() {
+		assert_eq!(page(&[appearance(1, 0)], 0), (Vec::new(), true));
+		assert_eq!(page(&[], 0), (Vec::new(), false));
+	}
+
+	#[test]
+	fn appearance_keys_round_trip() {
+		let address = H160::repeat_byte(7);
+		let appearance = Appearance {
+			block_number: 0x0102_0304_0506_0708,
+			transaction_index: 0x0a0b_0c0d,
+			transaction_hash: H256::repeat_byte(9),
+		};
+
+		let key = appearance.key(&address);
+		assert_eq!(key.len(), Appearance::KEY_LEN);
+		assert_eq!(&key[..20], address.as_bytes());
+		assert_eq!(&key[20..28], &[1, 2, 3, 4, 5, 6, 7, 8]);
+		assert_eq!(&key[28..32], &[0x0a, 0x0b, 0x0c, 0x0d]);
+		assert_eq!(&key[32..], appearance.transaction_hash.as_bytes());
+		assert_eq!(Appearance::from_key(&key), Ok(appearance.clone()));
+
+		let reverse_key = appearance.reverse_key(&address);
+		assert_eq!(&reverse_key[..20], address.as_bytes());
+		assert_eq!(
+			&reverse_key[20..28],
+			&(!0x0102_0304_0506_0708u64).to_be_bytes()
+		);
+		assert_eq!(Appearance::from_reverse_key(&reverse_key), Ok(appearance));
+
+		assert!(Appearance::from_key(&key[..Appearance::KEY_LEN - 1]).is_err());
+		assert!(Appearance::from_reverse_key(&[]).is_err());
+	}
+
+	#[test]
+	fn appearance_keys_follow_chain_order() {
+		let address = H160::repeat_byte(7);
+		let appearances = vec![
+			appearance(1, 1),
+			appearance(1, 256),
+			appearance(256, 0),
+			appearance(u64::MAX, 0),
+		];
+
+		let keys: Vec<_> = appearances.iter().map(|a| a.key(&address)).collect();
+		let mut sorted = keys.clone();
+		sorted.sort();
+		assert_eq!(keys, sorted);
+
+		let reverse_keys: Vec<_> = appearances
+			.iter()
+			.rev()
+			.map(|a| a.reverse_key(&address))
+			.collect();
+		let mut sorted = reverse_keys.clone();
+		sorted.sort();
+		assert_eq!(reverse_keys, sorted);
+
+		// Keys of an address never interleave with those of the next one.
+		let next = H160::repeat_byte(8);
+		assert!(appearance(u64::MAX, u32::MAX).key(&address) < appearance(0, 0).key(&next));
+	}
+
+	#[test]
+	fn index_pages_appearances_in_both_directions() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), true);
+		let index = backend.index().expect("transaction index is enabled");
+		let sender = H160::repeat_byte(1);
+		let other = H160::repeat_byte(2);
+
+		for block_number in 1..=5 {
+			index
+				.write_block(
+					block_number,
+					vec![
+						indexed(block_number, 0, sender, vec![sender]),
+						indexed(block_number, 1, other, vec![other, sender]),
+					],
+				)
+				.unwrap();
+		}
+		// Indexing a block again changes nothing.
+		index
+			.write_block(3, vec![indexed(3, 0, sender, vec![sender])])
+			.unwrap();
+
+		let (appearances, more) = index.appearances_before(&sender, 5, 3).unwrap();
+		assert_eq!(
+			appearances,
+			vec![
+				appearance(4, 1),
+				appearance(4, 0),
+				appearance(3, 1),
+				appearance(3, 0)
+			]
+		);
+		assert!(more);
+		let (appearances, more) = index.appearances_before(&sender, 3, 10).unwrap();
+		assert_eq!(
+			appearances,
+			vec![
+				appearance(2, 1),
+				appearance(2, 0),
+				appearance(1, 1),
+				appearance(1, 0)
+			]
+		);
+		assert!(!more);
+		assert_eq!(
+			index.appearances_before(&sender, 0, 10).unwrap(),
+			(Vec::new(), false)
+		);
+
+		let (appearances, more) = index.appearances_after(&other, 2, 1).unwrap();
+		assert_eq!(appearances, vec![appearance(3, 1)]);
+		assert!(more);
+		let (appearances, more) = index.appearances_after(&other, 4, 10).unwrap();
+		assert_eq!(appearances, vec![appearance(5, 1)]);
+		assert!(!more);
+
+		assert_eq!(
+			index
+				.transactions_by_sender_and_nonce(&sender, U256::from(3))
+				.unwrap(),
+			vec![appearance(3, 0).transaction_hash]
+		);
+	}
+
+	#[test]
+	fn index_prunes_old_blocks() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), true);
+		let index = backend.index().expect("transaction index is enabled");
+		let sender = H160::repeat_byte(1);
+		let contract = H160::repeat_byte(3);
+
+		for block_number in 1..=4 {
+			let mut transaction = indexed(block_number, 0, sender, vec![sender, contract]);
+			// The same contract creation is included in blocks #2 and #3 of two forks.
+			if block_number == 2 || block_number == 3 {
+				transaction.hash = H256::repeat_byte(0xcc);
+				transaction.nonce = U256::from(2);
+				transaction.contract_address = Some(contract);
+			}
+			index.write_block(block_number, vec![transaction]).unwrap();
+		}
+
+		index.prune_until(3).unwrap();
+
+		let (appearances, more) = index.appearances_before(&sender, u64::MAX, 10).unwrap();
+		assert_eq!(
+			appearances
+				.iter()
+				.map(|a| a.block_number)
+				.collect::<Vec<_>>(),
+			vec![4, 3]
+		);
+		assert!(!more);
+		assert_eq!(
+			index.appearances_after(&contract, 0, 10).unwrap().0.len(),
+			2
+		);
+		assert_eq!(
+			index
+				.transactions_by_sender_and_nonce(&sender, U256::from(1))
+				.unwrap(),
+			Vec::<H256>::new()
+		);
+		assert_eq!(
+			index
+				.transactions_by_sender_and_nonce(&sender, U256::from(2))
+				.unwrap(),
+			vec![H256::repeat_byte(0xcc)]
+		);
+		assert_eq!(
+			index.contract_creations(&contract).unwrap(),
+			vec![(H256::repeat_byte(0xcc), sender)]
+		);
+
+		index.prune_until(5).unwrap();
+		assert!(index.contract_creations(&contract).unwrap().is_empty());
+		assert_eq!(
+			index.appearances_after(&sender, 0, 10).unwrap(),
+			(Vec::new(), false)
+		);
+	}
+
+	#[test]
+	fn index_prunes_in_batches() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), true);
+		let index = backend.index().expect("transaction index is enabled");
+		let sender = H160::repeat_byte(1);
+		let last = PRUNE_BATCH_SIZE as u64 + 2;
+
+		for block_number in 1..=last {
+			index
+				.write_block(
+					block_number,
+					vec![indexed(block_number, 0, sender, vec![sender])],
+				)
+				.unwrap();
+		}
+
+		index.prune_until(last).unwrap();
+
+		let (appearances, more) = index.appearances_after(&sender, 0, 10).unwrap();
+		assert_eq!(
+			appearances
+				.iter()
+				.map(|a| a.block_number)
+				.collect::<Vec<_>>(),
+			vec![last]
+		);
+		assert!(!more);
+		assert_eq!(
+			index
+				.transactions_by_sender_and_nonce(&sender, U256::from(last - 1))
+				.unwrap(),
+			Vec::<H256>::new()
+		);
+	}
+
+	#[test]
+	fn index_cursor_starts_unset() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		{
+			let backend = open_backend(tmp.path(), true);
+			let index = backend.index().expect("transaction index is enabled");
+
+			assert_eq!(index.cursor().unwrap(), None);
+			index.write_cursor(42).unwrap();
+			assert_eq!(index.cursor().unwrap(), Some(42));
+		}
+
+		// The indices are kept in their own database, next to the mapping one.
+		assert!(tmp.path().join("index").exists());
+		assert!(open_backend(tmp.path(), false).index().is_none());
+	}
+
+	#[test]
+	fn write_batch_is_idempotent() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), false);
+		let mapping = backend.mapping();
+
+		let block = commitment(1, &[0x11, 0x12]);
+		let (block_hash, ethereum_block_hash) = (block.block_hash, block.ethereum_block_hash);
+		let unmapped = H256::repeat_byte(2);
+		mapping.write_batch(vec![block], vec![unmapped]).unwrap();
+		mapping
+			.write_batch(vec![commitment(1, &[0x11, 0x12])], vec![unmapped])
+			.unwrap();
+
+		assert_eq!(
+			mapping.block_hash(&ethereum_block_hash).unwrap(),
+			Some(block_hash)
+		);
+		assert_eq!(including_blocks(mapping, 0x11), vec![block_hash]);
+		assert_eq!(
+			mapping
+				.transaction_metadata(&H256::repeat_byte(0x12))
+				.unwrap()
+				.into_iter()
+				.map(|metadata| metadata.ethereum_index)
+				.collect::<Vec<_>>(),
+			vec![1]
+		);
+		assert!(mapping.is_synced(&block_hash).unwrap());
+		assert!(mapping.is_synced(&unmapped).unwrap());
+	}
+
+	#[test]
+	fn write_batch_dedups_transactions_across_the_batch() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), false);
+		let mapping = backend.mapping();
+
+		// Transaction 0x11 is included in both blocks, and block 1 appears twice in the batch.
+		mapping
+			.write_batch(
+				vec![
+					commitment(1, &[0x11]),
+					commitment(2, &[0x11]),
+					commitment(1, &[0x11]),
+				],
+				Vec::new(),
+			)
+			.unwrap();
+
+		assert_eq!(
+			including_blocks(mapping, 0x11),
+			vec![H256::repeat_byte(2), H256::repeat_byte(1)]
+		);
+	}
+
+	#[test]
+	fn prune_batch_deletes_the_mappings_of_the_blocks() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), false);
+		let mapping = backend.mapping();
+
+		// Transaction 0x12 is included in both blocks, as on two forks.
+		let pruned = commitment(1, &[0x11, 0x12]);
+		let kept = commitment(2, &[0x12]);
+		let (pruned_hash, pruned_ethereum_hash) = (pruned.block_hash, pruned.ethereum_block_hash);
+		let (kept_hash, kept_ethereum_hash) = (kept.block_hash, kept.ethereum_block_hash);
+		let unmapped = H256::repeat_byte(3);
+		mapping
+			.write_batch(vec![pruned, kept], vec![unmapped])
+			.unwrap();
+
+		mapping
+			.prune_batch(vec![commitment(1, &[0x11, 0x12])], vec![unmapped])
+			.unwrap();
+
+		assert_eq!(mapping.block_hash(&pruned_ethereum_hash).unwrap(), None);
+		assert!(!mapping.is_synced(&pruned_hash).unwrap());
+		assert!(!mapping.is_synced(&unmapped).unwrap());
+		assert!(including_blocks(mapping, 0x11).is_empty());
+		assert_eq!(including_blocks(mapping, 0x12), vec![kept_hash]);
+		assert_eq!(
+			mapping.block_hash(&kept_ethereum_hash).unwrap(),
+			Some(kept_hash)
+		);
+		assert!(mapping.is_synced(&kept_hash).unwrap());
+	}
+
+	#[test]
+	fn prune_batch_keeps_an_ethereum_block_mapped_to_another_block() {
+		let tmp = tempfile::tempdir().expect("create temporary directory");
+		let backend = open_backend(tmp.path(), false);
+		let mapping = backend.mapping();
+
+		let pruned = commitment(1, &[]);
+		let ethereum_block_hash = pruned.ethereum_block_hash;
+		let remapped_hash = H256::repeat_byte(2);
+		mapping.write_hashes(pruned).unwrap();
+		mapping
+			.write_hashes(MappingCommitment {
+				block_hash: remapped_hash,
+				..commitment(1, &[])
+			})
+			.unwrap();
+
+		mapping
+			.prune_batch(vec![commitment(1, &[])], Vec::new())
+			.unwrap();
+
+		assert_eq!(
+			mapping.block_hash(&ethereum_block_hash).unwrap(),
+			Some(remapped_hash)
+		);
+		assert!(!mapping.is_synced(&H256::repeat_byte(1)).unwrap());
+		assert!(mapping.is_synced(&remapped_hash).unwrap());
+	}
 }
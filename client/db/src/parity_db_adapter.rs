@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use sp_database::{error::DatabaseError, Change, ColumnId, Database, Transaction};
+
+use crate::IterableDatabase;
+
+fn handle_err<T>(result: parity_db::Result<T>) -> T {
+	match result {
+		Ok(r) => r,
+		Err(e) => {
+			panic!("Critical database error: {:?}", e);
+		}
+	}
+}
+
+/// Wrap ParityDb database into a trait object that implements `sp_database::Database`
+pub struct DbAdapter(pub parity_db::Db);
+
+impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
+	fn commit(&self, transaction: Transaction<H>) -> Result<(), DatabaseError> {
+		let mut changes = Vec::with_capacity(transaction.0.len());
+		for change in transaction.0 {
+			match change {
+				Change::Set(col, key, value) => changes.push((col as u8, key, Some(value))),
+				Change::Remove(col, key) => changes.push((col as u8, key, None)),
+				// The mapping database never stores reference counted values.
+				Change::Store(..) | Change::Reference(..) | Change::Release(..) => {
+					return Err(DatabaseError(Box::new(std::io::Error::new(
+						std::io::ErrorKind::Unsupported,
+						"reference counted values are not supported",
+					))))
+				}
+			}
+		}
+
+		self.0
+			.commit(changes)
+			.map_err(|e| DatabaseError(Box::new(e)))
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		handle_err(self.0.get(col as u8, key))
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		handle_err(self.0.get_size(col as u8, key)).is_some()
+	}
+
+	fn value_size(&self, col: ColumnId, key: &[u8]) -> Option<usize> {
+		handle_err(self.0.get_size(col as u8, key)).map(|s| s as usize)
+	}
+}
+
+impl IterableDatabase for DbAdapter {
+	fn for_each_entry(&self, col: u32, f: &mut dyn FnMut(&[u8], &[u8])) -> Result<(), String> {
+		let mut iter = self.0.iter(col as u8).map_err(|err| format!("{}", err))?;
+		iter.seek(&[]).map_err(|err| format!("{}", err))?;
+		while let Some((key, value)) = iter.next().map_err(|err| format!("{}", err))? {
+			f(&key, &value);
+		}
+		Ok(())
+	}
+
+	fn for_each_entry_from(
+		&self,
+		col: u32,
+		prefix: &[u8],
+		start: &[u8],
+		f: &mut dyn FnMut(&[u8], &[u8]) -> bool,
+	) -> Result<(), String> {
+		let mut iter = self.0.iter(col as u8).map_err(|err| format!("{}", err))?;
+		iter.seek(start.max(prefix))
+			.map_err(|err| format!("{}", err))?;
+		while let Some((key, value)) = iter.next().map_err(|err| format!("{}", err))? {
+			if !key.starts_with(prefix) || !f(&key, &value) {
+				break;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use sp_database::{error::DatabaseError, Change, ColumnId, Database, Transaction};
+
+use crate::{DbHash, IterableDatabase};
+
+fn handle_err<T>(result: std::io::Result<T>) -> T {
+	match result {
+		Ok(r) => r,
+		Err(e) => {
+			panic!("Critical database error: {:?}", e);
+		}
+	}
+}
+
+/// Wrap RocksDB database into a trait object that implements `sp_database::Database`
+pub struct DbAdapter(pub kvdb_rocksdb::Database);
+
+impl Database<DbHash> for DbAdapter {
+	fn commit(&self, transaction: Transaction<DbHash>) -> Result<(), DatabaseError> {
+		let mut tx = kvdb::DBTransaction::new();
+		for change in transaction.0 {
+			match change {
+				Change::Set(col, key, value) => tx.put_vec(col, &key, value),
+				Change::Remove(col, key) => tx.delete(col, &key),
+				// The mapping database never stores reference counted values.
+				Change::Store(..) | Change::Reference(..) | Change::Release(..) => {
+					return Err(DatabaseError(Box::new(std::io::Error::new(
+						std::io::ErrorKind::Unsupported,
+						"reference counted values are not supported",
+					))))
+				}
+			}
+		}
+
+		self.0.write(tx).map_err(|e| DatabaseError(Box::new(e)))
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		handle_err(self.0.get(col, key))
+	}
+}
+
+impl IterableDatabase for DbAdapter {
+	fn for_each_entry(&self, col: u32, f: &mut dyn FnMut(&[u8], &[u8])) -> Result<(), String> {
+		for (key, value) in self.0.iter(col) {
+			f(&key, &value);
+		}
+		Ok(())
+	}
+
+	fn for_each_entry_from(
+		&self,
+		col: u32,
+		prefix: &[u8],
+		start: &[u8],
+		f: &mut dyn FnMut(&[u8], &[u8]) -> bool,
+	) -> Result<(), String> {
+		// kvdb-rocksdb only seeks to the prefix it iterates over, so seek to each range holding
+		// keys from `start` in turn rather than walking the keys below it.
+		for seek in seek_prefixes(prefix, start) {
+			for (key, value) in self.0.iter_with_prefix(col, &seek) {
+				if !f(&key, &value) {
+					return Ok(());
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Prefixes of the key ranges holding, in key order, the keys starting with `prefix` that are
+/// not below `start`: the keys starting with `start`, then for each byte of `start` past
+/// `prefix`, from the last one, the keys sharing the bytes before it and greater at it.
+fn seek_prefixes<'a>(prefix: &'a [u8], start: &'a [u8]) -> impl Iterator<Item = Vec<u8>> + 'a {
+	let start = if start.starts_with(prefix) {
+		Some(start)
+	} else if start < prefix {
+		Some(prefix)
+	} else {
+		None
+	};
+	start.into_iter().flat_map(move |start| {
+		let greater = (prefix.len()..start.len()).rev().flat_map(move |i| {
+			(start[i]..u8::MAX).map(move |byte| {
+				let mut seek = start[..i].to_vec();
+				seek.push(byte + 1);
+				seek
+			})
+		});
+		std::iter::once(start.to_vec()).chain(greater)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn seek_prefixes_cover_the_keys_from_start() {
+		let seeks = seek_prefixes(&[1], &[1, 2, 254]).collect::<Vec<_>>();
+		let mut expected = vec![vec![1, 2, 254], vec![1, 2, 255]];
+		expected.extend((3..=255).map(|byte| vec![1, byte]));
+		assert_eq!(seeks, expected);
+
+		assert_eq!(
+			seek_prefixes(&[1], &[0, 9]).collect::<Vec<_>>(),
+			vec![vec![1]]
+		);
+		assert_eq!(seek_prefixes(&[1], &[2]).count(), 0);
+	}
+}
@@ -16,26 +16,91 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
-use crate::{Database, DatabaseSettings, DatabaseSettingsSrc, DbHash};
+use crate::{DatabaseSettings, DatabaseSettingsSrc, IterableDatabase};
 
-pub fn open_database(config: &DatabaseSettings) -> Result<Arc<dyn Database<DbHash>>, String> {
-	let db: Arc<dyn Database<DbHash>> = match &config.source {
+/// Number of entries copied per ParityDb commit when migrating.
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+pub fn open_database(config: &DatabaseSettings) -> Result<Arc<dyn IterableDatabase>, String> {
+	let db: Arc<dyn IterableDatabase> = match &config.source {
 		DatabaseSettingsSrc::RocksDb {
 			path,
 			cache_size: _,
-		} => {
-			let db_config = kvdb_rocksdb::DatabaseConfig::with_columns(crate::columns::NUM_COLUMNS);
-			let path = path
-				.to_str()
-				.ok_or_else(|| "Invalid database path".to_string())?;
-
-			let db = kvdb_rocksdb::Database::open(&db_config, &path)
-				.map_err(|err| format!("{}", err))?;
-			sp_database::as_database(db)
-		}
+		} => Arc::new(crate::rocksdb_adapter::DbAdapter(open_kvdb_rocksdb(path)?)),
+		DatabaseSettingsSrc::ParityDb { path } => Arc::new(crate::parity_db_adapter::DbAdapter(
+			open_parity_db(path, crate::columns::NUM_COLUMNS)?,
+		)),
 	};
 
 	Ok(db)
 }
+
+/// Open the transaction indices, a ParityDb database in an `index` directory next to the mapping
+/// database of `source`, whatever its kind.
+pub(crate) fn open_index_database(
+	source: &DatabaseSettingsSrc,
+) -> Result<Arc<dyn IterableDatabase>, String> {
+	let path = source
+		.path()
+		.ok_or_else(|| "The transaction indices need a database on disk".to_string())?
+		.with_file_name("index");
+
+	Ok(Arc::new(crate::parity_db_adapter::DbAdapter(
+		open_parity_db(&path, crate::index_columns::NUM_COLUMNS)?,
+	)))
+}
+
+fn open_kvdb_rocksdb(path: &Path) -> Result<kvdb_rocksdb::Database, String> {
+	let db_config = kvdb_rocksdb::DatabaseConfig::with_columns(crate::columns::NUM_COLUMNS);
+	let path = path
+		.to_str()
+		.ok_or_else(|| "Invalid database path".to_string())?;
+
+	kvdb_rocksdb::Database::open(&db_config, &path).map_err(|err| format!("{}", err))
+}
+
+fn open_parity_db(path: &Path, num_columns: u32) -> Result<parity_db::Db, String> {
+	let mut config = parity_db::Options::with_columns(path, num_columns as u8);
+	// Keep the columns ordered, so that their entries can be iterated.
+	for column in config.columns.iter_mut() {
+		column.btree_index = true;
+	}
+
+	parity_db::Db::open_or_create(&config).map_err(|err| format!("{}", err))
+}
+
+/// Copy every entry of the RocksDB database at `from` into a new ParityDb database at `to`.
+/// Returns the number of entries copied.
+pub fn migrate_rocksdb_to_paritydb(from: &Path, to: &Path) -> Result<u64, String> {
+	if !from.exists() {
+		return Err(format!("No RocksDB database at {}", from.display()));
+	}
+	if to.exists() {
+		return Err(format!("{} already exists", to.display()));
+	}
+
+	let source = open_kvdb_rocksdb(from)?;
+	let destination = open_parity_db(to, crate::columns::NUM_COLUMNS)?;
+
+	let mut copied = 0;
+	for column in 0..crate::columns::NUM_COLUMNS {
+		let mut batch = Vec::with_capacity(MIGRATION_BATCH_SIZE);
+		for (key, value) in source.iter(column) {
+			batch.push((column as u8, key.into_vec(), Some(value.into_vec())));
+			if batch.len() == MIGRATION_BATCH_SIZE {
+				copied += batch.len() as u64;
+				destination
+					.commit(batch.drain(..))
+					.map_err(|err| format!("{}", err))?;
+			}
+		}
+		copied += batch.len() as u64;
+		destination
+			.commit(batch)
+			.map_err(|err| format!("{}", err))?;
+	}
+
+	Ok(copied)
+}
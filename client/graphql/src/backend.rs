@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use ethereum_types::{H160, H256, U256, U64};
+use jsonrpc_core::{BoxFuture, Result};
+use sc_client_api::backend::{Backend, StateBackend, StorageProvider};
+use sc_network::ExHashT;
+use sc_transaction_pool::ChainApi;
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT};
+
+use fc_rpc::{CallOutcome, EthApi};
+use fc_rpc_core::{types::*, EthApi as EthApiT, EthFilterApi as EthFilterApiT};
+use fp_rpc::{ConvertTransaction, ConvertTransactionRuntimeApi, EthereumRuntimeRPCApi};
+
+/// The Ethereum data the GraphQL resolvers are built on. It keeps the schema independent of the
+/// client types, which `RpcBackend` wraps.
+pub trait EthereumBackend: Send + Sync {
+	fn block_number(&self) -> Result<U256>;
+
+	fn block_by_number(
+		&self,
+		number: BlockNumber,
+		full: bool,
+	) -> BoxFuture<Result<Option<RichBlock>>>;
+
+	fn block_by_hash(&self, hash: H256, full: bool) -> BoxFuture<Result<Option<RichBlock>>>;
+
+	fn transaction_by_hash(&self, hash: H256) -> BoxFuture<Result<Option<Transaction>>>;
+
+	fn transaction_receipt(&self, hash: H256) -> BoxFuture<Result<Option<Receipt>>>;
+
+	fn block_receipts(&self, number: BlockNumber) -> BoxFuture<Result<Option<Vec<Receipt>>>>;
+
+	fn logs(&self, filter: Filter) -> BoxFuture<Result<Vec<Log>>>;
+
+	fn balance(&self, address: H160, number: BlockNumber) -> Result<U256>;
+
+	fn transaction_count(&self, address: H160, number: BlockNumber) -> Result<U256>;
+
+	fn code_at(&self, address: H160, number: BlockNumber) -> Result<Bytes>;
+
+	fn storage_at(&self, address: H160, index: U256, number: BlockNumber) -> Result<H256>;
+
+	fn call(&self, request: CallRequest, number: BlockNumber) -> Result<CallOutcome>;
+
+	fn estimate_gas(&self, request: CallRequest, number: BlockNumber) -> BoxFuture<Result<U256>>;
+
+	fn gas_price(&self) -> Result<U256>;
+
+	fn max_priority_fee_per_gas(&self) -> Result<U256>;
+
+	fn chain_id(&self) -> Result<Option<U64>>;
+
+	fn syncing(&self) -> Result<SyncStatus>;
+
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<Result<H256>>;
+}
+
+/// `EthereumBackend` of the `eth_*` RPC implementation, with the filter API serving logs.
+pub struct RpcBackend<E, F> {
+	eth: E,
+	filter: F,
+}
+
+impl<E, F> RpcBackend<E, F> {
+	pub fn new(eth: E, filter: F) -> Self {
+		Self { eth, filter }
+	}
+}
+
+impl<B, C, P, CT, BE, H: ExHashT, A, F> EthereumBackend
+	for RpcBackend<EthApi<B, C, P, CT, BE, H, A>, F>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: BlockBuilderApi<B> + ConvertTransactionRuntimeApi<B> + EthereumRuntimeRPCApi<B>,
+	P: TransactionPool<Block = B> + Send + Sync + 'static,
+	CT: ConvertTransaction<<B as BlockT>::Extrinsic> + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+	A: ChainApi<Block = B> + 'static,
+	F: EthFilterApiT,
+{
+	fn block_number(&self) -> Result<U256> {
+		EthApiT::block_number(&self.eth)
+	}
+
+	fn block_by_number(
+		&self,
+		number: BlockNumber,
+		full: bool,
+	) -> BoxFuture<Result<Option<RichBlock>>> {
+		EthApiT::block_by_number(&self.eth, number, full)
+	}
+
+	fn block_by_hash(&self, hash: H256, full: bool) -> BoxFuture<Result<Option<RichBlock>>> {
+		EthApiT::block_by_hash(&self.eth, hash, full)
+	}
+
+	fn transaction_by_hash(&self, hash: H256) -> BoxFuture<Result<Option<Transaction>>> {
+		EthApiT::transaction_by_hash(&self.eth, hash)
+	}
+
+	fn transaction_receipt(&self, hash: H256) -> BoxFuture<Result<Option<Receipt>>> {
+		EthApiT::transaction_receipt(&self.eth, hash)
+	}
+
+	fn block_receipts(&self, number: BlockNumber) -> BoxFuture<Result<Option<Vec<Receipt>>>> {
+		EthApiT::block_receipts(&self.eth, number)
+	}
+
+	fn logs(&self, filter: Filter) -> BoxFuture<Result<Vec<Log>>> {
+		EthFilterApiT::logs(&self.filter, filter)
+	}
+
+	fn balance(&self, address: H160, number: BlockNumber) -> Result<U256> {
+		EthApiT::balance(&self.eth, address, Some(number))
+	}
+
+	fn transaction_count(&self, address: H160, number: BlockNumber) -> Result<U256> {
+		EthApiT::transaction_count(&self.eth, address, Some(number))
+	}
+
+	fn code_at(&self, address: H160, number: BlockNumber) -> Result<Bytes> {
+		EthApiT::code_at(&self.eth, address, Some(number))
+	}
+
+	fn storage_at(&self, address: H160, index: U256, number: BlockNumber) -> Result<H256> {
+		EthApiT::storage_at(&self.eth, address, index, Some(number))
+	}
+
+	fn call(&self, request: CallRequest, number: BlockNumber) -> Result<CallOutcome> {
+		self.eth.call_outcome(request, Some(number))
+	}
+
+	fn estimate_gas(&self, request: CallRequest, number: BlockNumber) -> BoxFuture<Result<U256>> {
+		EthApiT::estimate_gas(&self.eth, request, Some(number))
+	}
+
+	fn gas_price(&self) -> Result<U256> {
+		EthApiT::gas_price(&self.eth)
+	}
+
+	fn max_priority_fee_per_gas(&self) -> Result<U256> {
+		EthApiT::max_priority_fee_per_gas(&self.eth)
+	}
+
+	fn chain_id(&self) -> Result<Option<U64>> {
+		EthApiT::chain_id(&self.eth)
+	}
+
+	fn syncing(&self) -> Result<SyncStatus> {
+		EthApiT::syncing(&self.eth)
+	}
+
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<Result<H256>> {
+		EthApiT::send_raw_transaction(&self.eth, bytes)
+	}
+}
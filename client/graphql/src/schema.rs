@@ -0,0 +1,1051 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{str::FromStr, sync::Arc};
+
+use async_graphql::{
+	Context, EmptySubscription, Error, InputObject, InputValueError, InputValueResult, Object,
+	Result, Scalar, ScalarType, Schema, SimpleObject, Value,
+};
+use ethereum_types::{H160, H256, U256};
+use tokio::sync::OnceCell;
+
+use fc_rpc::RpcGuard;
+use fc_rpc_core::types::{
+	BlockNumber, BlockTransactions, Bytes as RpcBytes, CallRequest, Filter, FilterAddress,
+	Log as RpcLog, Receipt, RichBlock, SyncStatus, Topic, Transaction as RpcTransaction,
+	VariadicValue,
+};
+
+use crate::EthereumBackend;
+
+/// Widest range of blocks a single `blocks` query returns.
+const MAX_BLOCKS_RANGE: u64 = 1000;
+
+/// Complexity multiplier of the fields returning an account, whose fields read the state.
+const ACCOUNT_COMPLEXITY: usize = 5;
+/// Complexity multiplier of the fields returning the transactions of a block.
+const TRANSACTIONS_COMPLEXITY: usize = 20;
+/// Complexity multiplier of the fields returning the logs of a block or a transaction, and of
+/// each block of a log query.
+const LOGS_COMPLEXITY: usize = 20;
+
+pub type EthSchema = Schema<Query, Mutation, EmptySubscription>;
+
+/// The EIP-1767 schema over `backend`, rejecting queries deeper than `max_depth` or more complex
+/// than `max_complexity`. Each field counts for one, times the number of blocks, transactions or
+/// logs it may return, or `ACCOUNT_COMPLEXITY` for accounts. Calls and gas estimations are
+/// subject to the EVM limits of `guard`.
+pub fn schema(
+	backend: Arc<dyn EthereumBackend>,
+	guard: Arc<RpcGuard>,
+	max_depth: usize,
+	max_complexity: usize,
+) -> EthSchema {
+	Schema::build(Query, Mutation, EmptySubscription)
+		.data(backend)
+		.data(guard)
+		.limit_depth(max_depth)
+		.limit_complexity(max_complexity)
+		.finish()
+}
+
+fn backend<'a>(ctx: &Context<'a>) -> &'a Arc<dyn EthereumBackend> {
+	ctx.data_unchecked::<Arc<dyn EthereumBackend>>()
+}
+
+fn guard<'a>(ctx: &Context<'a>) -> &'a Arc<RpcGuard> {
+	ctx.data_unchecked::<Arc<RpcGuard>>()
+}
+
+/// Number of blocks from `from` to `to` included, `MAX_BLOCKS_RANGE` if open ended, or one if
+/// both are the latest block.
+fn range_len(from: Option<u64>, to: Option<u64>) -> usize {
+	match (from, to) {
+		(None, None) => 1,
+		(Some(from), Some(to)) => to.saturating_sub(from).saturating_add(1) as usize,
+		_ => MAX_BLOCKS_RANGE as usize,
+	}
+}
+
+fn rpc_err(err: jsonrpc_core::Error) -> Error {
+	Error::new(err.message)
+}
+
+// ############################################################################
+// Scalars
+// ############################################################################
+
+/// A 32 byte binary string, hex encoded with a `0x` prefix.
+pub struct Bytes32(pub H256);
+
+#[Scalar(name = "Bytes32")]
+impl ScalarType for Bytes32 {
+	fn parse(value: Value) -> InputValueResult<Self> {
+		match &value {
+			Value::String(s) => H256::from_str(s.trim_start_matches("0x"))
+				.map(Bytes32)
+				.map_err(|_| InputValueError::custom("invalid Bytes32")),
+			_ => Err(InputValueError::expected_type(value)),
+		}
+	}
+
+	fn to_value(&self) -> Value {
+		Value::String(format!("{:?}", self.0))
+	}
+}
+
+/// A 20 byte Ethereum address, hex encoded with a `0x` prefix.
+pub struct Address(pub H160);
+
+#[Scalar(name = "Address")]
+impl ScalarType for Address {
+	fn parse(value: Value) -> InputValueResult<Self> {
+		match &value {
+			Value::String(s) => H160::from_str(s.trim_start_matches("0x"))
+				.map(Address)
+				.map_err(|_| InputValueError::custom("invalid Address")),
+			_ => Err(InputValueError::expected_type(value)),
+		}
+	}
+
+	fn to_value(&self) -> Value {
+		Value::String(format!("{:?}", self.0))
+	}
+}
+
+/// An arbitrary length binary string, hex encoded with a `0x` prefix.
+pub struct Bytes(pub Vec<u8>);
+
+#[Scalar(name = "Bytes")]
+impl ScalarType for Bytes {
+	fn parse(value: Value) -> InputValueResult<Self> {
+		match &value {
+			Value::String(s) => {
+				let s = s.strip_prefix("0x").unwrap_or(s);
+				if s.len() % 2 != 0 {
+					return Err(InputValueError::custom("invalid Bytes"));
+				}
+				(0..s.len())
+					.step_by(2)
+					.map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+					.collect::<std::result::Result<Vec<_>, _>>()
+					.map(Bytes)
+					.map_err(|_| InputValueError::custom("invalid Bytes"))
+			}
+			_ => Err(InputValueError::expected_type(value)),
+		}
+	}
+
+	fn to_value(&self) -> Value {
+		let hex: String = self.0.iter().map(|byte| format!("{:02x}", byte)).collect();
+		Value::String(format!("0x{}", hex))
+	}
+}
+
+/// A 256 bit unsigned integer, hex encoded with a `0x` prefix. Decimal strings are accepted as
+/// input.
+pub struct BigInt(pub U256);
+
+#[Scalar(name = "BigInt")]
+impl ScalarType for BigInt {
+	fn parse(value: Value) -> InputValueResult<Self> {
+		let parsed = match &value {
+			Value::String(s) => match s.strip_prefix("0x") {
+				Some(hex) => U256::from_str_radix(hex, 16).ok(),
+				None => U256::from_dec_str(s).ok(),
+			},
+			Value::Number(n) => n.as_u64().map(U256::from),
+			_ => return Err(InputValueError::expected_type(value)),
+		};
+		parsed
+			.map(BigInt)
+			.ok_or_else(|| InputValueError::custom("invalid BigInt"))
+	}
+
+	fn to_value(&self) -> Value {
+		Value::String(format!("{:#x}", self.0))
+	}
+}
+
+/// A 64 bit unsigned integer. Hex strings with a `0x` prefix are accepted as input.
+pub struct Long(pub u64);
+
+#[Scalar(name = "Long")]
+impl ScalarType for Long {
+	fn parse(value: Value) -> InputValueResult<Self> {
+		let parsed = match &value {
+			Value::Number(n) => n.as_u64(),
+			Value::String(s) => match s.strip_prefix("0x") {
+				Some(hex) => u64::from_str_radix(hex, 16).ok(),
+				None => s.parse().ok(),
+			},
+			_ => return Err(InputValueError::expected_type(value)),
+		};
+		parsed
+			.map(Long)
+			.ok_or_else(|| InputValueError::custom("invalid Long"))
+	}
+
+	fn to_value(&self) -> Value {
+		Value::Number(self.0.into())
+	}
+}
+
+// ############################################################################
+// Inputs
+// ############################################################################
+
+/// Arguments of a call or gas estimation.
+#[derive(InputObject)]
+pub struct CallData {
+	from: Option<Address>,
+	to: Option<Address>,
+	gas: Option<Long>,
+	gas_price: Option<BigInt>,
+	max_fee_per_gas: Option<BigInt>,
+	max_priority_fee_per_gas: Option<BigInt>,
+	value: Option<BigInt>,
+	data: Option<Bytes>,
+}
+
+impl From<CallData> for CallRequest {
+	fn from(data: CallData) -> Self {
+		CallRequest {
+			from: data.from.map(|a| a.0),
+			to: data.to.map(|a| a.0),
+			gas_price: data.gas_price.map(|v| v.0),
+			max_fee_per_gas: data.max_fee_per_gas.map(|v| v.0),
+			max_priority_fee_per_gas: data.max_priority_fee_per_gas.map(|v| v.0),
+			gas: data.gas.map(|v| U256::from(v.0)),
+			value: data.value.map(|v| v.0),
+			data: data.data.map(|d| RpcBytes(d.0)),
+			nonce: None,
+			access_list: None,
+			transaction_type: None,
+		}
+	}
+}
+
+/// Log filter within a single block.
+#[derive(InputObject)]
+pub struct BlockFilterCriteria {
+	addresses: Option<Vec<Address>>,
+	topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// Log filter over a range of blocks.
+#[derive(InputObject)]
+pub struct FilterCriteria {
+	from_block: Option<Long>,
+	to_block: Option<Long>,
+	addresses: Option<Vec<Address>>,
+	topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+fn filter_address(addresses: Option<Vec<Address>>) -> Option<FilterAddress> {
+	match addresses {
+		Some(addresses) if !addresses.is_empty() => Some(VariadicValue::Multiple(
+			addresses.into_iter().map(|a| a.0).collect(),
+		)),
+		_ => None,
+	}
+}
+
+/// Topic positions are matched by any of their hashes, and an empty position matches any topic.
+fn filter_topics(topics: Option<Vec<Vec<Bytes32>>>) -> Option<Topic> {
+	topics.map(|topics| {
+		VariadicValue::Multiple(
+			topics
+				.into_iter()
+				.map(|position| match position.len() {
+					0 => None,
+					_ => Some(VariadicValue::Multiple(
+						position.into_iter().map(|topic| Some(topic.0)).collect(),
+					)),
+				})
+				.collect(),
+		)
+	})
+}
+
+// ############################################################################
+// Objects
+// ############################################################################
+
+/// An Ethereum account at a block.
+pub struct Account {
+	address: H160,
+	number: BlockNumber,
+}
+
+#[Object]
+impl Account {
+	async fn address(&self) -> Address {
+		Address(self.address)
+	}
+
+	async fn balance(&self, ctx: &Context<'_>) -> Result<BigInt> {
+		backend(ctx)
+			.balance(self.address, self.number)
+			.map(BigInt)
+			.map_err(rpc_err)
+	}
+
+	async fn transaction_count(&self, ctx: &Context<'_>) -> Result<Long> {
+		backend(ctx)
+			.transaction_count(self.address, self.number)
+			.map(|count| Long(count.low_u64()))
+			.map_err(rpc_err)
+	}
+
+	async fn code(&self, ctx: &Context<'_>) -> Result<Bytes> {
+		backend(ctx)
+			.code_at(self.address, self.number)
+			.map(|code| Bytes(code.0))
+			.map_err(rpc_err)
+	}
+
+	async fn storage(&self, ctx: &Context<'_>, slot: Bytes32) -> Result<Bytes32> {
+		backend(ctx)
+			.storage_at(
+				self.address,
+				U256::from_big_endian(slot.0.as_bytes()),
+				self.number,
+			)
+			.map(Bytes32)
+			.map_err(rpc_err)
+	}
+}
+
+/// A log emitted by a transaction.
+pub struct Log {
+	inner: RpcLog,
+}
+
+#[Object]
+impl Log {
+	/// Index of the log in its block.
+	async fn index(&self) -> i32 {
+		self.inner.log_index.unwrap_or_default().low_u32() as i32
+	}
+
+	/// The account that emitted the log, at block `block`, or at the block of the log.
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn account(&self, block: Option<Long>) -> Account {
+		Account {
+			address: self.inner.address,
+			number: block_number_or(block, self.inner.block_number),
+		}
+	}
+
+	async fn topics(&self) -> Vec<Bytes32> {
+		self.inner.topics.iter().cloned().map(Bytes32).collect()
+	}
+
+	async fn data(&self) -> Bytes {
+		Bytes(self.inner.data.0.clone())
+	}
+
+	async fn transaction(&self, ctx: &Context<'_>) -> Result<Option<Transaction>> {
+		match self.inner.transaction_hash {
+			Some(hash) => transaction(ctx, hash).await,
+			None => Ok(None),
+		}
+	}
+}
+
+/// An Ethereum transaction.
+pub struct Transaction {
+	inner: RpcTransaction,
+	receipt: OnceCell<Option<Receipt>>,
+}
+
+impl Transaction {
+	fn new(inner: RpcTransaction, receipt: Option<Receipt>) -> Self {
+		let cell = OnceCell::new();
+		if let Some(receipt) = receipt {
+			let _ = cell.set(Some(receipt));
+		}
+		Self {
+			inner,
+			receipt: cell,
+		}
+	}
+
+	async fn receipt(&self, ctx: &Context<'_>) -> Result<Option<&Receipt>> {
+		let receipt = self
+			.receipt
+			.get_or_try_init(|| async {
+				backend(ctx)
+					.transaction_receipt(self.inner.hash)
+					.await
+					.map_err(rpc_err)
+			})
+			.await?;
+		Ok(receipt.as_ref())
+	}
+}
+
+#[Object]
+impl Transaction {
+	async fn hash(&self) -> Bytes32 {
+		Bytes32(self.inner.hash)
+	}
+
+	async fn nonce(&self) -> Long {
+		Long(self.inner.nonce.low_u64())
+	}
+
+	async fn index(&self) -> Option<i32> {
+		self.inner
+			.transaction_index
+			.map(|index| index.low_u32() as i32)
+	}
+
+	/// The sender, at block `block`, or at the latest block.
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn from(&self, block: Option<Long>) -> Account {
+		Account {
+			address: self.inner.from,
+			number: block_number_or(block, None),
+		}
+	}
+
+	/// The recipient, at block `block`, or at the latest block. Null for contract creations.
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn to(&self, block: Option<Long>) -> Option<Account> {
+		self.inner.to.map(|address| Account {
+			address,
+			number: block_number_or(block, None),
+		})
+	}
+
+	async fn value(&self) -> BigInt {
+		BigInt(self.inner.value)
+	}
+
+	async fn gas_price(&self) -> BigInt {
+		BigInt(self.inner.gas_price.unwrap_or_default())
+	}
+
+	async fn max_fee_per_gas(&self) -> Option<BigInt> {
+		self.inner.max_fee_per_gas.map(BigInt)
+	}
+
+	async fn max_priority_fee_per_gas(&self) -> Option<BigInt> {
+		self.inner.max_priority_fee_per_gas.map(BigInt)
+	}
+
+	async fn effective_gas_price(&self, ctx: &Context<'_>) -> Result<Option<BigInt>> {
+		Ok(self
+			.receipt(ctx)
+			.await?
+			.map(|receipt| BigInt(receipt.effective_gas_price)))
+	}
+
+	async fn gas(&self) -> Long {
+		Long(self.inner.gas.low_u64())
+	}
+
+	async fn input_data(&self) -> Bytes {
+		Bytes(self.inner.input.0.clone())
+	}
+
+	/// The block that includes the transaction. Null while it is pending.
+	async fn block(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+		match self.inner.block_hash {
+			Some(hash) => block_by_hash(ctx, hash).await,
+			None => Ok(None),
+		}
+	}
+
+	/// 1 if the transaction succeeded, 0 if it failed. Null while it is pending.
+	async fn status(&self, ctx: &Context<'_>) -> Result<Option<Long>> {
+		Ok(self
+			.receipt(ctx)
+			.await?
+			.and_then(|receipt| receipt.status_code)
+			.map(|status| Long(status.low_u64())))
+	}
+
+	async fn gas_used(&self, ctx: &Context<'_>) -> Result<Option<Long>> {
+		Ok(self
+			.receipt(ctx)
+			.await?
+			.and_then(|receipt| receipt.gas_used)
+			.map(|gas| Long(gas.low_u64())))
+	}
+
+	async fn cumulative_gas_used(&self, ctx: &Context<'_>) -> Result<Option<Long>> {
+		Ok(self
+			.receipt(ctx)
+			.await?
+			.map(|receipt| Long(receipt.cumulative_gas_used.low_u64())))
+	}
+
+	/// The contract the transaction created, at block `block`, or at the latest block.
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn created_contract(
+		&self,
+		ctx: &Context<'_>,
+		block: Option<Long>,
+	) -> Result<Option<Account>> {
+		Ok(self
+			.receipt(ctx)
+			.await?
+			.and_then(|receipt| receipt.contract_address)
+			.map(|address| Account {
+				address,
+				number: block_number_or(block, None),
+			}))
+	}
+
+	#[graphql(complexity = "LOGS_COMPLEXITY * child_complexity")]
+	async fn logs(&self, ctx: &Context<'_>) -> Result<Option<Vec<Log>>> {
+		Ok(self.receipt(ctx).await?.map(|receipt| {
+			receipt
+				.logs
+				.iter()
+				.cloned()
+				.map(|inner| Log { inner })
+				.collect()
+		}))
+	}
+
+	async fn r(&self) -> BigInt {
+		BigInt(self.inner.r)
+	}
+
+	async fn s(&self) -> BigInt {
+		BigInt(self.inner.s)
+	}
+
+	async fn v(&self) -> BigInt {
+		BigInt(self.inner.v)
+	}
+
+	#[graphql(name = "type")]
+	async fn transaction_type(&self) -> Option<i32> {
+		self.inner
+			.transaction_type
+			.map(|transaction_type| transaction_type.low_u32() as i32)
+	}
+
+	async fn access_list(&self) -> Option<Vec<AccessTuple>> {
+		self.inner.access_list.as_ref().map(|access_list| {
+			access_list
+				.iter()
+				.map(|item| AccessTuple {
+					address: Address(item.address),
+					storage_keys: item.storage_keys.iter().cloned().map(Bytes32).collect(),
+				})
+				.collect()
+		})
+	}
+}
+
+/// An address and the storage keys a transaction pre-pays the access of.
+#[derive(SimpleObject)]
+pub struct AccessTuple {
+	address: Address,
+	storage_keys: Vec<Bytes32>,
+}
+
+/// An Ethereum block.
+pub struct Block {
+	inner: RichBlock,
+	receipts: OnceCell<Option<Vec<Receipt>>>,
+}
+
+impl Block {
+	fn new(inner: RichBlock) -> Self {
+		Self {
+			inner,
+			receipts: OnceCell::new(),
+		}
+	}
+
+	fn block_number(&self) -> u64 {
+		self.inner.header.number.unwrap_or_default().low_u64()
+	}
+
+	async fn receipts(&self, ctx: &Context<'_>) -> Result<Option<&Vec<Receipt>>> {
+		let receipts = self
+			.receipts
+			.get_or_try_init(|| async {
+				backend(ctx)
+					.block_receipts(BlockNumber::Num(self.block_number()))
+					.await
+					.map_err(rpc_err)
+			})
+			.await?;
+		Ok(receipts.as_ref())
+	}
+
+	fn full_transactions(&self) -> &[RpcTransaction] {
+		match &self.inner.transactions {
+			BlockTransactions::Full(transactions) => transactions,
+			BlockTransactions::Hashes(_) => &[],
+		}
+	}
+}
+
+/// Fields of `Transaction` that are read from its receipt.
+const RECEIPT_FIELDS: &[&str] = &[
+	"effectiveGasPrice",
+	"status",
+	"gasUsed",
+	"cumulativeGasUsed",
+	"createdContract",
+	"logs",
+];
+
+#[Object]
+impl Block {
+	async fn number(&self) -> Long {
+		Long(self.block_number())
+	}
+
+	async fn hash(&self) -> Bytes32 {
+		Bytes32(self.inner.header.hash.unwrap_or_default())
+	}
+
+	/// The parent block. Null for the genesis block.
+	async fn parent(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+		if self.block_number() == 0 {
+			return Ok(None);
+		}
+		block_by_hash(ctx, self.inner.header.parent_hash).await
+	}
+
+	async fn nonce(&self) -> Bytes {
+		Bytes(
+			self.inner
+				.header
+				.seal_fields
+				.get(1)
+				.map(|nonce| nonce.0.clone())
+				.unwrap_or_default(),
+		)
+	}
+
+	async fn transactions_root(&self) -> Bytes32 {
+		Bytes32(self.inner.header.transactions_root)
+	}
+
+	async fn transaction_count(&self) -> i32 {
+		self.full_transactions().len() as i32
+	}
+
+	async fn state_root(&self) -> Bytes32 {
+		Bytes32(self.inner.header.state_root)
+	}
+
+	async fn receipts_root(&self) -> Bytes32 {
+		Bytes32(self.inner.header.receipts_root)
+	}
+
+	/// The block author, at block `block`, or at this block.
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn miner(&self, block: Option<Long>) -> Account {
+		Account {
+			address: self.inner.header.miner,
+			number: block.map_or(BlockNumber::Num(self.block_number()), |block| {
+				BlockNumber::Num(block.0)
+			}),
+		}
+	}
+
+	async fn extra_data(&self) -> Bytes {
+		Bytes(self.inner.header.extra_data.0.clone())
+	}
+
+	async fn gas_limit(&self) -> Long {
+		Long(self.inner.header.gas_limit.low_u64())
+	}
+
+	async fn gas_used(&self) -> Long {
+		Long(self.inner.header.gas_used.low_u64())
+	}
+
+	async fn base_fee_per_gas(&self) -> Option<BigInt> {
+		self.inner.base_fee_per_gas.map(BigInt)
+	}
+
+	async fn timestamp(&self) -> Long {
+		Long(self.inner.header.timestamp.low_u64())
+	}
+
+	async fn logs_bloom(&self) -> Bytes {
+		Bytes(self.inner.header.logs_bloom.as_bytes().to_vec())
+	}
+
+	async fn mix_hash(&self) -> Bytes32 {
+		Bytes32(
+			self.inner
+				.header
+				.seal_fields
+				.get(0)
+				.map(|mix_hash| H256::from_slice(&mix_hash.0))
+				.unwrap_or_default(),
+		)
+	}
+
+	async fn difficulty(&self) -> BigInt {
+		BigInt(self.inner.header.difficulty)
+	}
+
+	async fn total_difficulty(&self) -> BigInt {
+		BigInt(self.inner.total_difficulty)
+	}
+
+	async fn ommer_count(&self) -> i32 {
+		0
+	}
+
+	async fn ommers(&self) -> Vec<Block> {
+		Vec::new()
+	}
+
+	async fn ommer_at(&self, _index: i32) -> Option<Block> {
+		None
+	}
+
+	async fn ommer_hash(&self) -> Bytes32 {
+		Bytes32(self.inner.header.uncles_hash)
+	}
+
+	/// The transactions of the block. Their receipts are read at once when any receipt field is
+	/// selected.
+	#[graphql(complexity = "TRANSACTIONS_COMPLEXITY * child_complexity")]
+	async fn transactions(&self, ctx: &Context<'_>) -> Result<Vec<Transaction>> {
+		let look_ahead = ctx.look_ahead();
+		let receipts = if RECEIPT_FIELDS
+			.iter()
+			.any(|field| look_ahead.field(field).exists())
+		{
+			self.receipts(ctx).await?
+		} else {
+			None
+		};
+
+		Ok(self
+			.full_transactions()
+			.iter()
+			.enumerate()
+			.map(|(index, transaction)| {
+				Transaction::new(
+					transaction.clone(),
+					receipts.and_then(|receipts| receipts.get(index).cloned()),
+				)
+			})
+			.collect())
+	}
+
+	async fn transaction_at(&self, index: i32) -> Option<Transaction> {
+		self.full_transactions()
+			.get(index as usize)
+			.map(|transaction| Transaction::new(transaction.clone(), None))
+	}
+
+	#[graphql(complexity = "LOGS_COMPLEXITY * child_complexity")]
+	async fn logs(&self, ctx: &Context<'_>, filter: BlockFilterCriteria) -> Result<Vec<Log>> {
+		logs(
+			ctx,
+			Filter {
+				from_block: None,
+				to_block: None,
+				block_hash: self.inner.header.hash,
+				address: filter_address(filter.addresses),
+				topics: filter_topics(filter.topics),
+			},
+		)
+		.await
+	}
+
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn account(&self, address: Address) -> Account {
+		Account {
+			address: address.0,
+			number: BlockNumber::Num(self.block_number()),
+		}
+	}
+
+	async fn call(&self, ctx: &Context<'_>, data: CallData) -> Result<CallResult> {
+		call(ctx, data, BlockNumber::Num(self.block_number()))
+	}
+
+	async fn estimate_gas(&self, ctx: &Context<'_>, data: CallData) -> Result<Long> {
+		estimate_gas(ctx, data, BlockNumber::Num(self.block_number())).await
+	}
+}
+
+/// The outcome of a call.
+#[derive(SimpleObject)]
+pub struct CallResult {
+	/// The output of the call, or its revert data.
+	data: Bytes,
+	gas_used: Long,
+	/// 1 if the call succeeded, 0 if it failed.
+	status: Long,
+}
+
+/// Progress of the major sync.
+#[derive(SimpleObject)]
+pub struct SyncState {
+	starting_block: Long,
+	current_block: Long,
+	highest_block: Long,
+}
+
+/// The block being built from the transaction pool.
+pub struct Pending;
+
+#[Object]
+impl Pending {
+	async fn transaction_count(&self, ctx: &Context<'_>) -> Result<i32> {
+		Ok(pending_block(ctx)
+			.await?
+			.map_or(0, |block| block.full_transactions().len() as i32))
+	}
+
+	#[graphql(complexity = "TRANSACTIONS_COMPLEXITY * child_complexity")]
+	async fn transactions(&self, ctx: &Context<'_>) -> Result<Option<Vec<Transaction>>> {
+		Ok(pending_block(ctx).await?.map(|block| {
+			block
+				.full_transactions()
+				.iter()
+				.map(|transaction| Transaction::new(transaction.clone(), None))
+				.collect()
+		}))
+	}
+
+	#[graphql(complexity = "ACCOUNT_COMPLEXITY * child_complexity")]
+	async fn account(&self, address: Address) -> Account {
+		Account {
+			address: address.0,
+			number: BlockNumber::Pending,
+		}
+	}
+
+	async fn call(&self, ctx: &Context<'_>, data: CallData) -> Result<CallResult> {
+		call(ctx, data, BlockNumber::Pending)
+	}
+
+	async fn estimate_gas(&self, ctx: &Context<'_>, data: CallData) -> Result<Long> {
+		estimate_gas(ctx, data, BlockNumber::Pending).await
+	}
+}
+
+// ############################################################################
+// Roots
+// ############################################################################
+
+pub struct Query;
+
+#[Object]
+impl Query {
+	/// The block with `number` or `hash`, or the latest block when neither is given.
+	async fn block(
+		&self,
+		ctx: &Context<'_>,
+		number: Option<Long>,
+		hash: Option<Bytes32>,
+	) -> Result<Option<Block>> {
+		match (number, hash) {
+			(Some(_), Some(_)) => Err(Error::new("only one of number or hash must be specified")),
+			(_, Some(hash)) => block_by_hash(ctx, hash.0).await,
+			(Some(number), None) => block_by_number(ctx, BlockNumber::Num(number.0)).await,
+			(None, None) => block_by_number(ctx, BlockNumber::Latest).await,
+		}
+	}
+
+	/// The blocks from `from` to `to` included, or to the latest block.
+	#[graphql(
+		complexity = "range_len(Some(from.0), to.as_ref().map(|to| to.0)) * child_complexity"
+	)]
+	async fn blocks(&self, ctx: &Context<'_>, from: Long, to: Option<Long>) -> Result<Vec<Block>> {
+		let to = match to {
+			Some(to) => to.0,
+			None => backend(ctx).block_number().map_err(rpc_err)?.low_u64(),
+		};
+		if to < from.0 {
+			return Ok(Vec::new());
+		}
+		if to - from.0 >= MAX_BLOCKS_RANGE {
+			return Err(Error::new(format!(
+				"block range is limited to {} blocks",
+				MAX_BLOCKS_RANGE
+			)));
+		}
+
+		let mut blocks = Vec::new();
+		for number in from.0..=to {
+			match block_by_number(ctx, BlockNumber::Num(number)).await? {
+				Some(block) => blocks.push(block),
+				None => break,
+			}
+		}
+		Ok(blocks)
+	}
+
+	async fn pending(&self) -> Pending {
+		Pending
+	}
+
+	async fn transaction(&self, ctx: &Context<'_>, hash: Bytes32) -> Result<Option<Transaction>> {
+		transaction(ctx, hash.0).await
+	}
+
+	#[graphql(complexity = "range_len(
+		filter.from_block.as_ref().map(|from| from.0),
+		filter.to_block.as_ref().map(|to| to.0),
+	) * LOGS_COMPLEXITY * child_complexity")]
+	async fn logs(&self, ctx: &Context<'_>, filter: FilterCriteria) -> Result<Vec<Log>> {
+		logs(
+			ctx,
+			Filter {
+				from_block: filter.from_block.map(|number| BlockNumber::Num(number.0)),
+				to_block: filter.to_block.map(|number| BlockNumber::Num(number.0)),
+				block_hash: None,
+				address: filter_address(filter.addresses),
+				topics: filter_topics(filter.topics),
+			},
+		)
+		.await
+	}
+
+	async fn gas_price(&self, ctx: &Context<'_>) -> Result<BigInt> {
+		backend(ctx).gas_price().map(BigInt).map_err(rpc_err)
+	}
+
+	async fn max_priority_fee_per_gas(&self, ctx: &Context<'_>) -> Result<BigInt> {
+		backend(ctx)
+			.max_priority_fee_per_gas()
+			.map(BigInt)
+			.map_err(rpc_err)
+	}
+
+	/// The sync progress, or null when the node is not syncing.
+	async fn syncing(&self, ctx: &Context<'_>) -> Result<Option<SyncState>> {
+		match backend(ctx).syncing().map_err(rpc_err)? {
+			SyncStatus::Info(info) => Ok(Some(SyncState {
+				starting_block: Long(info.starting_block.low_u64()),
+				current_block: Long(info.current_block.low_u64()),
+				highest_block: Long(info.highest_block.low_u64()),
+			})),
+			SyncStatus::None => Ok(None),
+		}
+	}
+
+	#[graphql(name = "chainID")]
+	async fn chain_id(&self, ctx: &Context<'_>) -> Result<BigInt> {
+		let chain_id = backend(ctx).chain_id().map_err(rpc_err)?;
+		Ok(BigInt(
+			chain_id
+				.map(|id| U256::from(id.as_u64()))
+				.unwrap_or_default(),
+		))
+	}
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+	async fn send_raw_transaction(&self, ctx: &Context<'_>, data: Bytes) -> Result<Bytes32> {
+		backend(ctx)
+			.send_raw_transaction(RpcBytes(data.0))
+			.await
+			.map(Bytes32)
+			.map_err(rpc_err)
+	}
+}
+
+// ############################################################################
+// Helpers
+// ############################################################################
+
+fn block_number_or(block: Option<Long>, default: Option<U256>) -> BlockNumber {
+	match (block, default) {
+		(Some(block), _) => BlockNumber::Num(block.0),
+		(None, Some(number)) => BlockNumber::Num(number.low_u64()),
+		(None, None) => BlockNumber::Latest,
+	}
+}
+
+async fn block_by_number(ctx: &Context<'_>, number: BlockNumber) -> Result<Option<Block>> {
+	Ok(backend(ctx)
+		.block_by_number(number, true)
+		.await
+		.map_err(rpc_err)?
+		.map(Block::new))
+}
+
+async fn block_by_hash(ctx: &Context<'_>, hash: H256) -> Result<Option<Block>> {
+	Ok(backend(ctx)
+		.block_by_hash(hash, true)
+		.await
+		.map_err(rpc_err)?
+		.map(Block::new))
+}
+
+async fn pending_block(ctx: &Context<'_>) -> Result<Option<Block>> {
+	block_by_number(ctx, BlockNumber::Pending).await
+}
+
+async fn transaction(ctx: &Context<'_>, hash: H256) -> Result<Option<Transaction>> {
+	Ok(backend(ctx)
+		.transaction_by_hash(hash)
+		.await
+		.map_err(rpc_err)?
+		.map(|transaction| Transaction::new(transaction, None)))
+}
+
+async fn logs(ctx: &Context<'_>, filter: Filter) -> Result<Vec<Log>> {
+	Ok(backend(ctx)
+		.logs(filter)
+		.await
+		.map_err(rpc_err)?
+		.into_iter()
+		.map(|inner| Log { inner })
+		.collect())
+}
+
+fn call(ctx: &Context<'_>, data: CallData, number: BlockNumber) -> Result<CallResult> {
+	let _permit = guard(ctx).admit_evm_call("eth_call").map_err(rpc_err)?;
+	let outcome = backend(ctx).call(data.into(), number).map_err(rpc_err)?;
+
+	Ok(CallResult {
+		status: Long(outcome.exit_reason.is_succeed() as u64),
+		data: Bytes(outcome.value),
+		gas_used: Long(outcome.used_gas.low_u64()),
+	})
+}
+
+async fn estimate_gas(ctx: &Context<'_>, data: CallData, number: BlockNumber) -> Result<Long> {
+	let _permit = guard(ctx)
+		.admit_evm_call("eth_estimateGas")
+		.map_err(rpc_err)?;
+	backend(ctx)
+		.estimate_gas(data.into(), number)
+		.await
+		.map(|gas| Long(gas.low_u64()))
+		.map_err(rpc_err)
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+	future::Future,
+	net::{IpAddr, SocketAddr},
+	sync::Arc,
+};
+
+use fc_rpc::RpcGuard;
+use hyper::{
+	header::CONTENT_TYPE,
+	server::conn::AddrStream,
+	service::{make_service_fn, service_fn},
+	Body, Method, Request, Response, Server, StatusCode,
+};
+
+use crate::EthSchema;
+
+/// Binds `addr` and returns the future serving GraphQL queries on `/` and `/graphql`, with the
+/// per-IP rate limit and the request size of `guard` enforced, each query counting as one call.
+pub fn start_server(
+	addr: SocketAddr,
+	schema: EthSchema,
+	guard: Arc<RpcGuard>,
+) -> Result<impl Future<Output = ()>, String> {
+	let builder = Server::try_bind(&addr)
+		.map_err(|err| format!("Failed to bind GraphQL server on {}: {:?}", addr, err))?;
+
+	let make_service = make_service_fn(move |conn: &AddrStream| {
+		let ip = conn.remote_addr().ip();
+		let schema = schema.clone();
+		let guard = guard.clone();
+		async move {
+			Ok::<_, hyper::Error>(service_fn(move |request| {
+				let schema = schema.clone();
+				let guard = guard.clone();
+				async move { Ok::<_, hyper::Error>(handle(ip, schema, guard, request).await) }
+			}))
+		}
+	});
+
+	let server = builder.serve(make_service);
+	log::info!(target: "graphql", "GraphQL server listening on {}", server.local_addr());
+
+	Ok(async move {
+		if let Err(err) = server.await {
+			log::error!(target: "graphql", "GraphQL server error: {:?}", err);
+		}
+	})
+}
+
+async fn handle(
+	ip: IpAddr,
+	schema: EthSchema,
+	guard: Arc<RpcGuard>,
+	request: Request<Body>,
+) -> Response<Body> {
+	if request.method() != Method::POST || !matches!(request.uri().path(), "/" | "/graphql") {
+		return response(StatusCode::NOT_FOUND, Body::empty());
+	}
+
+	if let Err(err) = guard.admit_request(ip, 1) {
+		return response(StatusCode::TOO_MANY_REQUESTS, Body::from(err.message));
+	}
+	let body = match guard.read_body(request).await {
+		Ok(body) => body,
+		Err(status) => return response(status, Body::empty()),
+	};
+	let request: async_graphql::Request = match serde_json::from_slice(&body) {
+		Ok(request) => request,
+		Err(err) => {
+			return response(
+				StatusCode::BAD_REQUEST,
+				Body::from(format!("Invalid GraphQL request: {}", err)),
+			)
+		}
+	};
+
+	let result = schema.execute(request).await;
+	match serde_json::to_vec(&result) {
+		Ok(json) => {
+			let mut ok = response(StatusCode::OK, Body::from(json));
+			ok.headers_mut().insert(
+				CONTENT_TYPE,
+				"application/json".parse().expect("valid header"),
+			);
+			ok
+		}
+		Err(err) => {
+			log::error!(target: "graphql", "Failed to encode GraphQL response: {:?}", err);
+			response(StatusCode::INTERNAL_SERVER_ERROR, Body::empty())
+		}
+	}
+}
+
+fn response(status: StatusCode, body: Body) -> Response<Body> {
+	let mut response = Response::new(body);
+	*response.status_mut() = status;
+	response
+}
@@ -0,0 +1,325 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2020-2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+use fp_rpc::EthereumRuntimeRPCApi;
+use log::{info, warn};
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use sc_client_api::BlockOf;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, UniqueSaturatedInto, Zero},
+};
+
+/// Delay before retrying a batch that failed.
+const RETRY_DELAY: Duration = Duration::from_secs(6);
+
+/// Progress of the mapping backfill, shared with `eth_syncing`.
+#[derive(Debug, Default)]
+pub struct BackfillProgress {
+	running: AtomicBool,
+	starting_block: AtomicU64,
+	current_block: AtomicU64,
+	highest_block: AtomicU64,
+}
+
+impl BackfillProgress {
+	/// The starting, current and highest block numbers while a backfill is running.
+	pub fn status(&self) -> Option<(u64, u64, u64)> {
+		if self.running.load(Ordering::Acquire) {
+			Some((
+				self.starting_block.load(Ordering::Relaxed),
+				self.current_block.load(Ordering::Relaxed),
+				self.highest_block.load(Ordering::Relaxed),
+			))
+		} else {
+			None
+		}
+	}
+
+	fn update(&self, starting_block: u64, current_block: u64, highest_block: u64) {
+		self.starting_block.store(starting_block, Ordering::Relaxed);
+		self.current_block.store(current_block, Ordering::Relaxed);
+		self.highest_block.store(highest_block, Ordering::Relaxed);
+		self.running.store(true, Ordering::Release);
+	}
+
+	fn finish(&self) {
+		self.running.store(false, Ordering::Release);
+	}
+}
+
+/// Prometheus metrics of the mapping backfill.
+#[derive(Clone)]
+pub struct BackfillMetrics {
+	current_block: Gauge<U64>,
+	highest_block: Gauge<U64>,
+	lag: Gauge<U64>,
+	blocks: Counter<U64>,
+}
+
+impl BackfillMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			current_block: register(
+				Gauge::new(
+					"frontier_mapping_backfill_current_block",
+					"Next block number to be mapped by the backfill",
+				)?,
+				registry,
+			)?,
+			highest_block: register(
+				Gauge::new(
+					"frontier_mapping_backfill_highest_block",
+					"Finalized block number the backfill is catching up with",
+				)?,
+				registry,
+			)?,
+			lag: register(
+				Gauge::new(
+					"frontier_mapping_backfill_lag",
+					"Number of finalized blocks not yet mapped by the backfill",
+				)?,
+				registry,
+			)?,
+			blocks: register(
+				Counter::new(
+					"frontier_mapping_backfill_blocks_total",
+					"Number of blocks mapped by the backfill",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Maps finalized history in parallel batches, while `MappingSyncWorker` keeps following the
+/// tips. Resumes from the cursor stored in the meta database.
+pub struct MappingBackfillWorker<Block: BlockT, C, B> {
+	client: Arc<C>,
+	substrate_backend: Arc<B>,
+	frontier_backend: Arc<fc_db::Backend<Block>>,
+	sync_from: NumberFor<Block>,
+	batch_size: u32,
+	threads: u32,
+	progress: Arc<BackfillProgress>,
+	metrics: Option<BackfillMetrics>,
+}
+
+impl<Block: BlockT, C, B> MappingBackfillWorker<Block, C, B>
+where
+	C: ProvideRuntimeApi<Block> + Send + Sync + HeaderBackend<Block> + BlockOf + 'static,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	B: sc_client_api::Backend<Block> + 'static,
+{
+	pub fn new(
+		client: Arc<C>,
+		substrate_backend: Arc<B>,
+		frontier_backend: Arc<fc_db::Backend<Block>>,
+		sync_from: NumberFor<Block>,
+		batch_size: u32,
+		threads: u32,
+		progress: Arc<BackfillProgress>,
+		metrics: Option<BackfillMetrics>,
+	) -> Self {
+		Self {
+			client,
+			substrate_backend,
+			frontier_backend,
+			sync_from,
+			batch_size: batch_size.max(1),
+			threads: threads.max(1),
+			progress,
+			metrics,
+		}
+	}
+
+	/// Map every block up to the finalized one, then return. Blocks the calling thread.
+	///
+	/// Without a stored cursor, the backfill starts from `sync_from` and skips the blocks that are
+	/// already synced. When the transaction indices were just enabled, blocks mapped before are
+	/// indexed again, up to the best block at startup.
+	pub fn run(self) {
+		let mut cursor = match self.frontier_backend.meta().backfill_cursor() {
+			Ok(cursor) => cursor.unwrap_or(self.sync_from).max(self.sync_from),
+			Err(e) => {
+				warn!(target: "mapping-sync", "Failed to read the backfill cursor: {:?}", e);
+				return;
+			}
+		};
+		let mut index_until = Zero::zero();
+		if let Some(index) = self.frontier_backend.index() {
+			match index.cursor() {
+				Ok(Some(index_cursor)) => {
+					cursor = cursor.min(index_cursor.unique_saturated_into());
+				}
+				Ok(None) => {
+					cursor = self.sync_from;
+					index_until = self.client.info().best_number;
+				}
+				Err(e) => {
+					warn!(target: "mapping-sync", "Failed to read the index cursor: {:?}", e);
+					return;
+				}
+			}
+		}
+		let starting_block: u64 = cursor.unique_saturated_into();
+
+		loop {
+			// Skip over blocks pruned since the last batch, and keep them from being pruned until
+			// the batch is written.
+			let pruning = self.frontier_backend.pruning_lock().read();
+			if let Ok(Some(first_retained)) = self.frontier_backend.meta().first_retained_block() {
+				cursor = cursor.max(first_retained);
+			}
+			let finalized = self.client.info().finalized_number.max(index_until);
+			if cursor > finalized {
+				break;
+			}
+			let highest_block: u64 = finalized.unique_saturated_into();
+			let current_block: u64 = cursor.unique_saturated_into();
+			self.progress
+				.update(starting_block, current_block, highest_block);
+			if let Some(metrics) = &self.metrics {
+				metrics.current_block.set(current_block);
+				metrics.highest_block.set(highest_block);
+				metrics.lag.set(highest_block.saturating_sub(current_block));
+			}
+
+			let batch_size = NumberFor::<Block>::from(self.batch_size);
+			let mut handles = Vec::with_capacity(self.threads as usize);
+			let mut from = cursor;
+			for _ in 0..self.threads {
+				if from > finalized {
+					break;
+				}
+				let to = (from + batch_size - One::one()).min(finalized);
+				let client = self.client.clone();
+				let substrate_backend = self.substrate_backend.clone();
+				let frontier_backend = self.frontier_backend.clone();
+				handles.push(thread::spawn(move || {
+					backfill_range(
+						client.as_ref(),
+						substrate_backend.blockchain(),
+						frontier_backend.as_ref(),
+						from,
+						to,
+					)
+				}));
+				from = to + One::one();
+			}
+
+			let mut result = Ok(());
+			for handle in handles {
+				let batch = handle
+					.join()
+					.unwrap_or_else(|_| Err("Backfill thread panicked".to_string()));
+				if result.is_ok() {
+					result = batch;
+				}
+			}
+			if let Err(e) = result
+				.and_then(|()| self.frontier_backend.meta().write_backfill_cursor(from))
+				.and_then(|()| match self.frontier_backend.index() {
+					Some(index) => index.write_cursor(from.unique_saturated_into()),
+					None => Ok(()),
+				}) {
+				drop(pruning);
+				warn!(target: "mapping-sync", "Backfill failed with error {:?}, retrying.", e);
+				thread::sleep(RETRY_DELAY);
+				continue;
+			}
+
+			if let Some(metrics) = &self.metrics {
+				let mapped: u64 = (from - cursor).unique_saturated_into();
+				metrics.blocks.inc_by(mapped);
+			}
+			cursor = from;
+		}
+
+		if let Some(metrics) = &self.metrics {
+			let current_block: u64 = cursor.unique_saturated_into();
+			metrics.current_block.set(current_block);
+			metrics.lag.set(0);
+		}
+		self.progress.finish();
+		info!(target: "mapping-sync", "Backfill caught up with block #{:?}", cursor);
+	}
+}
+
+/// Map the canonical blocks `from..=to` and write them in a single transaction.
+fn backfill_range<Block: BlockT, C, B>(
+	client: &C,
+	substrate_backend: &B,
+	frontier_backend: &fc_db::Backend<Block>,
+	from: NumberFor<Block>,
+	to: NumberFor<Block>,
+) -> Result<(), String>
+where
+	C: ProvideRuntimeApi<Block> + Send + Sync + HeaderBackend<Block> + BlockOf,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	B: sp_blockchain::HeaderBackend<Block>,
+{
+	let mut commitments = Vec::new();
+	let mut unmapped = Vec::new();
+
+	let mut number = from;
+	while number <= to {
+		let header = substrate_backend
+			.header(BlockId::Number(number))
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Header #{:?} not found", number))?;
+
+		// Blocks already mapped by the tip sync, or by a backfill run without a cursor, are not
+		// written again, but are still indexed.
+		let synced = frontier_backend.mapping().is_synced(&header.hash())?;
+		if header.number().is_zero() {
+			if !synced {
+				crate::sync_genesis_block(client, frontier_backend, &header)?;
+			}
+		} else {
+			match crate::mapping_commitment::<Block>(&header)? {
+				Some(commitment) => {
+					if let Some(index) = frontier_backend.index() {
+						crate::index_block(client, index, &header)?;
+					}
+					if !synced {
+						commitments.push(commitment)
+					}
+				}
+				None if !synced => unmapped.push(header.hash()),
+				None => (),
+			}
+		}
+		number += One::one();
+	}
+
+	frontier_backend
+		.mapping()
+		.write_batch(commitments, unmapped)
+}
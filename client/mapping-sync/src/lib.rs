@@ -16,9 +16,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod backfill;
+mod pruning;
 mod worker;
 
-pub use worker::{MappingSyncWorker, SyncStrategy};
+pub use backfill::{BackfillMetrics, BackfillProgress, MappingBackfillWorker};
+pub use pruning::pruning_task;
+pub use worker::{mapping_lag, MappingSyncMetrics, MappingSyncWorker, SyncStrategy};
 
 use fp_consensus::FindLogError;
 use fp_rpc::EthereumRuntimeRPCApi;
@@ -30,32 +34,96 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header as HeaderT, Zero},
 };
 
-pub fn sync_block<Block: BlockT>(
-	backend: &fc_db::Backend<Block>,
+/// The mapping of `header` derived from its Frontier log, or `None` if it has no log.
+pub fn mapping_commitment<Block: BlockT>(
 	header: &Block::Header,
-) -> Result<(), String> {
+) -> Result<Option<fc_db::MappingCommitment<Block>>, String> {
 	match fp_consensus::find_log(header.digest()) {
 		Ok(log) => {
 			let post_hashes = log.into_hashes();
 
-			let mapping_commitment = fc_db::MappingCommitment {
+			Ok(Some(fc_db::MappingCommitment {
 				block_hash: header.hash(),
 				ethereum_block_hash: post_hashes.block_hash,
 				ethereum_transaction_hashes: post_hashes.transaction_hashes,
-			};
-			backend.mapping().write_hashes(mapping_commitment)?;
-
-			Ok(())
-		}
-		Err(FindLogError::NotFound) => {
-			backend.mapping().write_none(header.hash())?;
-
-			Ok(())
+			}))
 		}
+		Err(FindLogError::NotFound) => Ok(None),
 		Err(FindLogError::MultipleLogs) => Err("Multiple logs found".to_string()),
 	}
 }
 
+pub fn sync_block<Block: BlockT>(
+	backend: &fc_db::Backend<Block>,
+	header: &Block::Header,
+) -> Result<(), String> {
+	match mapping_commitment::<Block>(header)? {
+		Some(mapping_commitment) => backend.mapping().write_hashes(mapping_commitment),
+		None => backend.mapping().write_none(header.hash()),
+	}
+}
+
+/// Record the Ethereum transactions of `header` in the transaction indices.
+pub fn index_block<Block: BlockT, C>(
+	client: &C,
+	index: &fc_db::IndexDb,
+	header: &Block::Header,
+) -> Result<(), String>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+{
+	let id = BlockId::Hash(header.hash());
+	let api = client.runtime_api();
+
+	// The nonces come from the `BlockV2` transactions of API version 2 and above.
+	match api
+		.api_version::<dyn EthereumRuntimeRPCApi<Block>>(&id)
+		.map_err(|e| format!("{:?}", e))?
+	{
+		Some(api_version) if api_version > 1 => (),
+		_ => return Ok(()),
+	}
+	let block = api.current_block(&id).map_err(|e| format!("{:?}", e))?;
+	let statuses = api
+		.current_transaction_statuses(&id)
+		.map_err(|e| format!("{:?}", e))?;
+	let (block, statuses) = match (block, statuses) {
+		(Some(block), Some(statuses)) => (block, statuses),
+		_ => return Ok(()),
+	};
+
+	let transactions = block
+		.transactions
+		.iter()
+		.zip(statuses)
+		.map(|(transaction, status)| {
+			let nonce = match transaction {
+				ethereum::TransactionV2::Legacy(t) => t.nonce,
+				ethereum::TransactionV2::EIP2930(t) => t.nonce,
+				ethereum::TransactionV2::EIP1559(t) => t.nonce,
+			};
+			let mut addresses = vec![status.from];
+			addresses.extend(status.to);
+			addresses.extend(status.contract_address);
+			addresses.extend(status.logs.iter().map(|log| log.address));
+			addresses.sort();
+			addresses.dedup();
+
+			fc_db::IndexedTransaction {
+				hash: status.transaction_hash,
+				index: status.transaction_index,
+				sender: status.from,
+				nonce,
+				addresses,
+				contract_address: status.contract_address,
+			}
+		})
+		.collect();
+
+	index.write_block(block.header.number.low_u64(), transactions)
+}
+
 pub fn sync_genesis_block<Block: BlockT, C>(
 	client: &C,
 	backend: &fc_db::Backend<Block>,
@@ -106,6 +174,11 @@ where
 	C::Api: EthereumRuntimeRPCApi<Block>,
 	B: sp_blockchain::HeaderBackend<Block> + sp_blockchain::Backend<Block>,
 {
+	// Blocks whose mappings were pruned must not be synced again.
+	let sync_from = match frontier_backend.meta().first_retained_block()? {
+		Some(first_retained) => sync_from.max(first_retained),
+		None => sync_from,
+	};
 	let mut current_syncing_tips = frontier_backend.meta().current_syncing_tips()?;
 
 	if current_syncing_tips.is_empty() {
@@ -149,6 +222,9 @@ where
 			return Ok(false);
 		}
 		sync_block(frontier_backend, &operating_header)?;
+		if let Some(index) = frontier_backend.index() {
+			index_block(client, index, &operating_header)?;
+		}
 
 		current_syncing_tips.push(*operating_header.parent_hash());
 		frontier_backend
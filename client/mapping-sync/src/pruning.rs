@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2020-2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use futures::prelude::*;
+use log::warn;
+use sc_client_api::BlockchainEvents;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{
+		Block as BlockT, Header as HeaderT, NumberFor, One, Saturating, UniqueSaturatedInto, Zero,
+	},
+};
+
+/// Number of blocks whose mappings are deleted in a single transaction.
+const PRUNING_BATCH_SIZE: u32 = 1000;
+
+/// Delete the mappings of blocks more than `retain_blocks` below the last finalized block,
+/// each time a block is finalized.
+pub async fn pruning_task<Block: BlockT, C, B>(
+	client: Arc<C>,
+	substrate_backend: Arc<B>,
+	frontier_backend: Arc<fc_db::Backend<Block>>,
+	retain_blocks: u32,
+) where
+	C: BlockchainEvents<Block>,
+	B: sc_client_api::Backend<Block>,
+{
+	let mut notification_st = client.finality_notification_stream();
+	// Non canonical blocks at the height of the last pruned block, whose descendants are pruned
+	// along with the canonical blocks. Forks branching off below the first retained block before
+	// a restart are not followed after it.
+	let mut forks = Vec::new();
+
+	while let Some(notification) = notification_st.next().await {
+		let until = notification
+			.header
+			.number()
+			.saturating_sub(retain_blocks.into());
+		if let Err(e) = prune_until(
+			substrate_backend.blockchain(),
+			frontier_backend.as_ref(),
+			until,
+			&mut forks,
+		) {
+			warn!(target: "mapping-sync", "Pruning failed with error {:?}, retrying.", e);
+		}
+	}
+}
+
+/// Delete the mappings of the blocks below `until` descending from the canonical chain or from
+/// `forks`, and the transaction indices of every block below it. `forks` is updated to the non
+/// canonical blocks at the height of the last pruned block.
+fn prune_until<Block: BlockT, B>(
+	substrate_backend: &B,
+	frontier_backend: &fc_db::Backend<Block>,
+	until: NumberFor<Block>,
+	forks: &mut Vec<Block::Hash>,
+) -> Result<(), String>
+where
+	B: sp_blockchain::Backend<Block>,
+{
+	let mut next = frontier_backend
+		.meta()
+		.first_retained_block()?
+		.unwrap_or_else(Zero::zero);
+
+	while next < until {
+		let to = (next + PRUNING_BATCH_SIZE.into()).min(until);
+
+		let mut commitments = Vec::new();
+		let mut unmapped = Vec::new();
+		let mut level_forks = forks.clone();
+		let mut number = next;
+		while number < to {
+			let canonical = canonical_hash(substrate_backend, number)?;
+			let mut level = vec![canonical];
+			if !number.is_zero() {
+				let parent = canonical_hash(substrate_backend, number - One::one())?;
+				for parent in std::iter::once(parent).chain(level_forks.drain(..)) {
+					for child in substrate_backend
+						.children(parent)
+						.map_err(|e| format!("{:?}", e))?
+					{
+						if child != canonical {
+							level.push(child);
+						}
+					}
+				}
+			}
+
+			for hash in &level {
+				let header = substrate_backend
+					.header(BlockId::Hash(*hash))
+					.map_err(|e| format!("{:?}", e))?
+					.ok_or_else(|| format!("Header {:?} not found", hash))?;
+				match crate::mapping_commitment::<Block>(&header)? {
+					Some(commitment) => commitments.push(commitment),
+					None => unmapped.push(*hash),
+				}
+			}
+			level_forks = level.split_off(1);
+			number += One::one();
+		}
+
+		{
+			let _pruning = frontier_backend.pruning_lock().write();
+			frontier_backend
+				.mapping()
+				.prune_batch(commitments, unmapped)?;
+			if let Some(index) = frontier_backend.index() {
+				index.prune_until(to.unique_saturated_into())?;
+			}
+			frontier_backend.meta().write_first_retained_block(to)?;
+		}
+		*forks = level_forks;
+		next = to;
+	}
+
+	Ok(())
+}
+
+fn canonical_hash<Block: BlockT, B>(
+	substrate_backend: &B,
+	number: NumberFor<Block>,
+) -> Result<Block::Hash, String>
+where
+	B: HeaderBackend<Block>,
+{
+	substrate_backend
+		.hash(number)
+		.map_err(|e| format!("{:?}", e))?
+		.ok_or_else(|| format!("Header #{:?} not found", number))
+}
@@ -23,11 +23,43 @@ use futures::{
 };
 use futures_timer::Delay;
 use log::debug;
+use prometheus_endpoint::{register, Gauge, PrometheusError, Registry, U64};
 use sc_client_api::{BlockOf, ImportNotifications};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{pin::Pin, sync::Arc, time::Duration};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, Zero},
+};
+use std::{
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+/// Number of blocks below the best one searched for a mapped block when measuring the lag.
+const MAX_MEASURED_LAG: u64 = 1024;
+
+/// Prometheus metrics of the mapping sync.
+pub struct MappingSyncMetrics {
+	lag: Gauge<U64>,
+	measured_at: Option<Instant>,
+}
+
+impl MappingSyncMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			lag: register(
+				Gauge::new(
+					"frontier_mapping_sync_lag",
+					"Number of best chain blocks above the highest mapped one, measured up to 1024",
+				)?,
+				registry,
+			)?,
+			measured_at: None,
+		})
+	}
+}
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum SyncStrategy {
@@ -48,6 +80,7 @@ pub struct MappingSyncWorker<Block: BlockT, C, B> {
 	retry_times: usize,
 	sync_from: <Block::Header as HeaderT>::Number,
 	strategy: SyncStrategy,
+	metrics: Option<MappingSyncMetrics>,
 }
 
 impl<Block: BlockT, C, B> Unpin for MappingSyncWorker<Block, C, B> {}
@@ -62,6 +95,7 @@ impl<Block: BlockT, C, B> MappingSyncWorker<Block, C, B> {
 		retry_times: usize,
 		sync_from: <Block::Header as HeaderT>::Number,
 		strategy: SyncStrategy,
+		metrics: Option<MappingSyncMetrics>,
 	) -> Self {
 		Self {
 			import_notifications,
@@ -76,6 +110,7 @@ impl<Block: BlockT, C, B> MappingSyncWorker<Block, C, B> {
 			retry_times,
 			sync_from,
 			strategy,
+			metrics,
 		}
 	}
 }
@@ -118,14 +153,17 @@ where
 		if fire {
 			self.inner_delay = None;
 
-			match crate::sync_blocks(
+			let result = crate::sync_blocks(
 				self.client.as_ref(),
 				self.substrate_backend.blockchain(),
 				self.frontier_backend.as_ref(),
 				self.retry_times,
 				self.sync_from,
 				self.strategy,
-			) {
+			);
+			self.measure_lag();
+
+			match result {
 				Ok(have_next) => {
 					self.have_next = have_next;
 					Poll::Ready(Some(()))
@@ -141,3 +179,62 @@ where
 		}
 	}
 }
+
+impl<Block: BlockT, C, B> MappingSyncWorker<Block, C, B>
+where
+	C: HeaderBackend<Block>,
+	B: sc_client_api::Backend<Block>,
+{
+	/// Update the lag metric, at most once per timeout as it walks back from the best block.
+	fn measure_lag(&mut self) {
+		let timeout = self.timeout;
+		let metrics = match &mut self.metrics {
+			Some(metrics)
+				if metrics
+					.measured_at
+					.map_or(true, |measured_at| measured_at.elapsed() >= timeout) =>
+			{
+				metrics
+			}
+			_ => return,
+		};
+		metrics.measured_at = Some(Instant::now());
+
+		match mapping_lag(
+			self.substrate_backend.blockchain(),
+			self.frontier_backend.as_ref(),
+			self.client.info().best_hash,
+			MAX_MEASURED_LAG,
+		) {
+			Ok(lag) => metrics.lag.set(lag),
+			Err(e) => debug!(target: "mapping-sync", "Measuring the lag failed with error {:?}", e),
+		}
+	}
+}
+
+/// Number of blocks from `best_hash` down to the first one `MappingDb::is_synced` reports as
+/// mapped, walking at most `max_lag` blocks.
+pub fn mapping_lag<Block: BlockT, B>(
+	substrate_backend: &B,
+	frontier_backend: &fc_db::Backend<Block>,
+	best_hash: Block::Hash,
+	max_lag: u64,
+) -> Result<u64, String>
+where
+	B: sp_blockchain::HeaderBackend<Block>,
+{
+	let mut hash = best_hash;
+	for lag in 0..max_lag {
+		if frontier_backend.mapping().is_synced(&hash)? {
+			return Ok(lag);
+		}
+		match substrate_backend
+			.header(BlockId::Hash(hash))
+			.map_err(|e| format!("{:?}", e))?
+		{
+			Some(header) if !header.number().is_zero() => hash = *header.parent_hash(),
+			_ => return Ok(lag + 1),
+		}
+	}
+	Ok(max_lag)
+}
@@ -132,6 +132,10 @@ pub trait EthApi {
 	#[rpc(name = "eth_getTransactionReceipt")]
 	fn transaction_receipt(&self, hash: H256) -> BoxFuture<Result<Option<Receipt>>>;
 
+	/// Returns the receipts of every transaction of a block.
+	#[rpc(name = "eth_getBlockReceipts")]
+	fn block_receipts(&self, number: BlockNumber) -> BoxFuture<Result<Option<Vec<Receipt>>>>;
+
 	// ########################################################################
 	// State
 	// ########################################################################
@@ -168,6 +172,14 @@ pub trait EthApi {
 		number: Option<BlockNumber>,
 	) -> BoxFuture<Result<U256>>;
 
+	/// Generates an access list for the given call, along with the gas it uses with that list.
+	#[rpc(name = "eth_createAccessList")]
+	fn create_access_list(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> Result<AccessListResult>;
+
 	// ########################################################################
 	// Fee
 	// ########################################################################
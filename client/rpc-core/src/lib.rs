@@ -21,11 +21,15 @@ pub mod types;
 mod eth;
 mod eth_pubsub;
 mod net;
+mod ots;
+mod txpool;
 mod web3;
 
 pub use self::{
 	eth::{EthApi, EthApiServer, EthFilterApi, EthFilterApiServer},
 	eth_pubsub::{EthPubSubApi, EthPubSubApiServer},
 	net::{NetApi, NetApiServer},
+	ots::{OtsApi, OtsApiServer},
+	txpool::{TxPoolApi, TxPoolApiServer},
 	web3::{Web3Api, Web3ApiServer},
 };
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Otterscan rpc interface.
+
+use ethereum_types::{H160, H256, U256};
+use jsonrpc_core::{BoxFuture, Result};
+use jsonrpc_derive::rpc;
+
+use crate::types::*;
+
+pub use rpc_impl_OtsApi::gen_server::OtsApi as OtsApiServer;
+
+/// Otterscan rpc interface.
+#[rpc(server)]
+pub trait OtsApi {
+	/// Returns the version of the Otterscan API implemented.
+	#[rpc(name = "ots_getApiLevel")]
+	fn get_api_level(&self) -> Result<u8>;
+
+	/// Returns a block without its transactions, with its transaction count, issuance and fees.
+	#[rpc(name = "ots_getBlockDetails")]
+	fn get_block_details(&self, number: BlockNumber) -> BoxFuture<Result<Option<BlockDetails>>>;
+
+	/// Returns a page of the transactions of a block, with their receipts.
+	#[rpc(name = "ots_getBlockTransactions")]
+	fn get_block_transactions(
+		&self,
+		number: BlockNumber,
+		page_number: usize,
+		page_size: usize,
+	) -> BoxFuture<Result<Option<BlockTransactionsPage>>>;
+
+	/// Returns whether an address has code at a block.
+	#[rpc(name = "ots_hasCode")]
+	fn has_code(&self, address: H160, number: Option<BlockNumber>) -> Result<bool>;
+
+	/// Returns the revert data of a transaction, empty if it did not revert.
+	#[rpc(name = "ots_getTransactionError")]
+	fn get_transaction_error(&self, hash: H256) -> Result<Bytes>;
+
+	/// Returns the transactions an address appears in, in blocks below a block number, most
+	/// recent first. Block number 0 starts from the most recent block.
+	#[rpc(name = "ots_searchTransactionsBefore")]
+	fn search_transactions_before(
+		&self,
+		address: H160,
+		block_number: u64,
+		page_size: usize,
+	) -> BoxFuture<Result<TransactionsWithReceipts>>;
+
+	/// Returns the transactions an address appears in, in blocks above a block number, most
+	/// recent first. Block number 0 starts from the genesis block.
+	#[rpc(name = "ots_searchTransactionsAfter")]
+	fn search_transactions_after(
+		&self,
+		address: H160,
+		block_number: u64,
+		page_size: usize,
+	) -> BoxFuture<Result<TransactionsWithReceipts>>;
+
+	/// Returns the hash of the canonical transaction sent by an address with a nonce.
+	#[rpc(name = "ots_getTransactionBySenderAndNonce")]
+	fn get_transaction_by_sender_and_nonce(
+		&self,
+		sender: H160,
+		nonce: U256,
+	) -> Result<Option<H256>>;
+
+	/// Returns the canonical transaction that created a contract, and its sender.
+	#[rpc(name = "ots_getContractCreator")]
+	fn get_contract_creator(&self, address: H160) -> Result<Option<ContractCreator>>;
+}
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! TxPool rpc interface.
+
+use ethereum_types::U256;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+use crate::types::*;
+
+pub use rpc_impl_TxPoolApi::gen_server::TxPoolApi as TxPoolApiServer;
+
+/// TxPool rpc interface.
+#[rpc(server)]
+pub trait TxPoolApi {
+	/// Returns the exact details of all the transactions currently pending for inclusion in the
+	/// next block(s), as well as the ones that are being scheduled for future execution only,
+	/// grouped by sender and nonce.
+	#[rpc(name = "txpool_content")]
+	fn content(&self) -> Result<TxPoolResult<TransactionMap<Transaction>>>;
+
+	/// Returns a textual summary of all the transactions currently pending for inclusion in the
+	/// next block(s), as well as the ones that are being scheduled for future execution only,
+	/// grouped by sender and nonce.
+	#[rpc(name = "txpool_inspect")]
+	fn inspect(&self) -> Result<TxPoolResult<TransactionMap<Summary>>>;
+
+	/// Returns the number of transactions currently pending for inclusion in the next block(s),
+	/// as well as the ones that are being scheduled for future execution only.
+	#[rpc(name = "txpool_status")]
+	fn status(&self) -> Result<TxPoolResult<U256>>;
+}
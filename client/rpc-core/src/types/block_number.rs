@@ -41,6 +41,10 @@ pub enum BlockNumber {
 	Earliest,
 	/// Pending block (being mined)
 	Pending,
+	/// The most recent block considered safe from reorgs
+	Safe,
+	/// The most recent finalized block
+	Finalized,
 }
 
 impl Default for BlockNumber {
@@ -85,6 +89,8 @@ impl Serialize for BlockNumber {
 			BlockNumber::Latest => serializer.serialize_str("latest"),
 			BlockNumber::Earliest => serializer.serialize_str("earliest"),
 			BlockNumber::Pending => serializer.serialize_str("pending"),
+			BlockNumber::Safe => serializer.serialize_str("safe"),
+			BlockNumber::Finalized => serializer.serialize_str("finalized"),
 		}
 	}
 }
@@ -97,7 +103,7 @@ impl<'a> Visitor<'a> for BlockNumberVisitor {
 	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
 		write!(
 			formatter,
-			"a block number or 'latest', 'earliest' or 'pending'"
+			"a block number or 'latest', 'earliest', 'pending', 'safe' or 'finalized'"
 		)
 	}
 
@@ -162,6 +168,8 @@ impl<'a> Visitor<'a> for BlockNumberVisitor {
 			"latest" => Ok(BlockNumber::Latest),
 			"earliest" => Ok(BlockNumber::Earliest),
 			"pending" => Ok(BlockNumber::Pending),
+			"safe" => Ok(BlockNumber::Safe),
+			"finalized" => Ok(BlockNumber::Finalized),
 			_ if value.starts_with("0x") => u64::from_str_radix(&value[2..], 16)
 				.map(BlockNumber::Num)
 				.map_err(|e| Error::custom(format!("Invalid block number: {}", e))),
@@ -211,4 +219,18 @@ mod tests {
 		assert_eq!(match_block_number(bn_hex).unwrap(), 69 as u64);
 		assert_eq!(match_block_number(bn_u64).unwrap(), 420 as u64);
 	}
+
+	#[test]
+	fn block_number_tags() {
+		let bn_safe: BlockNumber = serde_json::from_str(r#""safe""#).unwrap();
+		let bn_finalized: BlockNumber = serde_json::from_str(r#""finalized""#).unwrap();
+
+		assert_eq!(bn_safe, BlockNumber::Safe);
+		assert_eq!(bn_finalized, BlockNumber::Finalized);
+		assert_eq!(serde_json::to_string(&bn_safe).unwrap(), r#""safe""#);
+		assert_eq!(
+			serde_json::to_string(&bn_finalized).unwrap(),
+			r#""finalized""#
+		);
+	}
 }
@@ -18,6 +18,7 @@
 
 //! RPC types
 
+mod access_list;
 mod account_info;
 mod block;
 mod block_number;
@@ -27,15 +28,18 @@ mod fee;
 mod filter;
 mod index;
 mod log;
+mod otterscan;
 mod receipt;
 mod sync;
 mod transaction;
 mod transaction_request;
+mod txpool;
 mod work;
 
 pub mod pubsub;
 
 pub use self::{
+	access_list::AccessListResult,
 	account_info::{AccountInfo, EthAccount, ExtAccountInfo, RecoveredAccount, StorageProof},
 	block::{Block, BlockTransactions, Header, Rich, RichBlock, RichHeader},
 	block_number::BlockNumber,
@@ -48,6 +52,10 @@ pub use self::{
 	},
 	index::Index,
 	log::Log,
+	otterscan::{
+		BlockDetails, BlockSummary, BlockTransactionsPage, ContractCreator, Issuance,
+		TransactionsWithReceipts,
+	},
 	receipt::Receipt,
 	sync::{
 		ChainStatus, EthProtocolInfo, PeerCount, PeerInfo, PeerNetworkInfo, PeerProtocolsInfo,
@@ -55,5 +63,6 @@ pub use self::{
 	},
 	transaction::{LocalTransactionStatus, RichRawTransaction, Transaction},
 	transaction_request::{TransactionMessage, TransactionRequest},
+	txpool::{Get as TxPoolGet, Summary, TransactionMap, TxPoolResult},
 	work::Work,
 };
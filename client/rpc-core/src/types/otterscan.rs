@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use ethereum_types::{H160, H256, U256};
+use serde::Serialize;
+
+use crate::types::{Receipt, RichBlock, Transaction};
+
+/// `ots_getBlockDetails` response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDetails {
+	/// The block, without its transactions.
+	pub block: BlockSummary,
+	/// Rewards issued with the block.
+	pub issuance: Issuance,
+	/// Fees paid by the transactions of the block.
+	pub total_fees: U256,
+}
+
+/// A block without its transactions, and how many it has.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSummary {
+	#[serde(flatten)]
+	pub block: RichBlock,
+	pub transaction_count: usize,
+}
+
+/// Rewards issued with a block. Frontier chains issue none.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issuance {
+	pub block_reward: U256,
+	pub uncle_reward: U256,
+	pub issuance: U256,
+}
+
+/// `ots_getBlockTransactions` response
+#[derive(Debug, Serialize)]
+pub struct BlockTransactionsPage {
+	/// The block, with the full transactions of the page only.
+	pub fullblock: RichBlock,
+	/// Receipts of the transactions of the page.
+	pub receipts: Vec<Receipt>,
+}
+
+/// `ots_searchTransactionsBefore` and `ots_searchTransactionsAfter` response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsWithReceipts {
+	/// Transactions, most recent first.
+	pub txs: Vec<Transaction>,
+	/// Receipts of the transactions, in the same order.
+	pub receipts: Vec<Receipt>,
+	/// Whether no more recent transactions remain.
+	pub first_page: bool,
+	/// Whether no older transactions remain.
+	pub last_page: bool,
+}
+
+/// `ots_getContractCreator` response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractCreator {
+	/// Hash of the transaction that created the contract.
+	pub hash: H256,
+	/// Sender of that transaction.
+	pub creator: H160,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn contract_creator_serialize() {
+		let creator = ContractCreator {
+			hash: H256::from_low_u64_be(1),
+			creator: H160::repeat_byte(0x11),
+		};
+		let serialized = serde_json::to_string(&creator).unwrap();
+		assert_eq!(
+			serialized,
+			r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000001","creator":"0x1111111111111111111111111111111111111111"}"#
+		);
+	}
+
+	#[test]
+	fn transactions_with_receipts_serialize() {
+		let page = TransactionsWithReceipts {
+			txs: Vec::new(),
+			receipts: Vec::new(),
+			first_page: true,
+			last_page: false,
+		};
+		let serialized = serde_json::to_string(&page).unwrap();
+		assert_eq!(
+			serialized,
+			r#"{"txs":[],"receipts":[],"firstPage":true,"lastPage":false}"#
+		);
+	}
+}
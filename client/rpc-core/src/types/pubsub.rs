@@ -18,30 +18,53 @@
 
 //! Pub-Sub types.
 
-use crate::types::{Filter, Log, RichHeader};
-use ethereum_types::H256;
+use crate::types::{
+	Filter, FilterAddress, Log, Receipt, RichHeader, SyncInfo, Transaction, VariadicValue,
+};
+use ethereum_types::{H160, H256};
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{from_value, Value};
 
 /// Subscription result.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Result {
 	/// New block header.
 	Header(Box<RichHeader>),
+	/// New block header with its transaction hashes and/or receipts.
+	HeaderWithTransactions(Box<HeaderWithTransactions>),
 	/// Log
 	Log(Box<Log>),
 	/// Transaction hash
 	TransactionHash(H256),
+	/// Full transaction
+	Transaction(Box<Transaction>),
 	/// SyncStatus
 	SyncState(PubSubSyncStatus),
 }
 
+/// New block header along with the hashes of the transactions it includes and/or their receipts.
+#[derive(Debug, Serialize, Eq, PartialEq, Clone)]
+pub struct HeaderWithTransactions {
+	/// Block header.
+	#[serde(flatten)]
+	pub header: RichHeader,
+	/// Hashes of the block transactions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub transactions: Option<Vec<H256>>,
+	/// Receipts of the block transactions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub receipts: Option<Vec<Receipt>>,
+}
+
 /// PubSbub sync status
 #[derive(Debug, Serialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PubSubSyncStatus {
 	/// is_major_syncing?
 	pub syncing: bool,
+	/// Progress of the sync, while syncing.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<SyncInfo>,
 }
 
 impl Serialize for Result {
@@ -51,8 +74,10 @@ impl Serialize for Result {
 	{
 		match *self {
 			Result::Header(ref header) => header.serialize(serializer),
+			Result::HeaderWithTransactions(ref header) => header.serialize(serializer),
 			Result::Log(ref log) => log.serialize(serializer),
 			Result::TransactionHash(ref hash) => hash.serialize(serializer),
+			Result::Transaction(ref transaction) => transaction.serialize(serializer),
 			Result::SyncState(ref sync) => sync.serialize(serializer),
 		}
 	}
@@ -73,6 +98,51 @@ pub enum Kind {
 	Syncing,
 }
 
+/// Pending transactions subscription filter.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTransactionsParams {
+	/// Sender address(es) to match.
+	pub from_address: Option<FilterAddress>,
+	/// Recipient address(es) to match.
+	pub to_address: Option<FilterAddress>,
+	/// Only notify transaction hashes instead of full transactions.
+	#[serde(default)]
+	pub hashes_only: bool,
+}
+
+impl PendingTransactionsParams {
+	/// Whether a transaction from `from` to `to` passes the filter.
+	pub fn matches(&self, from: &H160, to: Option<&H160>) -> bool {
+		fn address_matches(filter: &Option<FilterAddress>, address: Option<&H160>) -> bool {
+			match (filter, address) {
+				(None, _) | (Some(VariadicValue::Null), _) => true,
+				(Some(VariadicValue::Single(filter)), Some(address)) => filter == address,
+				(Some(VariadicValue::Multiple(filter)), Some(address)) => {
+					filter.is_empty() || filter.contains(address)
+				}
+				(Some(VariadicValue::Multiple(filter)), None) => filter.is_empty(),
+				(Some(VariadicValue::Single(_)), None) => false,
+			}
+		}
+		address_matches(&self.from_address, Some(from)) && address_matches(&self.to_address, to)
+	}
+}
+
+/// New heads subscription options.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct NewHeadsParams {
+	/// Include the hashes of the block transactions in each notification.
+	#[serde(default)]
+	pub include_transactions: bool,
+	/// Include the receipts of the block transactions in each notification.
+	#[serde(default)]
+	pub include_receipts: bool,
+}
+
 /// Subscription kind.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Params {
@@ -80,6 +150,14 @@ pub enum Params {
 	None,
 	/// Log parameters.
 	Logs(Filter),
+	/// Log parameters, notifying logs only once their block is finalized.
+	FinalizedLogs(Filter),
+	/// Whether to notify full pending transactions instead of hashes.
+	FullTransactions(bool),
+	/// Pending transactions filter.
+	PendingTransactions(PendingTransactionsParams),
+	/// New heads options.
+	NewHeads(NewHeadsParams),
 }
 
 impl Default for Params {
@@ -93,14 +171,100 @@ impl<'a> Deserialize<'a> for Params {
 	where
 		D: Deserializer<'a>,
 	{
-		let v: Value = Deserialize::deserialize(deserializer)?;
+		let mut v: Value = Deserialize::deserialize(deserializer)?;
 
 		if v.is_null() {
 			return Ok(Params::None);
 		}
+		if let Some(full) = v.as_bool() {
+			return Ok(Params::FullTransactions(full));
+		}
+		if let Some(finalized_only) = v.as_object_mut().and_then(|o| o.remove("finalizedOnly")) {
+			let finalized_only = finalized_only.as_bool().ok_or_else(|| {
+				D::Error::custom("Invalid Pub-Sub parameters: finalizedOnly must be a boolean")
+			})?;
+			return from_value(v)
+				.map(|filter| match finalized_only {
+					true => Params::FinalizedLogs(filter),
+					false => Params::Logs(filter),
+				})
+				.map_err(|e| D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e)));
+		}
 
 		from_value(v.clone())
 			.map(Params::Logs)
+			.or_else(|_| from_value(v.clone()).map(Params::PendingTransactions))
+			.or_else(|_| from_value(v).map(Params::NewHeads))
 			.map_err(|e| D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e)))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	#[test]
+	fn params_deserialization() {
+		let params: Params = serde_json::from_str("true").unwrap();
+		assert_eq!(params, Params::FullTransactions(true));
+
+		let params: Params = serde_json::from_str("{}").unwrap();
+		assert!(matches!(params, Params::Logs(_)));
+
+		let params: Params = serde_json::from_str(
+			r#"{"fromAddress":"0x0000000000000000000000000000000000000001","hashesOnly":true}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			params,
+			Params::PendingTransactions(PendingTransactionsParams {
+				from_address: Some(VariadicValue::Single(H160::from_low_u64_be(1))),
+				to_address: None,
+				hashes_only: true,
+			})
+		);
+
+		let params: Params = serde_json::from_str(r#"{"includeTransactions":true}"#).unwrap();
+		assert_eq!(
+			params,
+			Params::NewHeads(NewHeadsParams {
+				include_transactions: true,
+				include_receipts: false,
+			})
+		);
+
+		let params: Params = serde_json::from_str(r#"{"includeReceipts":true}"#).unwrap();
+		assert_eq!(
+			params,
+			Params::NewHeads(NewHeadsParams {
+				include_transactions: false,
+				include_receipts: true,
+			})
+		);
+
+		let params: Params = serde_json::from_str(r#"{"topics":[],"finalizedOnly":true}"#).unwrap();
+		assert!(matches!(params, Params::FinalizedLogs(_)));
+
+		assert!(serde_json::from_str::<Params>(r#"{"unknown":true}"#).is_err());
+	}
+
+	#[test]
+	fn pending_transactions_params_matches() {
+		let a = H160::from_str("0x0000000000000000000000000000000000000001").unwrap();
+		let b = H160::from_low_u64_be(2);
+
+		let params = PendingTransactionsParams::default();
+		assert!(params.matches(&a, None));
+
+		let params = PendingTransactionsParams {
+			from_address: Some(VariadicValue::Single(a)),
+			to_address: Some(VariadicValue::Multiple(vec![a, b])),
+			hashes_only: false,
+		};
+		assert!(params.matches(&a, Some(&b)));
+		assert!(!params.matches(&b, Some(&b)));
+		// Contract creations have no recipient.
+		assert!(!params.matches(&a, None));
+	}
+}
@@ -21,7 +21,7 @@ use ethereum_types::{Bloom as H2048, H160, H256, U256, U64};
 use serde::Serialize;
 
 /// Receipt
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Receipt {
 	/// Transaction Hash
@@ -56,4 +56,7 @@ pub struct Receipt {
 	pub status_code: Option<U64>,
 	/// Effective gas price. Pre-eip1559 this is just the gasprice. Post-eip1559 this is base fee + priority fee.
 	pub effective_gas_price: U256,
+	/// Reason of the revert, for reverted calls.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub revert_reason: Option<String>,
 }
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction pool inspection types.
+
+use std::collections::HashMap;
+
+use ethereum::{TransactionAction, TransactionV2 as EthereumTransaction};
+use ethereum_types::{H160, H256, U256};
+use serde::{Serialize, Serializer};
+
+use crate::types::Transaction;
+
+/// Transactions grouped by sender address and nonce.
+pub type TransactionMap<T> = HashMap<H160, HashMap<U256, T>>;
+
+/// Builds a txpool response item out of a pool transaction and its sender.
+pub trait Get {
+	fn get(hash: H256, from_address: H160, txn: &EthereumTransaction) -> Self;
+}
+
+/// Geth-compatible `pending` and `queued` pair.
+#[derive(Debug, Serialize)]
+pub struct TxPoolResult<T: Serialize> {
+	/// Transactions ready to be included in the next block.
+	pub pending: T,
+	/// Transactions waiting on a nonce gap, scheduled for future execution.
+	pub queued: T,
+}
+
+/// Textual summary of a pool transaction, as returned by `txpool_inspect`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+	pub to: Option<H160>,
+	pub value: U256,
+	pub gas: U256,
+	pub gas_price: U256,
+}
+
+impl Serialize for Summary {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let res = format!(
+			"{}: {} wei + {} gas x {} wei",
+			self.to
+				.map(|to| format!("0x{:x}", to))
+				.unwrap_or_else(|| "contract creation".to_string()),
+			self.value,
+			self.gas,
+			self.gas_price
+		);
+		serializer.serialize_str(&res)
+	}
+}
+
+impl Get for Summary {
+	fn get(_hash: H256, _from_address: H160, txn: &EthereumTransaction) -> Self {
+		let (action, value, gas_price, gas_limit) = match txn {
+			EthereumTransaction::Legacy(t) => (t.action, t.value, t.gas_price, t.gas_limit),
+			EthereumTransaction::EIP2930(t) => (t.action, t.value, t.gas_price, t.gas_limit),
+			EthereumTransaction::EIP1559(t) => (t.action, t.value, t.max_fee_per_gas, t.gas_limit),
+		};
+		Self {
+			to: match action {
+				TransactionAction::Call(to) => Some(to),
+				_ => None,
+			},
+			value,
+			gas_price,
+			gas: gas_limit,
+		}
+	}
+}
+
+impl Get for Transaction {
+	fn get(_hash: H256, from_address: H160, txn: &EthereumTransaction) -> Self {
+		let action = match txn {
+			EthereumTransaction::Legacy(t) => t.action,
+			EthereumTransaction::EIP2930(t) => t.action,
+			EthereumTransaction::EIP1559(t) => t.action,
+		};
+		let mut transaction: Transaction = txn.clone().into();
+		transaction.from = from_address;
+		transaction.to = match action {
+			TransactionAction::Call(to) => Some(to),
+			_ => None,
+		};
+		// Not mined yet, the gas price is just the max fee per gas.
+		if let EthereumTransaction::EIP1559(_) = txn {
+			transaction.gas_price = transaction.max_fee_per_gas;
+		}
+		transaction
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn summary_serialize() {
+		let call = Summary {
+			to: Some(H160::repeat_byte(0x11)),
+			value: U256::from(1),
+			gas: U256::from(21_000),
+			gas_price: U256::from(10),
+		};
+		let create = Summary {
+			to: None,
+			..call.clone()
+		};
+
+		assert_eq!(
+			serde_json::to_string(&call).unwrap(),
+			r#""0x1111111111111111111111111111111111111111: 1 wei + 21000 gas x 10 wei""#
+		);
+		assert_eq!(
+			serde_json::to_string(&create).unwrap(),
+			r#""contract creation: 1 wei + 21000 gas x 10 wei""#
+		);
+	}
+}
@@ -24,11 +24,17 @@ use jsonrpc_core::{BoxFuture, Result};
 use sc_client_api::backend::{Backend, StateBackend, StorageProvider};
 use sc_network::ExHashT;
 use sc_transaction_pool::ChainApi;
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_blockchain::HeaderBackend;
 use sp_core::hashing::keccak_256;
-use sp_runtime::traits::{BlakeTwo256, Block as BlockT};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Block as BlockT},
+};
 
 use fc_rpc_core::types::*;
+use fp_rpc::EthereumRuntimeRPCApi;
 
 use crate::{
 	eth::{rich_block_build, EthApi},
@@ -38,9 +44,12 @@ use crate::{
 impl<B, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H, A>
 where
 	B: BlockT<Hash = H256> + Send + Sync + 'static,
-	C: StorageProvider<B, BE> + HeaderBackend<B> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
 	BE: Backend<B> + 'static,
 	BE::State: StateBackend<BlakeTwo256>,
+	A: ChainApi<Block = B> + 'static,
 {
 	pub fn block_by_hash(&self, hash: H256, full: bool) -> BoxFuture<Result<Option<RichBlock>>> {
 		let client = Arc::clone(&self.client);
@@ -53,7 +62,7 @@ where
 				.map_err(|err| internal_err(format!("{:?}", err)))?
 			{
 				Some(hash) => hash,
-				_ => return Ok(None),
+				_ => return frontier_backend_client::missing_or_pruned::<B, _>(backend.as_ref()),
 			};
 			let substrate_hash = client
 				.expect_block_hash_from_id(&id)
@@ -97,8 +106,38 @@ where
 		let overrides = Arc::clone(&self.overrides);
 		let block_data_cache = Arc::clone(&self.block_data_cache);
 		let backend = Arc::clone(&self.backend);
+		let graph = Arc::clone(&self.graph);
+		let pending_block = Arc::clone(&self.pending_block);
 
 		Box::pin(async move {
+			if let BlockNumber::Pending = number {
+				let pending = match pending_block.block(client.as_ref(), graph.as_ref())? {
+					Some(pending) => pending,
+					None => return Ok(None),
+				};
+				let id = BlockId::Hash(pending.parent_hash);
+				let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(
+					client.as_ref(),
+					id,
+				);
+				let handler = overrides
+					.schemas
+					.get(&schema)
+					.unwrap_or(&overrides.fallback);
+
+				let mut rich_block = rich_block_build(
+					pending.block,
+					pending.statuses.into_iter().map(Some).collect(),
+					None,
+					full,
+					handler.base_fee(&id),
+					handler.is_eip1559(&id),
+				);
+				// The pending block is not sealed yet, so it has no hash.
+				rich_block.inner.header.hash = None;
+				return Ok(Some(rich_block));
+			}
+
 			let id = match frontier_backend_client::native_block_id::<B, C>(
 				client.as_ref(),
 				backend.as_ref(),
@@ -149,7 +188,7 @@ where
 			.map_err(|err| internal_err(format!("{:?}", err)))?
 		{
 			Some(hash) => hash,
-			_ => return Ok(None),
+			_ => return frontier_backend_client::missing_or_pruned::<B, _>(self.backend.as_ref()),
 		};
 		let schema =
 			frontier_backend_client::onchain_storage_schema::<B, C, BE>(self.client.as_ref(), id);
@@ -168,10 +207,10 @@ where
 
 	pub fn block_transaction_count_by_number(&self, number: BlockNumber) -> Result<Option<U256>> {
 		if let BlockNumber::Pending = number {
-			// get the pending transactions count
-			return Ok(Some(U256::from(
-				self.graph.validated_pool().ready().count(),
-			)));
+			return Ok(self
+				.pending_block
+				.block(self.client.as_ref(), self.graph.as_ref())?
+				.map(|pending| U256::from(pending.block.transactions.len())));
 		}
 
 		let id = match frontier_backend_client::native_block_id::<B, C>(
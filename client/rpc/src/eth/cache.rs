@@ -20,19 +20,22 @@ use std::{
 	collections::{BTreeMap, HashMap},
 	marker::PhantomData,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
 
 use ethereum::BlockV2 as EthereumBlock;
 use ethereum_types::{H256, U256};
 use futures::StreamExt;
+use futures_timer::Delay;
 use lru::LruCache;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 
 use codec::Decode;
 use sc_client_api::{
 	backend::{Backend, StateBackend, StorageProvider},
 	client::BlockchainEvents,
 };
+use sc_network::{ExHashT, NetworkService};
 use sc_service::SpawnTaskHandle;
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
@@ -48,6 +51,7 @@ use fp_storage::EthereumStorageSchema;
 use crate::{
 	frontier_backend_client,
 	overrides::{OverrideHandle, StorageOverride},
+	RpcMetrics,
 };
 
 enum EthBlockDataCacheMessage<B: BlockT> {
@@ -70,6 +74,8 @@ enum EthBlockDataCacheMessage<B: BlockT> {
 		block_hash: B::Hash,
 		statuses: Option<Vec<TransactionStatus>>,
 	},
+
+	Clear,
 }
 
 /// Manage LRU caches for block data and their transaction statuses.
@@ -84,6 +90,7 @@ impl<B: BlockT> EthBlockDataCache<B> {
 		overrides: Arc<OverrideHandle<B>>,
 		blocks_cache_size: usize,
 		statuses_cache_size: usize,
+		metrics: Option<RpcMetrics>,
 	) -> Self {
 		let (task_tx, mut task_rx) = mpsc::channel(100);
 		let outer_task_tx = task_tx.clone();
@@ -112,6 +119,7 @@ impl<B: BlockT> EthBlockDataCache<B> {
 						response_tx,
 					} => Self::request_current(
 						&spawn_handle,
+						metrics.as_ref().map(|metrics| (metrics, "blocks")),
 						&mut blocks_cache,
 						&mut awaiting_blocks,
 						Arc::clone(&overrides),
@@ -142,6 +150,7 @@ impl<B: BlockT> EthBlockDataCache<B> {
 						response_tx,
 					} => Self::request_current(
 						&spawn_handle,
+						metrics.as_ref().map(|metrics| (metrics, "statuses")),
 						&mut statuses_cache,
 						&mut awaiting_statuses,
 						Arc::clone(&overrides),
@@ -169,6 +178,11 @@ impl<B: BlockT> EthBlockDataCache<B> {
 							statuses_cache.put(block_hash, statuses);
 						}
 					}
+
+					Clear => {
+						blocks_cache.clear();
+						statuses_cache.clear();
+					}
 				}
 			}
 		});
@@ -178,6 +192,7 @@ impl<B: BlockT> EthBlockDataCache<B> {
 
 	fn request_current<T, F>(
 		spawn_handle: &SpawnTaskHandle,
+		metrics: Option<(&RpcMetrics, &str)>,
 		cache: &mut LruCache<B::Hash, T>,
 		wait_list: &mut HashMap<B::Hash, Vec<oneshot::Sender<Option<T>>>>,
 		overrides: Arc<OverrideHandle<B>>,
@@ -191,8 +206,13 @@ impl<B: BlockT> EthBlockDataCache<B> {
 		F: FnOnce(&Box<dyn StorageOverride<B> + Send + Sync>) -> EthBlockDataCacheMessage<B>,
 		F: Send + 'static,
 	{
+		let cached = cache.get(&block_hash).cloned();
+		if let Some((metrics, cache_name)) = metrics {
+			metrics.cache_lookup(cache_name, cached.is_some());
+		}
+
 		// Data is cached, we respond immediately.
-		if let Some(data) = cache.get(&block_hash).cloned() {
+		if let Some(data) = cached {
 			let _ = response_tx.send(Some(data));
 			return;
 		}
@@ -263,8 +283,16 @@ impl<B: BlockT> EthBlockDataCache<B> {
 
 		response_rx.await.ok()?
 	}
+
+	/// Drop every cached block and transaction statuses, for example after blocks were reverted.
+	pub async fn clear(&self) {
+		let _ = self.0.send(EthBlockDataCacheMessage::Clear).await;
+	}
 }
 
+/// Interval at which `EthTask::sync_progress_task` polls the network sync status.
+const SYNC_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct EthTask<B, C, BE>(PhantomData<(B, C, BE)>);
 
 impl<B, C, BE> EthTask<B, C, BE>
@@ -366,6 +394,7 @@ where
 		client: Arc<C>,
 		filter_pool: Arc<Mutex<BTreeMap<U256, FilterPoolItem>>>,
 		retain_threshold: u64,
+		metrics: Option<RpcMetrics>,
 	) {
 		let mut notification_st = client.import_notification_stream();
 
@@ -393,6 +422,10 @@ where
 				for key in remove_list {
 					filter_pool.remove(&key);
 				}
+
+				if let Some(metrics) = &metrics {
+					metrics.set_filter_pool_size(filter_pool.len());
+				}
 			}
 		}
 	}
@@ -402,6 +435,7 @@ where
 		overrides: Arc<OverrideHandle<B>>,
 		fee_history_cache: FeeHistoryCache,
 		block_limit: u64,
+		metrics: Option<RpcMetrics>,
 	) {
 		use sp_runtime::Permill;
 
@@ -537,6 +571,10 @@ where
 					let key = first_out - i;
 					fee_history_cache.remove(&key);
 				}
+
+				if let Some(metrics) = &metrics {
+					metrics.set_fee_history_cache_size(fee_history_cache.len());
+				}
 			}
 		};
 
@@ -581,4 +619,67 @@ where
 			}
 		}
 	}
+
+	/// Task that keeps the major sync progress reported by `eth_syncing` and the `syncing`
+	/// subscription up to date.
+	pub async fn sync_progress_task<H: ExHashT>(
+		client: Arc<C>,
+		network: Arc<NetworkService<B, H>>,
+		progress: watch::Sender<Option<SyncInfo>>,
+	) {
+		let mut starting_block = None;
+		loop {
+			Delay::new(SYNC_PROGRESS_INTERVAL).await;
+			let status = match network.status().await {
+				Ok(status) => status,
+				// The network worker is gone.
+				Err(()) => return,
+			};
+
+			let current_block: u64 = client.info().best_number.unique_saturated_into();
+			let info = if network.is_major_syncing() || status.warp_sync.is_some() {
+				let starting_block = *starting_block.get_or_insert(current_block);
+				let highest_block = status
+					.best_seen_block
+					.map(UniqueSaturatedInto::<u64>::unique_saturated_into)
+					.unwrap_or(current_block)
+					.max(current_block);
+				// Substrate does not download the state in chunks: warp sync progress is
+				// reported as the percentage of the state downloaded, out of 100 chunks.
+				let (warp_chunks_amount, warp_chunks_processed) = match status.warp_sync {
+					Some(_) => (
+						Some(U256::from(100)),
+						Some(U256::from(
+							status.state_sync.map(|state| state.percentage).unwrap_or(0),
+						)),
+					),
+					None => (None, None),
+				};
+				Some(SyncInfo {
+					starting_block: U256::from(starting_block),
+					current_block: U256::from(current_block),
+					highest_block: U256::from(highest_block),
+					warp_chunks_amount,
+					warp_chunks_processed,
+				})
+			} else {
+				starting_block = None;
+				None
+			};
+
+			if *progress.borrow() != info && progress.send(info).is_err() {
+				// Nobody is listening anymore.
+				return;
+			}
+		}
+	}
+}
+
+/// Progress of the major sync, `None` when the node is not major syncing. It is kept up to date
+/// by `EthTask::sync_progress_task`.
+pub type SyncProgress = watch::Receiver<Option<SyncInfo>>;
+
+/// Channel `EthTask::sync_progress_task` reports the major sync progress to.
+pub fn sync_progress_channel() -> (watch::Sender<Option<SyncInfo>>, SyncProgress) {
+	watch::channel(None)
 }
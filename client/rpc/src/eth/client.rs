@@ -48,20 +48,32 @@ where
 	}
 
 	pub fn syncing(&self) -> Result<SyncStatus> {
-		if self.network.is_major_syncing() {
+		if let Some(info) = self.sync_progress.borrow().clone() {
+			Ok(SyncStatus::Info(info))
+		} else if self.network.is_major_syncing() {
+			// The sync progress task has not caught up with the sync yet.
 			let block_number = U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(
-				self.client.info().best_number.clone(),
+				self.client.info().best_number,
 			));
 			Ok(SyncStatus::Info(SyncInfo {
-				starting_block: U256::zero(),
+				starting_block: block_number,
 				current_block: block_number,
-				// TODO `highest_block` is not correct, should load `best_seen_block` from NetworkWorker,
-				// but afaik that is not currently possible in Substrate:
-				// https://github.com/paritytech/substrate/issues/7311
 				highest_block: block_number,
 				warp_chunks_amount: None,
 				warp_chunks_processed: None,
 			}))
+		} else if let Some((starting_block, current_block, highest_block)) =
+			self.backfill_progress.status()
+		{
+			// Historical blocks are still being mapped, so older transactions and receipts
+			// cannot be found yet.
+			Ok(SyncStatus::Info(SyncInfo {
+				starting_block: U256::from(starting_block),
+				current_block: U256::from(current_block),
+				highest_block: U256::from(highest_block),
+				warp_chunks_amount: None,
+				warp_chunks_processed: None,
+			}))
 		} else {
 			Ok(SyncStatus::None)
 		}
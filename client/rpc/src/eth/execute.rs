@@ -18,7 +18,8 @@
 
 use std::sync::Arc;
 
-use ethereum_types::{H256, U256};
+use ethereum::AccessListItem;
+use ethereum_types::{H160, H256, U256};
 use evm::{ExitError, ExitReason};
 use jsonrpc_core::{BoxFuture, Error, ErrorCode, Result, Value};
 
@@ -36,10 +37,15 @@ use sp_runtime::{
 use fc_rpc_core::types::*;
 use fp_rpc::EthereumRuntimeRPCApi;
 
-use crate::{
-	eth::{pending_runtime_api, EthApi},
-	frontier_backend_client, internal_err,
-};
+use crate::{eth::EthApi, frontier_backend_client, internal_err};
+
+/// How an `eth_call` execution ended. `value` is the output of a call, or the code of the
+/// contract a create deployed.
+pub struct CallOutcome {
+	pub exit_reason: ExitReason,
+	pub value: Vec<u8>,
+	pub used_gas: U256,
+}
 
 impl<B, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H, A>
 where
@@ -52,6 +58,18 @@ where
 	A: ChainApi<Block = B> + 'static,
 {
 	pub fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes> {
+		let outcome = self.call_outcome(request, number)?;
+		error_on_execution_failure(&outcome.exit_reason, &outcome.value)?;
+		Ok(Bytes(outcome.value))
+	}
+
+	/// Execute `request` like `eth_call`, but return how the execution ended along with the gas
+	/// it used instead of failing when it did not succeed.
+	pub fn call_outcome(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> Result<CallOutcome> {
 		let CallRequest {
 			from,
 			to,
@@ -83,9 +101,8 @@ where
 			Some(id) => (id, self.client.runtime_api()),
 			None => {
 				// Not mapped in the db, assume pending.
-				let id = BlockId::Hash(self.client.info().best_hash);
-				let api = pending_runtime_api(self.client.as_ref(), self.graph.as_ref())?;
-				(id, api)
+				self.pending_block
+					.runtime_api(self.client.as_ref(), self.graph.as_ref())?
 			}
 		};
 
@@ -124,6 +141,7 @@ where
 				}
 			}
 		};
+		let gas_limit = cap_gas(gas_limit, self.gas_cap);
 		let data = data.map(|d| d.0).unwrap_or_default();
 		match to {
 			Some(to) => {
@@ -144,8 +162,11 @@ where
 					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &info.value)?;
-					Ok(Bytes(info.value))
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value: info.value,
+						used_gas: info.used_gas,
+					})
 				} else if api_version >= 2 && api_version < 4 {
 					// Post-london
 					#[allow(deprecated)]
@@ -164,9 +185,12 @@ where
 					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &info.value)?;
-					Ok(Bytes(info.value))
-				} else if api_version == 4 {
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value: info.value,
+						used_gas: info.used_gas,
+					})
+				} else if api_version >= 4 {
 					// Post-london + access list support
 					let access_list = access_list.unwrap_or_default();
 					let info = api
@@ -191,8 +215,11 @@ where
 						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &info.value)?;
-					Ok(Bytes(info.value))
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value: info.value,
+						used_gas: info.used_gas,
+					})
 				} else {
 					return Err(internal_err(format!(
 						"failed to retrieve Runtime Api version"
@@ -216,12 +243,18 @@ where
 					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &[])?;
-
-					let code = api
-						.account_code_at(&id, info.value)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
-					Ok(Bytes(code))
+					// A failed create has no code, and no revert data either.
+					let value = match info.exit_reason {
+						ExitReason::Succeed(_) => api
+							.account_code_at(&id, info.value)
+							.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?,
+						_ => Vec::new(),
+					};
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value,
+						used_gas: info.used_gas,
+					})
 				} else if api_version >= 2 && api_version < 4 {
 					// Post-london
 					#[allow(deprecated)]
@@ -239,13 +272,19 @@ where
 					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &[])?;
-
-					let code = api
-						.account_code_at(&id, info.value)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
-					Ok(Bytes(code))
-				} else if api_version == 4 {
+					// A failed create has no code, and no revert data either.
+					let value = match info.exit_reason {
+						ExitReason::Succeed(_) => api
+							.account_code_at(&id, info.value)
+							.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?,
+						_ => Vec::new(),
+					};
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value,
+						used_gas: info.used_gas,
+					})
+				} else if api_version >= 4 {
 					// Post-london + access list support
 					let access_list = access_list.unwrap_or_default();
 					let info = api
@@ -269,12 +308,18 @@ where
 						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
 						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-					error_on_execution_failure(&info.exit_reason, &[])?;
-
-					let code = api
-						.account_code_at(&id, info.value)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
-					Ok(Bytes(code))
+					// A failed create has no code, and no revert data either.
+					let value = match info.exit_reason {
+						ExitReason::Succeed(_) => api
+							.account_code_at(&id, info.value)
+							.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?,
+						_ => Vec::new(),
+					};
+					Ok(CallOutcome {
+						exit_reason: info.exit_reason,
+						value,
+						used_gas: info.used_gas,
+					})
 				} else {
 					return Err(internal_err(format!(
 						"failed to retrieve Runtime Api version"
@@ -291,6 +336,7 @@ where
 	) -> BoxFuture<Result<U256>> {
 		let client = Arc::clone(&self.client);
 		let block_data_cache = Arc::clone(&self.block_data_cache);
+		let gas_cap = self.gas_cap;
 
 		Box::pin(async move {
 			// Define the lower bound of estimate
@@ -336,7 +382,7 @@ where
 					frontier_backend_client::onchain_storage_schema::<B, C, BE>(&client, id);
 				let block = block_data_cache.current_block(schema, substrate_hash).await;
 				if let Some(block) = block {
-					Ok(block.header.gas_limit)
+					Ok(cap_gas(block.header.gas_limit, gas_cap))
 				} else {
 					return Err(internal_err("block unavailable, cannot query gas limit"));
 				}
@@ -344,7 +390,7 @@ where
 
 			// Determine the highest possible gas limits
 			let mut highest = match request.gas {
-				Some(gas) => gas,
+				Some(gas) => cap_gas(gas, gas_cap),
 				None => {
 					// query current block's gas limit
 					get_current_block_gas_limit().await?
@@ -668,6 +714,143 @@ where
 			}
 		})
 	}
+
+	pub fn create_access_list(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> Result<AccessListResult> {
+		let CallRequest {
+			from,
+			to,
+			gas_price,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			gas,
+			value,
+			data,
+			nonce,
+			access_list,
+			..
+		} = request;
+
+		let (max_fee_per_gas, max_priority_fee_per_gas) = {
+			let details = fee_details(gas_price, max_fee_per_gas, max_priority_fee_per_gas)?;
+			(details.max_fee_per_gas, details.max_priority_fee_per_gas)
+		};
+
+		let (id, api) = match frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			number,
+		)? {
+			Some(id) => (id, self.client.runtime_api()),
+			None => {
+				// Not mapped in the db, assume pending.
+				self.pending_block
+					.runtime_api(self.client.as_ref(), self.graph.as_ref())?
+			}
+		};
+
+		let api_version =
+			if let Ok(Some(api_version)) = api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&id) {
+				api_version
+			} else {
+				return Err(internal_err(format!(
+					"failed to retrieve Runtime Api version"
+				)));
+			};
+		if api_version < 6 {
+			return Err(internal_err(format!(
+				"eth_createAccessList is not supported by the runtime at this block"
+			)));
+		}
+
+		// use given gas limit or query current block's limit
+		let gas_limit = match gas {
+			Some(amount) => amount,
+			None => match api
+				.current_block(&id)
+				.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+			{
+				Some(block) => block.header.gas_limit,
+				None => {
+					return Err(internal_err(format!(
+						"block unavailable, cannot query gas limit"
+					)))
+				}
+			},
+		};
+		let gas_limit = cap_gas(gas_limit, self.gas_cap);
+		let from = from.unwrap_or_default();
+		let data = data.map(|d| d.0).unwrap_or_default();
+		let value = value.unwrap_or_default();
+
+		// Warming up the listed state changes the gas available to the execution, and so possibly
+		// its path: execute again with the produced list until it no longer changes.
+		let mut access_list: Vec<(H160, Vec<H256>)> = access_list
+			.unwrap_or_default()
+			.into_iter()
+			.map(|item| (item.address, item.storage_keys))
+			.collect();
+		let mut iterations = 0;
+		loop {
+			iterations += 1;
+			let info = api
+				.create_access_list(
+					&id,
+					from,
+					to,
+					data.clone(),
+					value,
+					gas_limit,
+					max_fee_per_gas,
+					max_priority_fee_per_gas,
+					nonce,
+					Some(access_list.clone()),
+				)
+				.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+				.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
+
+			if info.access_list == access_list || iterations == MAX_ACCESS_LIST_ITERATIONS {
+				let error = error_on_execution_failure(&info.exit_reason, &info.value)
+					.err()
+					.map(|err| err.message);
+				return Ok(AccessListResult {
+					access_list: info
+						.access_list
+						.into_iter()
+						.map(|(address, storage_keys)| AccessListItem {
+							address,
+							storage_keys,
+						})
+						.collect(),
+					gas_used: info.used_gas,
+					error,
+				});
+			}
+			access_list = info.access_list;
+		}
+	}
+}
+
+/// Error code of reverted executions, as returned by Geth.
+pub const EXECUTION_REVERTED_CODE: i64 = 3;
+
+/// Maximum number of executions `eth_createAccessList` performs for the access list to settle.
+const MAX_ACCESS_LIST_ITERATIONS: usize = 8;
+
+/// Selector of Solidity's `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of Solidity's `Panic(uint256)`.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Lower `gas_limit` to the gas cap of calls and estimations, when one is set.
+fn cap_gas(gas_limit: U256, gas_cap: Option<u64>) -> U256 {
+	match gas_cap {
+		Some(gas_cap) => gas_limit.min(U256::from(gas_cap)),
+		None => gas_limit,
+	}
 }
 
 pub fn error_on_execution_failure(reason: &ExitReason, data: &[u8]) -> Result<()> {
@@ -688,25 +871,11 @@ pub fn error_on_execution_failure(reason: &ExitReason, data: &[u8]) -> Result<()
 				data: Some(Value::String("0x".to_string())),
 			})
 		}
-		ExitReason::Revert(_) => {
-			let mut message = "VM Exception while processing transaction: revert".to_string();
-			// A minimum size of error function selector (4) + offset (32) + string length (32)
-			// should contain a utf-8 encoded revert reason.
-			if data.len() > 68 {
-				let message_len = data[36..68].iter().sum::<u8>();
-				if data.len() >= 68 + message_len as usize {
-					let body: &[u8] = &data[68..68 + message_len as usize];
-					if let Ok(reason) = std::str::from_utf8(body) {
-						message = format!("{} {}", message, reason.to_string());
-					}
-				}
-			}
-			Err(Error {
-				code: ErrorCode::InternalError,
-				message,
-				data: Some(Value::String(hex::encode(data))),
-			})
-		}
+		ExitReason::Revert(_) => Err(Error {
+			code: ErrorCode::ServerError(EXECUTION_REVERTED_CODE),
+			message: decode_revert_reason(data),
+			data: Some(Value::String(format!("0x{}", hex::encode(data)))),
+		}),
 		ExitReason::Fatal(e) => Err(Error {
 			code: ErrorCode::InternalError,
 			message: format!("evm fatal: {:?}", e),
@@ -715,6 +884,69 @@ pub fn error_on_execution_failure(reason: &ExitReason, data: &[u8]) -> Result<()
 	}
 }
 
+/// Readable message for the revert data of an execution, decoding Solidity's `Error(string)`
+/// and `Panic(uint256)`. Custom errors are reported by their selector.
+pub fn decode_revert_reason(data: &[u8]) -> String {
+	let message = "execution reverted".to_string();
+	if data.len() < 4 {
+		return message;
+	}
+
+	let (selector, body) = data.split_at(4);
+	if selector == ERROR_SELECTOR {
+		match decode_abi_string(body) {
+			Some(reason) => format!("{}: {}", message, reason),
+			None => message,
+		}
+	} else if selector == PANIC_SELECTOR {
+		match body.get(..32) {
+			Some(code) => format!("{}: {}", message, panic_reason(U256::from_big_endian(code))),
+			None => message,
+		}
+	} else {
+		format!("{}: custom error 0x{}", message, hex::encode(selector))
+	}
+}
+
+/// Decode an ABI-encoded `string`, the only parameter of `body`.
+fn decode_abi_string(body: &[u8]) -> Option<String> {
+	let word = |at: usize| -> Option<usize> {
+		let word = U256::from_big_endian(body.get(at..at.checked_add(32)?)?);
+		if word > U256::from(usize::MAX) {
+			return None;
+		}
+		Some(word.as_usize())
+	};
+
+	let offset = word(0)?;
+	let len = word(offset)?;
+	let start = offset.checked_add(32)?;
+	let bytes = body.get(start..start.checked_add(len)?)?;
+	String::from_utf8(bytes.to_vec()).ok()
+}
+
+/// Description of a Solidity panic code.
+fn panic_reason(code: U256) -> String {
+	let reason = if code > U256::from(u8::MAX) {
+		"unknown panic code"
+	} else {
+		match code.low_u64() {
+			0x00 => "generic compiler panic",
+			0x01 => "assertion failed",
+			0x11 => "arithmetic underflow or overflow",
+			0x12 => "division or modulo by zero",
+			0x21 => "enum conversion out of bounds",
+			0x22 => "invalid encoded storage byte array",
+			0x31 => "pop on an empty array",
+			0x32 => "array index out of bounds",
+			0x41 => "out of memory",
+			0x51 => "call to a zero-initialized function",
+			_ => "unknown panic code",
+		}
+	};
+	format!("panic: {} (0x{:x})", reason, code)
+}
+
 struct FeeDetails {
 	gas_price: Option<U256>,
 	max_fee_per_gas: Option<U256>,
@@ -754,3 +986,126 @@ fn fee_details(
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn word(value: usize) -> [u8; 32] {
+		let mut word = [0u8; 32];
+		U256::from(value).to_big_endian(&mut word);
+		word
+	}
+
+	/// ABI encoding of `Error(string)` with `reason`.
+	fn error(reason: &[u8]) -> Vec<u8> {
+		let mut data = ERROR_SELECTOR.to_vec();
+		data.extend_from_slice(&word(32));
+		data.extend_from_slice(&word(reason.len()));
+		data.extend_from_slice(reason);
+		data.resize(data.len() + (32 - reason.len() % 32) % 32, 0);
+		data
+	}
+
+	fn panic(code: U256) -> Vec<u8> {
+		let mut data = PANIC_SELECTOR.to_vec();
+		let mut word = [0u8; 32];
+		code.to_big_endian(&mut word);
+		data.extend_from_slice(&word);
+		data
+	}
+
+	#[test]
+	fn decodes_error_strings() {
+		assert_eq!(
+			decode_revert_reason(&error(b"error_msg")),
+			"execution reverted: error_msg"
+		);
+		assert_eq!(decode_revert_reason(&error(b"")), "execution reverted: ");
+
+		// Lengths that are not a multiple of the word size, and reasons longer than 255 bytes.
+		let reason = "x".repeat(300);
+		assert_eq!(
+			decode_revert_reason(&error(reason.as_bytes())),
+			format!("execution reverted: {}", reason)
+		);
+		assert_eq!(
+			decode_abi_string(&error(b"odd")[4..]),
+			Some("odd".to_string())
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_error_strings() {
+		// Truncated in the middle of the reason.
+		let data = error(b"error_msg");
+		assert_eq!(
+			decode_revert_reason(&data[..4 + 64 + 4]),
+			"execution reverted"
+		);
+		// Truncated before the length.
+		assert_eq!(decode_revert_reason(&data[..4 + 40]), "execution reverted");
+		// Offset beyond the data.
+		let mut data = error(b"error_msg");
+		data[4..36].copy_from_slice(&word(1024));
+		assert_eq!(decode_revert_reason(&data), "execution reverted");
+		// Offset and length that would overflow.
+		let mut data = error(b"error_msg");
+		data[4..36].copy_from_slice(&[0xff; 32]);
+		assert_eq!(decode_abi_string(&data[4..]), None);
+		let mut data = error(b"error_msg");
+		data[36..68].copy_from_slice(&word(usize::MAX));
+		assert_eq!(decode_abi_string(&data[4..]), None);
+		// Invalid UTF-8.
+		assert_eq!(
+			decode_revert_reason(&error(&[0xff, 0xfe])),
+			"execution reverted"
+		);
+	}
+
+	#[test]
+	fn decodes_panic_codes() {
+		assert_eq!(
+			decode_revert_reason(&panic(U256::from(0x11))),
+			"execution reverted: panic: arithmetic underflow or overflow (0x11)"
+		);
+		assert_eq!(
+			decode_revert_reason(&panic(U256::from(0x01))),
+			"execution reverted: panic: assertion failed (0x1)"
+		);
+		assert_eq!(
+			panic_reason(U256::from(0x99)),
+			"panic: unknown panic code (0x99)"
+		);
+		assert_eq!(
+			panic_reason(U256::from(0x111)),
+			"panic: unknown panic code (0x111)"
+		);
+		// Codes whose low bits match a known code are still unknown.
+		assert_eq!(
+			panic_reason(U256::from(0x11) + (U256::one() << 128)),
+			format!(
+				"panic: unknown panic code (0x{:x})",
+				U256::from(0x11) + (U256::one() << 128)
+			)
+		);
+		// Truncated code.
+		assert_eq!(
+			decode_revert_reason(&panic(U256::from(0x11))[..20]),
+			"execution reverted"
+		);
+	}
+
+	#[test]
+	fn reports_custom_errors_by_selector() {
+		assert_eq!(
+			decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef, 0x00]),
+			"execution reverted: custom error 0xdeadbeef"
+		);
+		assert_eq!(
+			decode_revert_reason(&[0x08, 0xc3, 0x79]),
+			"execution reverted"
+		);
+		assert_eq!(decode_revert_reason(&[]), "execution reverted");
+	}
+}
@@ -194,7 +194,7 @@ where
 						let mut current_number = filter
 							.to_block
 							.clone()
-							.and_then(|v| v.to_min_block_num())
+							.and_then(|v| range_bound::<B, C>(self.client.as_ref(), &v))
 							.map(|s| s.unique_saturated_into())
 							.unwrap_or(best_number);
 
@@ -212,7 +212,7 @@ where
 						let filter_from = filter
 							.from_block
 							.clone()
-							.and_then(|v| v.to_min_block_num())
+							.and_then(|v| range_bound::<B, C>(self.client.as_ref(), &v))
 							.map(|s| s.unique_saturated_into())
 							.unwrap_or(last_poll);
 
@@ -324,7 +324,7 @@ where
 			let mut current_number = filter
 				.to_block
 				.clone()
-				.and_then(|v| v.to_min_block_num())
+				.and_then(|v| range_bound::<B, C>(client.as_ref(), &v))
 				.map(|s| s.unique_saturated_into())
 				.unwrap_or(best_number);
 
@@ -339,7 +339,7 @@ where
 			let from_number = filter
 				.from_block
 				.clone()
-				.and_then(|v| v.to_min_block_num())
+				.and_then(|v| range_bound::<B, C>(client.as_ref(), &v))
 				.map(|s| s.unique_saturated_into())
 				.unwrap_or(client.info().best_number);
 
@@ -388,7 +388,12 @@ where
 					.map_err(|err| internal_err(format!("{:?}", err)))?
 				{
 					Some(hash) => hash,
-					_ => return Ok(Vec::new()),
+					_ => {
+						return frontier_backend_client::missing_or_pruned::<B, ()>(
+							backend.as_ref(),
+						)
+						.map(|_| Vec::new())
+					}
 				};
 				let substrate_hash = client
 					.expect_block_hash_from_id(&id)
@@ -411,7 +416,7 @@ where
 				let mut current_number = filter
 					.to_block
 					.clone()
-					.and_then(|v| v.to_min_block_num())
+					.and_then(|v| range_bound::<B, C>(client.as_ref(), &v))
 					.map(|s| s.unique_saturated_into())
 					.unwrap_or(best_number);
 
@@ -422,9 +427,13 @@ where
 				let from_number = filter
 					.from_block
 					.clone()
-					.and_then(|v| v.to_min_block_num())
+					.and_then(|v| range_bound::<B, C>(client.as_ref(), &v))
 					.map(|s| s.unique_saturated_into())
 					.unwrap_or(client.info().best_number);
+				frontier_backend_client::ensure_retained::<B>(
+					backend.as_ref(),
+					from_number.unique_saturated_into(),
+				)?;
 
 				let _ = filter_range_logs(
 					client.as_ref(),
@@ -443,6 +452,21 @@ where
 	}
 }
 
+/// Block number of a filter range bound. `finalized` and `safe` resolve to the last finalized
+/// block, other tags are left to the caller.
+fn range_bound<B, C>(client: &C, number: &BlockNumber) -> Option<u64>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+{
+	match number {
+		BlockNumber::Finalized | BlockNumber::Safe => Some(
+			UniqueSaturatedInto::<u64>::unique_saturated_into(client.info().finalized_number),
+		),
+		number => number.to_min_block_num(),
+	}
+}
+
 async fn filter_range_logs<B: BlockT, C, BE>(
 	client: &C,
 	backend: &fc_db::Backend<B>,
@@ -23,6 +23,7 @@ mod execute;
 mod fee;
 mod filter;
 mod mining;
+mod pending;
 mod state;
 mod submit;
 mod transaction;
@@ -37,8 +38,8 @@ use jsonrpc_core::Result;
 use sc_client_api::backend::{Backend, StateBackend, StorageProvider};
 use sc_network::{ExHashT, NetworkService};
 use sc_transaction_pool::{ChainApi, Pool};
-use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
-use sp_api::{Core, ProvideRuntimeApi};
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_blockchain::HeaderBackend;
 use sp_core::hashing::keccak_256;
@@ -47,14 +48,22 @@ use sp_runtime::{
 	traits::{BlakeTwo256, Block as BlockT, UniqueSaturatedInto},
 };
 
+use fc_mapping_sync::BackfillProgress;
 use fc_rpc_core::{types::*, EthApi as EthApiT};
 use fp_rpc::{ConvertTransactionRuntimeApi, EthereumRuntimeRPCApi, TransactionStatus};
 
-use crate::{internal_err, overrides::OverrideHandle, public_key, signer::EthSigner};
+use crate::{
+	internal_err,
+	overrides::OverrideHandle,
+	public_key,
+	signer::{EthImpersonator, EthSigner},
+};
 
 pub use self::{
-	cache::{EthBlockDataCache, EthTask},
+	cache::{sync_progress_channel, EthBlockDataCache, EthTask, SyncProgress},
+	execute::CallOutcome,
 	filter::EthFilterApi,
+	pending::{EthPendingBlockCache, PendingBlock, PendingBlockInherents},
 };
 
 pub struct EthApi<B: BlockT, C, P, CT, BE, H: ExHashT, A: ChainApi> {
@@ -65,11 +74,16 @@ pub struct EthApi<B: BlockT, C, P, CT, BE, H: ExHashT, A: ChainApi> {
 	network: Arc<NetworkService<B, H>>,
 	is_authority: bool,
 	signers: Vec<Box<dyn EthSigner>>,
+	impersonator: Option<Arc<dyn EthImpersonator>>,
 	overrides: Arc<OverrideHandle<B>>,
 	backend: Arc<fc_db::Backend<B>>,
 	block_data_cache: Arc<EthBlockDataCache<B>>,
 	fee_history_limit: u64,
 	fee_history_cache: FeeHistoryCache,
+	pending_block: Arc<EthPendingBlockCache<B>>,
+	backfill_progress: Arc<BackfillProgress>,
+	sync_progress: SyncProgress,
+	gas_cap: Option<u64>,
 	_marker: PhantomData<(B, BE)>,
 }
 
@@ -81,12 +95,17 @@ impl<B: BlockT, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H
 		convert_transaction: Option<CT>,
 		network: Arc<NetworkService<B, H>>,
 		signers: Vec<Box<dyn EthSigner>>,
+		impersonator: Option<Arc<dyn EthImpersonator>>,
 		overrides: Arc<OverrideHandle<B>>,
 		backend: Arc<fc_db::Backend<B>>,
 		is_authority: bool,
 		block_data_cache: Arc<EthBlockDataCache<B>>,
 		fee_history_limit: u64,
 		fee_history_cache: FeeHistoryCache,
+		pending_block: Arc<EthPendingBlockCache<B>>,
+		backfill_progress: Arc<BackfillProgress>,
+		sync_progress: SyncProgress,
+		gas_cap: Option<u64>,
 	) -> Self {
 		Self {
 			client,
@@ -96,11 +115,16 @@ impl<B: BlockT, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H
 			network,
 			is_authority,
 			signers,
+			impersonator,
 			overrides,
 			backend,
 			block_data_cache,
 			fee_history_limit,
 			fee_history_cache,
+			pending_block,
+			backfill_progress,
+			sync_progress,
+			gas_cap,
 			_marker: PhantomData,
 		}
 	}
@@ -221,6 +245,13 @@ where
 		self.transaction_receipt(hash)
 	}
 
+	fn block_receipts(
+		&self,
+		number: BlockNumber,
+	) -> BoxFuture<'static, Result<Option<Vec<Receipt>>>> {
+		self.block_receipts(number)
+	}
+
 	// ########################################################################
 	// State
 	// ########################################################################
@@ -257,6 +288,14 @@ where
 		self.estimate_gas(request, number)
 	}
 
+	fn create_access_list(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+	) -> Result<AccessListResult> {
+		self.create_access_list(request, number)
+	}
+
 	// ########################################################################
 	// Fee
 	// ########################################################################
@@ -315,7 +354,7 @@ where
 	}
 }
 
-fn rich_block_build(
+pub(crate) fn rich_block_build(
 	block: EthereumBlock,
 	statuses: Vec<Option<TransactionStatus>>,
 	hash: Option<H256>,
@@ -386,7 +425,7 @@ fn rich_block_build(
 	}
 }
 
-fn transaction_build(
+pub(crate) fn transaction_build(
 	ethereum_transaction: EthereumTransaction,
 	block: Option<EthereumBlock>,
 	status: Option<TransactionStatus>,
@@ -472,35 +511,187 @@ fn transaction_build(
 	transaction
 }
 
-fn pending_runtime_api<'a, B: BlockT, C, BE, A: ChainApi>(
-	client: &'a C,
-	graph: &'a Pool<A>,
-) -> Result<sp_api::ApiRef<'a, C::Api>>
+fn receipt_build(
+	block: &EthereumBlock,
+	receipts: &[ethereum::ReceiptV3],
+	statuses: &[TransactionStatus],
+	index: usize,
+	block_hash: Option<H256>,
+	base_fee: Option<U256>,
+	is_eip1559: bool,
+) -> Result<Receipt> {
+	let hash = statuses[index].transaction_hash;
+	let receipt = receipts[index].clone();
+
+	let (logs, logs_bloom, status_code, cumulative_gas_used, gas_used) = if !is_eip1559 {
+		// Pre-london frontier update stored receipts require cumulative gas calculation.
+		match receipt {
+			ethereum::ReceiptV3::Legacy(d) => {
+				let index = core::cmp::min(receipts.len(), index + 1);
+				let cumulative_gas: u32 = receipts[..index]
+					.iter()
+					.map(|r| match r {
+						ethereum::ReceiptV3::Legacy(d) => Ok(d.used_gas.as_u32()),
+						_ => Err(internal_err(format!(
+							"Unknown receipt for request {}",
+							hash
+						))),
+					})
+					.sum::<Result<u32>>()?;
+				(
+					d.logs,
+					d.logs_bloom,
+					d.status_code,
+					U256::from(cumulative_gas),
+					d.used_gas,
+				)
+			}
+			_ => {
+				return Err(internal_err(format!(
+					"Unknown receipt for request {}",
+					hash
+				)))
+			}
+		}
+	} else {
+		match receipt {
+			ethereum::ReceiptV3::Legacy(d)
+			| ethereum::ReceiptV3::EIP2930(d)
+			| ethereum::ReceiptV3::EIP1559(d) => {
+				let cumulative_gas = d.used_gas;
+				let gas_used = if index > 0 {
+					let previous_receipt = receipts[index - 1].clone();
+					let previous_gas_used = match previous_receipt {
+						ethereum::ReceiptV3::Legacy(d)
+						| ethereum::ReceiptV3::EIP2930(d)
+						| ethereum::ReceiptV3::EIP1559(d) => d.used_gas,
+					};
+					cumulative_gas.saturating_sub(previous_gas_used)
+				} else {
+					cumulative_gas
+				};
+				(
+					d.logs,
+					d.logs_bloom,
+					d.status_code,
+					cumulative_gas,
+					gas_used,
+				)
+			}
+		}
+	};
+
+	let status = statuses[index].clone();
+	// Logs are indexed from the first log of the block.
+	let pre_receipts_log_index: u32 = receipts
+		[..core::cmp::min(receipts.len(), status.transaction_index as usize)]
+		.iter()
+		.map(|r| match r {
+			ethereum::ReceiptV3::Legacy(d)
+			| ethereum::ReceiptV3::EIP2930(d)
+			| ethereum::ReceiptV3::EIP1559(d) => d.logs.len() as u32,
+		})
+		.sum();
+
+	let transaction = block.transactions[index].clone();
+	let effective_gas_price = match transaction {
+		EthereumTransaction::Legacy(t) => t.gas_price,
+		EthereumTransaction::EIP2930(t) => t.gas_price,
+		EthereumTransaction::EIP1559(t) => base_fee
+			.unwrap_or_default()
+			.checked_add(t.max_priority_fee_per_gas)
+			.unwrap_or(U256::max_value())
+			.min(t.max_fee_per_gas),
+	};
+
+	Ok(Receipt {
+		transaction_hash: Some(status.transaction_hash),
+		transaction_index: Some(status.transaction_index.into()),
+		block_hash,
+		from: Some(status.from),
+		to: status.to,
+		block_number: Some(block.header.number),
+		cumulative_gas_used,
+		gas_used: Some(gas_used),
+		contract_address: status.contract_address,
+		logs: logs
+			.iter()
+			.enumerate()
+			.map(|(i, log)| Log {
+				address: log.address,
+				topics: log.topics.clone(),
+				data: Bytes(log.data.clone()),
+				block_hash,
+				block_number: Some(block.header.number),
+				transaction_hash: Some(status.transaction_hash),
+				transaction_index: Some(status.transaction_index.into()),
+				log_index: Some(U256::from(pre_receipts_log_index + i as u32)),
+				transaction_log_index: Some(U256::from(i)),
+				removed: false,
+			})
+			.collect(),
+		status_code: Some(U64::from(status_code)),
+		logs_bloom,
+		state_root: None,
+		effective_gas_price,
+		revert_reason: None,
+	})
+}
+
+/// Receipts of every transaction of `block`, with the revert reasons of the reverted calls
+/// when the runtime kept their revert data.
+pub(crate) fn receipts_build<B, C>(
+	client: &C,
+	id: &BlockId<B>,
+	block: &EthereumBlock,
+	receipts: &[ethereum::ReceiptV3],
+	statuses: &[TransactionStatus],
+	block_hash: Option<H256>,
+	base_fee: Option<U256>,
+	is_eip1559: bool,
+) -> Result<Vec<Receipt>>
 where
-	B: BlockT<Hash = H256> + Send + Sync + 'static,
-	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
-	C: HeaderBackend<B> + Send + Sync + 'static,
-	C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
-	BE: Backend<B> + 'static,
-	BE::State: StateBackend<BlakeTwo256>,
-	A: ChainApi<Block = B> + 'static,
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: EthereumRuntimeRPCApi<B>,
+{
+	let mut built = (0..statuses.len())
+		.map(|index| {
+			receipt_build(
+				block, receipts, statuses, index, block_hash, base_fee, is_eip1559,
+			)
+		})
+		.collect::<Result<Vec<_>>>()?;
+	if built
+		.iter()
+		.any(|receipt| receipt.status_code == Some(U64::zero()))
+	{
+		let mut reasons = revert_reasons(client, id);
+		for (index, receipt) in built.iter_mut().enumerate() {
+			if receipt.status_code == Some(U64::zero()) {
+				receipt.revert_reason = reasons.remove(&index);
+			}
+		}
+	}
+	Ok(built)
+}
+
+/// Readable revert reasons of the reverted calls of block `id`, by transaction index, for the
+/// ones whose revert data the runtime kept.
+fn revert_reasons<B, C>(client: &C, id: &BlockId<B>) -> BTreeMap<usize, String>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: EthereumRuntimeRPCApi<B>,
 {
-	// In case of Pending, we need an overlayed state to query over.
 	let api = client.runtime_api();
-	let best = BlockId::Hash(client.info().best_hash);
-	// Get all transactions in the ready queue.
-	let xts: Vec<<B as BlockT>::Extrinsic> = graph
-		.validated_pool()
-		.ready()
-		.map(|in_pool_tx| in_pool_tx.data().clone())
-		.collect::<Vec<<B as BlockT>::Extrinsic>>();
-	// Manually initialize the overlay.
-	let header = client.header(best).unwrap().unwrap();
-	api.initialize_block(&best, &header)
-		.map_err(|e| internal_err(format!("Runtime api access error: {:?}", e)))?;
-	// Apply the ready queue to the best block's state.
-	for xt in xts {
-		let _ = api.apply_extrinsic(&best, xt);
-	}
-	Ok(api)
+	match api.api_version::<dyn EthereumRuntimeRPCApi<B>>(id) {
+		Ok(Some(api_version)) if api_version >= 5 => (),
+		_ => return BTreeMap::new(),
+	}
+	api.current_revert_data(id)
+		.unwrap_or_default()
+		.into_iter()
+		.map(|(index, data)| (index as usize, self::execute::decode_revert_reason(&data)))
+		.collect()
 }
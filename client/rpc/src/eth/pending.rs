@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use ethereum::BlockV2 as EthereumBlock;
+use ethereum_types::{H160, H256, U256};
+use jsonrpc_core::Result;
+
+use sc_transaction_pool::{ChainApi, Pool};
+use sc_transaction_pool_api::InPoolTransaction;
+use sp_api::{ApiExt, ApiRef, Core, ProvideRuntimeApi, TransactionOutcome};
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::HeaderBackend;
+use sp_inherents::InherentData;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, One},
+	Digest,
+};
+
+use fp_rpc::{EthereumRuntimeRPCApi, TransactionStatus};
+
+use crate::internal_err;
+
+/// Provides the inherent data and pre-runtime digest the node's block author would use on top
+/// of `parent`, so the pending block passes the same runtime checks as an authored one.
+pub trait PendingBlockInherents<B: BlockT>: Send + Sync {
+	fn inherents(&self, parent: &B::Header) -> std::result::Result<(InherentData, Digest), String>;
+}
+
+/// Ethereum data of the block that would be authored on top of the best block with the
+/// transactions currently in the ready queue.
+#[derive(Clone)]
+pub struct PendingBlock {
+	/// Substrate hash of the best block the pending block was built on.
+	pub parent_hash: H256,
+	pub block: EthereumBlock,
+	pub receipts: Vec<ethereum::ReceiptV3>,
+	pub statuses: Vec<TransactionStatus>,
+}
+
+/// Best block and ready queue a pending block is built from. Anything built from a different
+/// key is stale.
+#[derive(Clone, PartialEq, Eq)]
+struct PendingKey {
+	parent_hash: H256,
+	ready: Vec<H256>,
+}
+
+/// Pending block and pending state queries answered on top of the same best block and ready
+/// queue.
+struct PendingCache {
+	key: PendingKey,
+	block: Option<PendingBlock>,
+	/// Balance and nonce of the queried accounts.
+	accounts: HashMap<H160, (U256, U256)>,
+	codes: HashMap<H160, Vec<u8>>,
+	storages: HashMap<(H160, U256), H256>,
+}
+
+impl PendingCache {
+	fn new(key: PendingKey) -> Self {
+		Self {
+			key,
+			block: None,
+			accounts: HashMap::new(),
+			codes: HashMap::new(),
+			storages: HashMap::new(),
+		}
+	}
+}
+
+/// Builds the pending block by running the block builder runtime api over the ready queue.
+/// The pending block and the pending state queries are cached until the best block or the
+/// ready queue changes.
+pub struct EthPendingBlockCache<B: BlockT> {
+	inherents: Box<dyn PendingBlockInherents<B>>,
+	cache: Mutex<Option<PendingCache>>,
+}
+
+impl<B: BlockT<Hash = H256>> EthPendingBlockCache<B> {
+	pub fn new(inherents: Box<dyn PendingBlockInherents<B>>) -> Self {
+		Self {
+			inherents,
+			cache: Mutex::new(None),
+		}
+	}
+
+	/// The best block and the ready queue, with the extrinsics of the ready queue.
+	fn pending_key<C, A>(client: &C, graph: &Pool<A>) -> (PendingKey, Vec<B::Extrinsic>)
+	where
+		C: HeaderBackend<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		let (ready, xts) = graph
+			.validated_pool()
+			.ready()
+			.map(|in_pool_tx| (*in_pool_tx.hash(), in_pool_tx.data().clone()))
+			.unzip();
+		let key = PendingKey {
+			parent_hash: client.info().best_hash,
+			ready,
+		};
+
+		(key, xts)
+	}
+
+	/// Runtime api with an overlayed state in which the pending block is initialized and both
+	/// its inherents and the ready queue are applied.
+	pub(crate) fn runtime_api<'a, C, A>(
+		&self,
+		client: &'a C,
+		graph: &Pool<A>,
+	) -> Result<(BlockId<B>, ApiRef<'a, C::Api>)>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		let (key, xts) = Self::pending_key(client, graph);
+		self.build_api(client, key.parent_hash, xts)
+	}
+
+	fn build_api<'a, C>(
+		&self,
+		client: &'a C,
+		best_hash: H256,
+		xts: Vec<B::Extrinsic>,
+	) -> Result<(BlockId<B>, ApiRef<'a, C::Api>)>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+	{
+		let api = client.runtime_api();
+		let best = BlockId::Hash(best_hash);
+		let best_header = client
+			.header(best)
+			.map_err(|err| internal_err(format!("fetch best header failed: {:?}", err)))?
+			.ok_or_else(|| internal_err("best header not found"))?;
+
+		let (inherent_data, digest) = self
+			.inherents
+			.inherents(&best_header)
+			.map_err(|err| internal_err(format!("pending inherent data unavailable: {}", err)))?;
+		let pending_header = <B::Header as HeaderT>::new(
+			*best_header.number() + One::one(),
+			Default::default(),
+			Default::default(),
+			best_hash,
+			digest,
+		);
+
+		// Manually initialize the overlay.
+		api.initialize_block(&best, &pending_header)
+			.map_err(|err| internal_err(format!("Runtime api access error: {:?}", err)))?;
+		// Apply the inherents first, as the block author would.
+		let inherents = api
+			.inherent_extrinsics(&best, inherent_data)
+			.map_err(|err| internal_err(format!("Runtime api access error: {:?}", err)))?;
+		for xt in inherents {
+			api.apply_extrinsic(&best, xt)
+				.map_err(|err| internal_err(format!("Runtime api access error: {:?}", err)))?
+				.map_err(|err| internal_err(format!("pending inherent invalid: {:?}", err)))?;
+		}
+		// Apply the ready queue to the pending block's state. As the block builder does,
+		// extrinsics that turn out invalid are rolled back and left out.
+		for xt in xts {
+			api.execute_in_transaction(|api| match api.apply_extrinsic(&best, xt) {
+				Ok(Ok(_)) => TransactionOutcome::Commit(Ok(())),
+				Ok(Err(_)) => TransactionOutcome::Rollback(Ok(())),
+				Err(err) => TransactionOutcome::Rollback(Err(internal_err(format!(
+					"Runtime api access error: {:?}",
+					err
+				)))),
+			})?;
+		}
+
+		Ok((best, api))
+	}
+
+	/// Drop the cached pending block, for example after blocks were reverted.
+	pub fn clear(&self) {
+		if let Ok(mut cache) = self.cache.lock() {
+			*cache = None;
+		}
+	}
+
+	/// The pending block on top of the current best block and ready queue, built on first
+	/// request.
+	pub(crate) fn block<C, A>(&self, client: &C, graph: &Pool<A>) -> Result<Option<PendingBlock>>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		let (key, xts) = Self::pending_key(client, graph);
+		if let Some(pending) = self.cached(&key, |cache| cache.block.clone()) {
+			return Ok(Some(pending));
+		}
+
+		let (id, api) = self.build_api(client, key.parent_hash, xts)?;
+		// Only runtimes exposing `ReceiptV3` can be served.
+		match api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&id) {
+			Ok(Some(api_version)) if api_version >= 4 => (),
+			_ => return Ok(None),
+		}
+		// Run the `on_finalize` hooks, pallet-ethereum stores the pending block in them.
+		api.finalize_block(&id)
+			.map_err(|err| internal_err(format!("Runtime api access error: {:?}", err)))?;
+		let pending = match api
+			.current_all(&id)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+		{
+			(Some(block), Some(receipts), Some(statuses)) => PendingBlock {
+				parent_hash: key.parent_hash,
+				block,
+				receipts,
+				statuses,
+			},
+			_ => return Ok(None),
+		};
+
+		self.store(key, |cache| cache.block = Some(pending.clone()));
+		Ok(Some(pending))
+	}
+
+	/// Balance and nonce of `address` after the pending block.
+	pub(crate) fn account_basic<C, A>(
+		&self,
+		client: &C,
+		graph: &Pool<A>,
+		address: H160,
+	) -> Result<(U256, U256)>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		self.query(
+			client,
+			graph,
+			|cache| cache.accounts.get(&address).cloned(),
+			|id, api| {
+				api.account_basic(id, address)
+					.map(|account| (account.balance, account.nonce))
+					.map_err(|err| {
+						internal_err(format!("fetch runtime account basic failed: {:?}", err))
+					})
+			},
+			|cache, account| {
+				cache.accounts.insert(address, account);
+			},
+		)
+	}
+
+	/// Code of `address` after the pending block.
+	pub(crate) fn code_at<C, A>(
+		&self,
+		client: &C,
+		graph: &Pool<A>,
+		address: H160,
+	) -> Result<Vec<u8>>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		self.query(
+			client,
+			graph,
+			|cache| cache.codes.get(&address).cloned(),
+			|id, api| {
+				api.account_code_at(id, address).map_err(|err| {
+					internal_err(format!("fetch runtime account code failed: {:?}", err))
+				})
+			},
+			|cache, code| {
+				cache.codes.insert(address, code);
+			},
+		)
+	}
+
+	/// Storage slot `index` of `address` after the pending block.
+	pub(crate) fn storage_at<C, A>(
+		&self,
+		client: &C,
+		graph: &Pool<A>,
+		address: H160,
+		index: U256,
+	) -> Result<H256>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+	{
+		self.query(
+			client,
+			graph,
+			|cache| cache.storages.get(&(address, index)).cloned(),
+			|id, api| {
+				api.storage_at(id, address, index)
+					.map_err(|err| internal_err(format!("fetch runtime storage failed: {:?}", err)))
+			},
+			|cache, value| {
+				cache.storages.insert((address, index), value);
+			},
+		)
+	}
+
+	/// Answer a pending state query from the cache, or from a freshly built pending state.
+	fn query<C, A, T>(
+		&self,
+		client: &C,
+		graph: &Pool<A>,
+		get: impl FnOnce(&PendingCache) -> Option<T>,
+		fetch: impl FnOnce(&BlockId<B>, &C::Api) -> Result<T>,
+		put: impl FnOnce(&mut PendingCache, T),
+	) -> Result<T>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+		C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
+		A: ChainApi<Block = B> + 'static,
+		T: Clone,
+	{
+		let (key, xts) = Self::pending_key(client, graph);
+		if let Some(value) = self.cached(&key, get) {
+			return Ok(value);
+		}
+
+		let (id, api) = self.build_api(client, key.parent_hash, xts)?;
+		let value = fetch(&id, &*api)?;
+		self.store(key, |cache| put(cache, value.clone()));
+		Ok(value)
+	}
+
+	/// Look up the cache built from `key`.
+	fn cached<T>(
+		&self,
+		key: &PendingKey,
+		get: impl FnOnce(&PendingCache) -> Option<T>,
+	) -> Option<T> {
+		let cache = self.cache.lock().ok()?;
+		cache
+			.as_ref()
+			.filter(|cache| cache.key == *key)
+			.and_then(get)
+	}
+
+	/// Update the cache built from `key`, replacing a cache built from any other key.
+	fn store(&self, key: PendingKey, put: impl FnOnce(&mut PendingCache)) {
+		if let Ok(mut cache) = self.cache.lock() {
+			if cache.as_ref().map_or(true, |cache| cache.key != key) {
+				*cache = Some(PendingCache::new(key));
+			}
+			if let Some(cache) = cache.as_mut() {
+				put(cache);
+			}
+		}
+	}
+}
@@ -27,18 +27,12 @@ use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{
-	generic::BlockId,
-	traits::{BlakeTwo256, Block as BlockT},
-};
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT};
 
 use fc_rpc_core::types::*;
 use fp_rpc::EthereumRuntimeRPCApi;
 
-use crate::{
-	eth::{pending_runtime_api, EthApi},
-	frontier_backend_client, internal_err,
-};
+use crate::{eth::EthApi, frontier_backend_client, internal_err};
 
 impl<B, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H, A>
 where
@@ -54,12 +48,12 @@ where
 	pub fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
 		let number = number.unwrap_or(BlockNumber::Latest);
 		if number == BlockNumber::Pending {
-			let api = pending_runtime_api(self.client.as_ref(), self.graph.as_ref())?;
-			return Ok(api
-				.account_basic(&BlockId::Hash(self.client.info().best_hash), address)
-				.map_err(|err| internal_err(format!("fetch runtime chain id failed: {:?}", err)))?
-				.balance
-				.into());
+			let (balance, _) = self.pending_block.account_basic(
+				self.client.as_ref(),
+				self.graph.as_ref(),
+				address,
+			)?;
+			return Ok(balance);
 		} else if let Ok(Some(id)) = frontier_backend_client::native_block_id::<B, C>(
 			self.client.as_ref(),
 			self.backend.as_ref(),
@@ -85,10 +79,12 @@ where
 	) -> Result<H256> {
 		let number = number.unwrap_or(BlockNumber::Latest);
 		if number == BlockNumber::Pending {
-			let api = pending_runtime_api(self.client.as_ref(), self.graph.as_ref())?;
-			return Ok(api
-				.storage_at(&BlockId::Hash(self.client.info().best_hash), address, index)
-				.unwrap_or_default());
+			return self.pending_block.storage_at(
+				self.client.as_ref(),
+				self.graph.as_ref(),
+				address,
+				index,
+			);
 		} else if let Ok(Some(id)) = frontier_backend_client::native_block_id::<B, C>(
 			self.client.as_ref(),
 			self.backend.as_ref(),
@@ -112,16 +108,12 @@ where
 
 	pub fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
 		if let Some(BlockNumber::Pending) = number {
-			let block = BlockId::Hash(self.client.info().best_hash);
-
-			let nonce = self
-				.client
-				.runtime_api()
-				.account_basic(&block, address)
-				.map_err(|err| {
-					internal_err(format!("fetch runtime account basic failed: {:?}", err))
-				})?
-				.nonce;
+			// Nonce after the pending block, plus any ready transactions that did not fit in it.
+			let (_, nonce) = self.pending_block.account_basic(
+				self.client.as_ref(),
+				self.graph.as_ref(),
+				address,
+			)?;
 
 			let mut current_nonce = nonce;
 			let mut current_tag = (address, nonce).encode();
@@ -160,10 +152,9 @@ where
 	pub fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes> {
 		let number = number.unwrap_or(BlockNumber::Latest);
 		if number == BlockNumber::Pending {
-			let api = pending_runtime_api(self.client.as_ref(), self.graph.as_ref())?;
-			return Ok(api
-				.account_code_at(&BlockId::Hash(self.client.info().best_hash), address)
-				.unwrap_or(vec![])
+			return Ok(self
+				.pending_block
+				.code_at(self.client.as_ref(), self.graph.as_ref(), address)?
 				.into());
 		} else if let Ok(Some(id)) = frontier_backend_client::native_block_id::<B, C>(
 			self.client.as_ref(),
@@ -134,6 +134,12 @@ where
 			}
 		};
 
+		if let Some(impersonator) = &self.impersonator {
+			if impersonator.is_impersonated(&from) {
+				return impersonator.submit(message, &from);
+			}
+		}
+
 		let mut transaction = None;
 
 		for signer in &self.signers {
@@ -27,6 +27,7 @@ use sc_network::ExHashT;
 use sc_transaction_pool::ChainApi;
 use sc_transaction_pool_api::InPoolTransaction;
 use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_blockchain::HeaderBackend;
 use sp_core::hashing::keccak_256;
 use sp_runtime::{
@@ -38,7 +39,7 @@ use fc_rpc_core::types::*;
 use fp_rpc::EthereumRuntimeRPCApi;
 
 use crate::{
-	eth::{transaction_build, EthApi},
+	eth::{receipt_build, receipts_build, revert_reasons, transaction_build, EthApi},
 	frontier_backend_client, internal_err,
 };
 
@@ -46,7 +47,7 @@ impl<B, C, P, CT, BE, H: ExHashT, A: ChainApi> EthApi<B, C, P, CT, BE, H, A>
 where
 	B: BlockT<Hash = H256> + Send + Sync + 'static,
 	C: ProvideRuntimeApi<B> + StorageProvider<B, BE> + HeaderBackend<B> + Send + Sync + 'static,
-	C::Api: EthereumRuntimeRPCApi<B>,
+	C::Api: BlockBuilderApi<B> + EthereumRuntimeRPCApi<B>,
 	BE: Backend<B> + 'static,
 	BE::State: StateBackend<BlakeTwo256>,
 	A: ChainApi<Block = B> + 'static,
@@ -129,7 +130,7 @@ where
 						}
 					}
 					// Unknown transaction.
-					return Ok(None);
+					return frontier_backend_client::missing_or_pruned::<B, _>(backend.as_ref());
 				}
 			};
 
@@ -186,7 +187,7 @@ where
 				.map_err(|err| internal_err(format!("{:?}", err)))?
 			{
 				Some(hash) => hash,
-				_ => return Ok(None),
+				_ => return frontier_backend_client::missing_or_pruned::<B, _>(backend.as_ref()),
 			};
 			let substrate_hash = client
 				.expect_block_hash_from_id(&id)
@@ -295,6 +296,8 @@ where
 		let overrides = Arc::clone(&self.overrides);
 		let block_data_cache = Arc::clone(&self.block_data_cache);
 		let backend = Arc::clone(&self.backend);
+		let graph = Arc::clone(&self.graph);
+		let pending_block = Arc::clone(&self.pending_block);
 
 		Box::pin(async move {
 			let (hash, index) = match frontier_backend_client::load_transactions::<B, C>(
@@ -306,7 +309,44 @@ where
 			.map_err(|err| internal_err(format!("{:?}", err)))?
 			{
 				Some((hash, index)) => (hash, index as usize),
-				None => return Ok(None),
+				None => {
+					// Not mapped yet, look for it in the pending block.
+					let index = pending_block
+						.block(client.as_ref(), graph.as_ref())?
+						.and_then(|pending| {
+							pending
+								.statuses
+								.iter()
+								.position(|status| status.transaction_hash == hash)
+								.map(|index| (pending, index))
+						});
+					let (pending, index) = match index {
+						Some(found) => found,
+						None => {
+							return frontier_backend_client::missing_or_pruned::<B, _>(
+								backend.as_ref(),
+							)
+						}
+					};
+					let id = BlockId::Hash(pending.parent_hash);
+					let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(
+						client.as_ref(),
+						id,
+					);
+					let handler = overrides
+						.schemas
+						.get(&schema)
+						.unwrap_or(&overrides.fallback);
+					return Ok(Some(receipt_build(
+						&pending.block,
+						&pending.receipts,
+						&pending.statuses,
+						index,
+						None,
+						handler.base_fee(&id),
+						handler.is_eip1559(&id),
+					)?));
+				}
 			};
 
 			let id = match frontier_backend_client::load_hash::<B>(backend.as_ref(), hash)
@@ -336,133 +376,103 @@ where
 			match (block, statuses, receipts) {
 				(Some(block), Some(statuses), Some(receipts)) => {
 					let block_hash = H256::from(keccak_256(&rlp::encode(&block.header)));
-					let receipt = receipts[index].clone();
-
-					let (logs, logs_bloom, status_code, cumulative_gas_used, gas_used) =
-						if !is_eip1559 {
-							// Pre-london frontier update stored receipts require cumulative gas calculation.
-							match receipt {
-								ethereum::ReceiptV3::Legacy(d) => {
-									let index = core::cmp::min(receipts.len(), index + 1);
-									let cumulative_gas: u32 = receipts[..index]
-										.iter()
-										.map(|r| match r {
-											ethereum::ReceiptV3::Legacy(d) => {
-												Ok(d.used_gas.as_u32())
-											}
-											_ => Err(internal_err(format!(
-												"Unknown receipt for request {}",
-												hash
-											))),
-										})
-										.sum::<Result<u32>>()?;
-									(
-										d.logs,
-										d.logs_bloom,
-										d.status_code,
-										U256::from(cumulative_gas),
-										d.used_gas,
-									)
-								}
-								_ => {
-									return Err(internal_err(format!(
-										"Unknown receipt for request {}",
-										hash
-									)))
-								}
-							}
-						} else {
-							match receipt {
-								ethereum::ReceiptV3::Legacy(d)
-								| ethereum::ReceiptV3::EIP2930(d)
-								| ethereum::ReceiptV3::EIP1559(d) => {
-									let cumulative_gas = d.used_gas;
-									let gas_used = if index > 0 {
-										let previous_receipt = receipts[index - 1].clone();
-										let previous_gas_used = match previous_receipt {
-											ethereum::ReceiptV3::Legacy(d)
-											| ethereum::ReceiptV3::EIP2930(d)
-											| ethereum::ReceiptV3::EIP1559(d) => d.used_gas,
-										};
-										cumulative_gas.saturating_sub(previous_gas_used)
-									} else {
-										cumulative_gas
-									};
-									(
-										d.logs,
-										d.logs_bloom,
-										d.status_code,
-										cumulative_gas,
-										gas_used,
-									)
-								}
-							}
-						};
-
-					let status = statuses[index].clone();
-					let mut cumulative_receipts = receipts.clone();
-					cumulative_receipts.truncate((status.transaction_index + 1) as usize);
-
-					let transaction = block.transactions[index].clone();
-					let effective_gas_price = match transaction {
-						EthereumTransaction::Legacy(t) => t.gas_price,
-						EthereumTransaction::EIP2930(t) => t.gas_price,
-						EthereumTransaction::EIP1559(t) => handler
-							.base_fee(&id)
-							.unwrap_or_default()
-							.checked_add(t.max_priority_fee_per_gas)
-							.unwrap_or(U256::max_value())
-							.min(t.max_fee_per_gas),
-					};
+					let mut receipt = receipt_build(
+						&block,
+						&receipts,
+						&statuses,
+						index,
+						Some(block_hash),
+						handler.base_fee(&id),
+						is_eip1559,
+					)?;
+					if receipt.status_code == Some(U64::zero()) {
+						receipt.revert_reason = revert_reasons(client.as_ref(), &id).remove(&index);
+					}
+					Ok(Some(receipt))
+				}
+				_ => Ok(None),
+			}
+		})
+	}
+
+	pub fn block_receipts(&self, number: BlockNumber) -> BoxFuture<Result<Option<Vec<Receipt>>>> {
+		let client = Arc::clone(&self.client);
+		let overrides = Arc::clone(&self.overrides);
+		let block_data_cache = Arc::clone(&self.block_data_cache);
+		let backend = Arc::clone(&self.backend);
+		let graph = Arc::clone(&self.graph);
+		let pending_block = Arc::clone(&self.pending_block);
+
+		Box::pin(async move {
+			if let BlockNumber::Pending = number {
+				let pending = match pending_block.block(client.as_ref(), graph.as_ref())? {
+					Some(pending) => pending,
+					None => return Ok(None),
+				};
+				let id = BlockId::Hash(pending.parent_hash);
+				let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(
+					client.as_ref(),
+					id,
+				);
+				let handler = overrides
+					.schemas
+					.get(&schema)
+					.unwrap_or(&overrides.fallback);
+				// The pending block is not sealed yet, and its revert data is not in any state.
+				return (0..pending.statuses.len())
+					.map(|index| {
+						receipt_build(
+							&pending.block,
+							&pending.receipts,
+							&pending.statuses,
+							index,
+							None,
+							handler.base_fee(&id),
+							handler.is_eip1559(&id),
+						)
+					})
+					.collect::<Result<Vec<_>>>()
+					.map(Some);
+			}
+
+			let id = match frontier_backend_client::native_block_id::<B, C>(
+				client.as_ref(),
+				backend.as_ref(),
+				Some(number),
+			)? {
+				Some(id) => id,
+				None => return Ok(None),
+			};
+			let substrate_hash = client
+				.expect_block_hash_from_id(&id)
+				.map_err(|_| internal_err(format!("Expect block number from id: {}", id)))?;
+
+			let schema =
+				frontier_backend_client::onchain_storage_schema::<B, C, BE>(client.as_ref(), id);
+			let handler = overrides
+				.schemas
+				.get(&schema)
+				.unwrap_or(&overrides.fallback);
 
-					return Ok(Some(Receipt {
-						transaction_hash: Some(status.transaction_hash),
-						transaction_index: Some(status.transaction_index.into()),
-						block_hash: Some(block_hash),
-						from: Some(status.from),
-						to: status.to,
-						block_number: Some(block.header.number),
-						cumulative_gas_used,
-						gas_used: Some(gas_used),
-						contract_address: status.contract_address,
-						logs: {
-							let mut pre_receipts_log_index = None;
-							if cumulative_receipts.len() > 0 {
-								cumulative_receipts.truncate(cumulative_receipts.len() - 1);
-								pre_receipts_log_index = Some(
-									cumulative_receipts
-										.iter()
-										.map(|r| match r {
-											ethereum::ReceiptV3::Legacy(d)
-											| ethereum::ReceiptV3::EIP2930(d)
-											| ethereum::ReceiptV3::EIP1559(d) => d.logs.len() as u32,
-										})
-										.sum::<u32>(),
-								);
-							}
-							logs.iter()
-								.enumerate()
-								.map(|(i, log)| Log {
-									address: log.address,
-									topics: log.topics.clone(),
-									data: Bytes(log.data.clone()),
-									block_hash: Some(block_hash),
-									block_number: Some(block.header.number),
-									transaction_hash: Some(status.transaction_hash),
-									transaction_index: Some(status.transaction_index.into()),
-									log_index: Some(U256::from(
-										(pre_receipts_log_index.unwrap_or(0)) + i as u32,
-									)),
-									transaction_log_index: Some(U256::from(i)),
-									removed: false,
-								})
-								.collect()
-						},
-						status_code: Some(U64::from(status_code)),
-						logs_bloom,
-						state_root: None,
-						effective_gas_price,
-					}));
+			let block = block_data_cache.current_block(schema, substrate_hash).await;
+			let statuses = block_data_cache
+				.current_transaction_statuses(schema, substrate_hash)
+				.await;
+			let receipts = handler.current_receipts(&id);
+
+			match (block, statuses, receipts) {
+				(Some(block), Some(statuses), Some(receipts)) => {
+					let block_hash = H256::from(keccak_256(&rlp::encode(&block.header)));
+					Ok(Some(receipts_build(
+						client.as_ref(),
+						&id,
+						&block,
+						&receipts,
+						&statuses,
+						Some(block_hash),
+						handler.base_fee(&id),
+						handler.is_eip1559(&id),
+					)?))
 				}
 				_ => Ok(None),
 			}
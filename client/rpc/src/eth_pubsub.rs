@@ -34,7 +34,6 @@ use sc_client_api::{
 	backend::{Backend, StateBackend, StorageProvider},
 	client::BlockchainEvents,
 };
-use sc_network::{ExHashT, NetworkService};
 use sc_rpc::Metadata;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::{BlockId, ProvideRuntimeApi};
@@ -44,8 +43,11 @@ use sp_runtime::traits::{BlakeTwo256, Block as BlockT, UniqueSaturatedInto};
 
 use fc_rpc_core::{
 	types::{
-		pubsub::{Kind, Params, PubSubSyncStatus, Result as PubSubResult},
-		Bytes, FilteredParams, Header, Log, Rich,
+		pubsub::{
+			HeaderWithTransactions, Kind, Params, PendingTransactionsParams, PubSubSyncStatus,
+			Result as PubSubResult,
+		},
+		Bytes, FilteredParams, Header, Log, Receipt, Rich,
 	},
 	EthPubSubApi as EthPubSubApiT,
 };
@@ -53,7 +55,12 @@ use fp_rpc::EthereumRuntimeRPCApi;
 
 use sp_api::ApiExt;
 
-use crate::{frontier_backend_client, overrides::OverrideHandle};
+use crate::{
+	eth::{receipts_build, transaction_build, SyncProgress},
+	frontier_backend_client,
+	overrides::OverrideHandle,
+	RpcMetrics,
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct HexEncodedIdProvider {
@@ -80,29 +87,32 @@ impl IdProvider for HexEncodedIdProvider {
 	}
 }
 
-pub struct EthPubSubApi<B: BlockT, P, C, BE, H: ExHashT> {
+pub struct EthPubSubApi<B: BlockT, P, C, BE> {
 	pool: Arc<P>,
 	client: Arc<C>,
-	network: Arc<NetworkService<B, H>>,
 	subscriptions: SubscriptionManager<HexEncodedIdProvider>,
 	overrides: Arc<OverrideHandle<B>>,
+	sync_progress: SyncProgress,
+	metrics: Option<RpcMetrics>,
 	_marker: PhantomData<BE>,
 }
 
-impl<B: BlockT, P, C, BE, H: ExHashT> EthPubSubApi<B, P, C, BE, H> {
+impl<B: BlockT, P, C, BE> EthPubSubApi<B, P, C, BE> {
 	pub fn new(
 		pool: Arc<P>,
 		client: Arc<C>,
-		network: Arc<NetworkService<B, H>>,
 		subscriptions: SubscriptionManager<HexEncodedIdProvider>,
 		overrides: Arc<OverrideHandle<B>>,
+		sync_progress: SyncProgress,
+		metrics: Option<RpcMetrics>,
 	) -> Self {
 		Self {
 			pool: pool.clone(),
 			client: client.clone(),
-			network,
 			subscriptions,
 			overrides,
+			sync_progress,
+			metrics,
 			_marker: PhantomData,
 		}
 	}
@@ -113,8 +123,13 @@ impl SubscriptionResult {
 	pub fn new() -> Self {
 		SubscriptionResult {}
 	}
-	pub fn new_heads(&self, block: EthereumBlock) -> PubSubResult {
-		PubSubResult::Header(Box::new(Rich {
+	pub fn new_heads(
+		&self,
+		block: EthereumBlock,
+		include_transactions: bool,
+		receipts: Option<Vec<Receipt>>,
+	) -> PubSubResult {
+		let header = Rich {
 			inner: Header {
 				hash: Some(H256::from(keccak_256(&rlp::encode(&block.header)))),
 				parent_hash: block.header.parent_hash,
@@ -138,13 +153,45 @@ impl SubscriptionResult {
 				size: Some(U256::from(rlp::encode(&block).len() as u32)),
 			},
 			extra_info: BTreeMap::new(),
-		}))
+		};
+		if include_transactions || receipts.is_some() {
+			PubSubResult::HeaderWithTransactions(Box::new(HeaderWithTransactions {
+				header,
+				transactions: include_transactions.then(|| {
+					block
+						.transactions
+						.iter()
+						.map(|transaction| transaction.hash())
+						.collect()
+				}),
+				receipts,
+			}))
+		} else {
+			PubSubResult::Header(Box::new(header))
+		}
+	}
+	pub fn pending_transaction(
+		&self,
+		transaction: EthereumTransaction,
+		params: &PendingTransactionsParams,
+	) -> Option<PubSubResult> {
+		let hash = transaction.hash();
+		let transaction = transaction_build(transaction, None, None, true, None);
+		if !params.matches(&transaction.from, transaction.to.as_ref()) {
+			return None;
+		}
+		if params.hashes_only {
+			Some(PubSubResult::TransactionHash(hash))
+		} else {
+			Some(PubSubResult::Transaction(Box::new(transaction)))
+		}
 	}
 	pub fn logs(
 		&self,
 		block: EthereumBlock,
 		receipts: Vec<ethereum::ReceiptV3>,
 		params: &FilteredParams,
+		removed: bool,
 	) -> Vec<Log> {
 		let block_hash = Some(H256::from(keccak_256(&rlp::encode(&block.header))));
 		let mut logs: Vec<Log> = vec![];
@@ -173,7 +220,7 @@ impl SubscriptionResult {
 						transaction_index: Some(U256::from(receipt_index)),
 						log_index: Some(U256::from(log_index)),
 						transaction_log_index: Some(U256::from(transaction_log_index)),
-						removed: false,
+						removed,
 					});
 				}
 				log_index += 1;
@@ -216,7 +263,53 @@ impl SubscriptionResult {
 	}
 }
 
-impl<B: BlockT, P, C, BE, H: ExHashT> EthPubSubApiT for EthPubSubApi<B, P, C, BE, H>
+fn block_with_receipts<B, C, BE>(
+	client: &C,
+	overrides: &OverrideHandle<B>,
+	hash: B::Hash,
+) -> Option<(EthereumBlock, Vec<ethereum::ReceiptV3>)>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: StorageProvider<B, BE> + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let id = BlockId::Hash(hash);
+	let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(client, id);
+	let handler = overrides
+		.schemas
+		.get(&schema)
+		.unwrap_or(&overrides.fallback);
+
+	match (handler.current_block(&id), handler.current_receipts(&id)) {
+		(Some(block), Some(receipts)) => Some((block, receipts)),
+		_ => None,
+	}
+}
+
+/// Blocks whose logs are notified when `hash` becomes the new best block, with whether their
+/// logs are removed. On a reorg, the `retracted` blocks come first, then the `enacted` ones,
+/// which stop at the parent of `hash`.
+fn new_best_route<H>(hash: H, retracted: Vec<H>, enacted: Vec<H>) -> Vec<(H, bool)> {
+	retracted
+		.into_iter()
+		.map(|hash| (hash, true))
+		.chain(
+			enacted
+				.into_iter()
+				.chain(iter::once(hash))
+				.map(|hash| (hash, false)),
+		)
+		.collect()
+}
+
+/// Blocks whose logs are notified when `hash` is finalized: the ones finalized implicitly along
+/// `tree_route` first, from the oldest, then `hash`.
+fn finalized_route<H: Clone>(hash: H, tree_route: &[H]) -> Vec<H> {
+	tree_route.iter().cloned().chain(iter::once(hash)).collect()
+}
+
+impl<B: BlockT, P, C, BE> EthPubSubApiT for EthPubSubApi<B, P, C, BE>
 where
 	B: BlockT<Hash = H256> + Send + Sync + 'static,
 	P: TransactionPool<Block = B> + Send + Sync + 'static,
@@ -234,53 +327,114 @@ where
 		kind: Kind,
 		params: Option<Params>,
 	) {
+		let (include_transactions, include_receipts) = match params {
+			Some(Params::NewHeads(ref new_heads)) => {
+				(new_heads.include_transactions, new_heads.include_receipts)
+			}
+			_ => (false, false),
+		};
+		let pending_params = match params {
+			Some(Params::PendingTransactions(ref pending)) => pending.clone(),
+			Some(Params::FullTransactions(full)) => PendingTransactionsParams {
+				hashes_only: !full,
+				..Default::default()
+			},
+			_ => PendingTransactionsParams {
+				hashes_only: true,
+				..Default::default()
+			},
+		};
+		let finalized_only = matches!(params, Some(Params::FinalizedLogs(_)));
 		let filtered_params = match params {
-			Some(Params::Logs(filter)) => FilteredParams::new(Some(filter)),
+			Some(Params::Logs(filter)) | Some(Params::FinalizedLogs(filter)) => {
+				FilteredParams::new(Some(filter))
+			}
 			_ => FilteredParams::default(),
 		};
 
 		let client = self.client.clone();
 		let pool = self.pool.clone();
-		let network = self.network.clone();
 		let overrides = self.overrides.clone();
+		// Dropped along with the subscription future, however the subscription ends.
+		let active = self
+			.metrics
+			.as_ref()
+			.map(|metrics| metrics.subscription_started(&kind));
 		match kind {
+			Kind::Logs if finalized_only => {
+				self.subscriptions.add(subscriber, |sink| {
+					let stream = client
+						.finality_notification_stream()
+						.flat_map(move |notification| {
+							let logs: Vec<Log> =
+								finalized_route(notification.hash, &notification.tree_route)
+									.into_iter()
+									.filter_map(|hash| {
+										block_with_receipts::<B, C, BE>(
+											client.as_ref(),
+											overrides.as_ref(),
+											hash,
+										)
+									})
+									.flat_map(|(block, receipts)| {
+										SubscriptionResult::new().logs(
+											block,
+											receipts,
+											&filtered_params,
+											false,
+										)
+									})
+									.collect();
+							futures::stream::iter(logs)
+						})
+						.map(|x| {
+							return Ok::<Result<PubSubResult, jsonrpc_core::types::error::Error>, ()>(
+								Ok(PubSubResult::Log(Box::new(x))),
+							);
+						});
+					stream
+						.forward(
+							sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)),
+						)
+						.map(move |_| drop(active))
+				});
+			}
 			Kind::Logs => {
 				self.subscriptions.add(subscriber, |sink| {
 					let stream = client
 						.import_notification_stream()
-						.filter_map(move |notification| {
-							if notification.is_new_best {
-								let id = BlockId::Hash(notification.hash);
-
-								let schema = frontier_backend_client::onchain_storage_schema::<
-									B,
-									C,
-									BE,
-								>(client.as_ref(), id);
-								let handler = overrides
-									.schemas
-									.get(&schema)
-									.unwrap_or(&overrides.fallback);
-
-								let block = handler.current_block(&id);
-								let receipts = handler.current_receipts(&id);
-
-								match (receipts, block) {
-									(Some(receipts), Some(block)) => {
-										futures::future::ready(Some((block, receipts)))
-									}
-									_ => futures::future::ready(None),
-								}
-							} else {
-								futures::future::ready(None)
+						.flat_map(move |notification| {
+							if !notification.is_new_best {
+								return futures::stream::iter(vec![]);
 							}
-						})
-						.flat_map(move |(block, receipts)| {
-							futures::stream::iter(SubscriptionResult::new().logs(
-								block,
-								receipts,
-								&filtered_params,
-							))
+							let (retracted, enacted) = match notification.tree_route {
+								Some(ref tree_route) => (
+									tree_route.retracted().iter().map(|b| b.hash).collect(),
+									tree_route.enacted().iter().map(|b| b.hash).collect(),
+								),
+								None => (vec![], vec![]),
+							};
+							let logs: Vec<Log> =
+								new_best_route(notification.hash, retracted, enacted)
+									.into_iter()
+									.filter_map(|(hash, removed)| {
+										block_with_receipts::<B, C, BE>(
+											client.as_ref(),
+											overrides.as_ref(),
+											hash,
+										)
+										.map(|(block, receipts)| (block, receipts, removed))
+									})
+									.flat_map(|(block, receipts, removed)| {
+										SubscriptionResult::new().logs(
+											block,
+											receipts,
+											&filtered_params,
+											removed,
+										)
+									})
+									.collect();
+							futures::stream::iter(logs)
 						})
 						.map(|x| {
 							return Ok::<Result<PubSubResult, jsonrpc_core::types::error::Error>, ()>(
@@ -291,7 +445,7 @@ where
 						.forward(
 							sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)),
 						)
-						.map(|_| ())
+						.map(move |_| drop(active))
 				});
 			}
 			Kind::NewHeads => {
@@ -312,20 +466,50 @@ where
 									.get(&schema)
 									.unwrap_or(&overrides.fallback);
 
-								let block = handler.current_block(&id);
-								futures::future::ready(block)
+								let block = match handler.current_block(&id) {
+									Some(block) => block,
+									None => return futures::future::ready(None),
+								};
+								let receipts = if include_receipts {
+									match (
+										handler.current_receipts(&id),
+										handler.current_transaction_statuses(&id),
+									) {
+										(Some(receipts), Some(statuses)) => receipts_build(
+											client.as_ref(),
+											&id,
+											&block,
+											&receipts,
+											&statuses,
+											Some(H256::from(keccak_256(&rlp::encode(
+												&block.header,
+											)))),
+											handler.base_fee(&id),
+											handler.is_eip1559(&id),
+										)
+										.ok(),
+										_ => None,
+									}
+								} else {
+									None
+								};
+								futures::future::ready(Some((block, receipts)))
 							} else {
 								futures::future::ready(None)
 							}
 						})
-						.map(|block| {
-							return Ok::<_, ()>(Ok(SubscriptionResult::new().new_heads(block)));
+						.map(move |(block, receipts)| {
+							return Ok::<_, ()>(Ok(SubscriptionResult::new().new_heads(
+								block,
+								include_transactions,
+								receipts,
+							)));
 						});
 					stream
 						.forward(
 							sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)),
 						)
-						.map(|_| ())
+						.map(move |_| drop(active))
 				});
 			}
 			Kind::NewPendingTransactions => {
@@ -366,7 +550,10 @@ where
 								let res = match txs {
 									Some(txs) => {
 										if txs.len() == 1 {
-											Some(txs[0].clone())
+											SubscriptionResult::new().pending_transaction(
+												txs[0].clone(),
+												&pending_params,
+											)
 										} else {
 											None
 										}
@@ -378,42 +565,42 @@ where
 								futures::future::ready(None)
 							}
 						})
-						.map(|transaction| {
+						.map(|result| {
 							return Ok::<Result<PubSubResult, jsonrpc_core::types::error::Error>, ()>(
-								Ok(PubSubResult::TransactionHash(transaction.hash())),
+								Ok(result),
 							);
 						});
 					stream
 						.forward(
 							sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)),
 						)
-						.map(|_| ())
+						.map(move |_| drop(active))
 				});
 			}
 			Kind::Syncing => {
+				let sync_progress = self.sync_progress.clone();
 				self.subscriptions.add(subscriber, |sink| {
-					let mut previous_syncing = network.is_major_syncing();
-					let stream = client
-						.import_notification_stream()
-						.filter_map(move |notification| {
-							let syncing = network.is_major_syncing();
-							if notification.is_new_best && previous_syncing != syncing {
-								previous_syncing = syncing;
-								futures::future::ready(Some(syncing))
-							} else {
-								futures::future::ready(None)
-							}
+					// Notified on every change of the progress, including the start and the end
+					// of the sync.
+					let stream =
+						futures::stream::unfold(sync_progress, |mut progress| async move {
+							progress.changed().await.ok()?;
+							let status = progress.borrow().clone();
+							Some((status, progress))
 						})
-						.map(|syncing| {
+						.map(|status| {
 							return Ok::<Result<PubSubResult, jsonrpc_core::types::error::Error>, ()>(
-								Ok(PubSubResult::SyncState(PubSubSyncStatus { syncing })),
+								Ok(PubSubResult::SyncState(PubSubSyncStatus {
+									syncing: status.is_some(),
+									status,
+								})),
 							);
 						});
 					stream
 						.forward(
 							sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e)),
 						)
-						.map(|_| ())
+						.map(move |_| drop(active))
 				});
 			}
 		}
@@ -427,3 +614,39 @@ where
 		Ok(self.subscriptions.cancel(subscription_id))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn new_best_block_without_reorg() {
+		assert_eq!(new_best_route(3, vec![], vec![]), vec![(3, false)]);
+	}
+
+	#[test]
+	fn new_best_block_after_reorg() {
+		// 1 - 2a - 3a is replaced by 1 - 2b - 3b - 4b.
+		assert_eq!(
+			new_best_route("4b", vec!["3a", "2a"], vec!["2b", "3b"]),
+			vec![
+				("3a", true),
+				("2a", true),
+				("2b", false),
+				("3b", false),
+				("4b", false)
+			]
+		);
+		// A sibling of the previous best block.
+		assert_eq!(
+			new_best_route("2b", vec!["2a"], vec![]),
+			vec![("2a", true), ("2b", false)]
+		);
+	}
+
+	#[test]
+	fn finalized_blocks_from_the_oldest() {
+		assert_eq!(finalized_route(5, &[]), vec![5]);
+		assert_eq!(finalized_route(5, &[2, 3, 4]), vec![2, 3, 4, 5]);
+	}
+}
@@ -0,0 +1,605 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+	cell::Cell,
+	cmp::Ordering,
+	collections::HashMap,
+	fmt,
+	future::Future,
+	net::{IpAddr, SocketAddr},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use futures::{
+	channel::oneshot,
+	future::{self, Either},
+	FutureExt,
+};
+use futures_timer::Delay;
+use hyper::{
+	body::HttpBody,
+	header::{CONTENT_LENGTH, CONTENT_TYPE},
+	server::conn::AddrStream,
+	service::{make_service_fn, service_fn},
+	Body, Method, Request, Response, Server, StatusCode,
+};
+use jsonrpc_core::{
+	serde_json, BoxFuture, Error, ErrorCode, IoHandler, Metadata, Params, RemoteProcedure, Result,
+	RpcMethod, Value,
+};
+use sc_service::SpawnTaskHandle;
+use serde::de::{DeserializeSeed, Deserializer, Error as _, IgnoredAny, SeqAccess, Visitor};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::Readiness;
+
+/// Methods executing the EVM, subject to the execution timeout.
+const EVM_METHODS: &[&str] = &["eth_call", "eth_estimateGas", "eth_createAccessList"];
+
+/// Number of IP addresses tracked. Past it, the ones with a full bucket are forgotten, then the
+/// fullest one.
+const MAX_TRACKED_IPS: usize = 10_000;
+
+/// Error code of rate limited and oversized requests, as assigned by EIP-1474.
+pub const LIMIT_EXCEEDED_ERROR_CODE: i64 = -32005;
+
+/// Error code of calls aborted by the execution timeout.
+pub const TIMEOUT_ERROR_CODE: i64 = -32000;
+
+fn limit_err<T: ToString>(message: T) -> Error {
+	Error {
+		code: ErrorCode::ServerError(LIMIT_EXCEEDED_ERROR_CODE),
+		message: message.to_string(),
+		data: None,
+	}
+}
+
+/// Refills `rate` tokens per second, holding at most one second worth of them.
+struct TokenBucket {
+	rate: f64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: u32) -> Self {
+		Self {
+			rate: rate as f64,
+			tokens: rate as f64,
+			last_refill: Instant::now(),
+		}
+	}
+
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now
+			.saturating_duration_since(self.last_refill)
+			.as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+		self.last_refill = now;
+	}
+
+	fn is_full(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.tokens >= self.rate
+	}
+
+	fn try_take(&mut self, tokens: usize, now: Instant) -> bool {
+		self.refill(now);
+		if self.tokens < tokens as f64 {
+			return false;
+		}
+		self.tokens -= tokens as f64;
+		true
+	}
+}
+
+/// Limits applied by `RpcGuard`. Unset limits are not enforced.
+#[derive(Clone, Debug, Default)]
+pub struct RpcGuardConfig {
+	/// Calls per second a single IP address may make, each call of a batch counting. Only
+	/// enforced by `start_guarded_server`.
+	pub ip_rate_limit: Option<u32>,
+	/// Calls per second of a method, across all callers.
+	pub method_rate_limits: HashMap<String, u32>,
+	/// Maximum number of calls in a batch. Only enforced by `start_guarded_server`.
+	pub max_batch_size: Option<usize>,
+	/// Maximum size of a request body, in bytes. Only enforced by `start_guarded_server`.
+	pub max_request_size: Option<usize>,
+	/// Time after which calls executing the EVM fail. The execution itself cannot be
+	/// interrupted and carries on in the background until it ends.
+	pub evm_timeout: Option<Duration>,
+	/// Maximum number of EVM executions at a time, including the ones that outlived the
+	/// timeout. Calls beyond it fail right away.
+	pub max_concurrent_evm_calls: Option<usize>,
+}
+
+/// Per-IP rate limit and maximum batch size of the requests of a transport.
+struct RequestLimiter {
+	ip_rate_limit: Option<u32>,
+	max_batch_size: Option<usize>,
+	ip_buckets: Mutex<HashMap<IpAddr, TokenBucket>>,
+}
+
+impl RequestLimiter {
+	fn new(ip_rate_limit: Option<u32>, max_batch_size: Option<usize>) -> Self {
+		Self {
+			ip_rate_limit,
+			max_batch_size,
+			ip_buckets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	fn admit(&self, ip: IpAddr, calls: usize, now: Instant) -> Result<()> {
+		if let Some(max_batch_size) = self.max_batch_size {
+			if calls > max_batch_size {
+				return Err(limit_err(format!(
+					"batch of {} calls exceeds the maximum of {}",
+					calls, max_batch_size
+				)));
+			}
+		}
+
+		if let Some(rate) = self.ip_rate_limit {
+			let mut buckets = self
+				.ip_buckets
+				.lock()
+				.expect("ip buckets lock poisoned; qed");
+			if buckets.len() >= MAX_TRACKED_IPS && !buckets.contains_key(&ip) {
+				buckets.retain(|_, bucket| !bucket.is_full(now));
+				if buckets.len() >= MAX_TRACKED_IPS {
+					// All buckets were just refilled, so the fullest is the least active.
+					let fullest = buckets
+						.iter()
+						.max_by(|(_, a), (_, b)| {
+							a.tokens.partial_cmp(&b.tokens).unwrap_or(Ordering::Equal)
+						})
+						.map(|(ip, _)| *ip);
+					if let Some(fullest) = fullest {
+						buckets.remove(&fullest);
+					}
+				}
+			}
+			let bucket = buckets.entry(ip).or_insert_with(|| TokenBucket::new(rate));
+			if !bucket.try_take(calls, now) {
+				return Err(limit_err(format!(
+					"rate limit of {} calls per second exceeded",
+					rate
+				)));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Protects the RPC against expensive or abusive use.
+///
+/// Per-method limits, the EVM timeout and the bound on concurrent EVM executions are enforced by
+/// the methods `guard` wraps, on any transport. The per-IP limit, the batch size and the request
+/// size are enforced by the transport, as the methods do not know who calls them; only
+/// `start_guarded_server` does, the standard RPC servers do not.
+pub struct RpcGuard {
+	config: RpcGuardConfig,
+	requests: RequestLimiter,
+	method_buckets: HashMap<String, Mutex<TokenBucket>>,
+	evm_calls: Option<Arc<Semaphore>>,
+	spawn_handle: SpawnTaskHandle,
+}
+
+impl RpcGuard {
+	pub fn new(config: RpcGuardConfig, spawn_handle: SpawnTaskHandle) -> Self {
+		let method_buckets = config
+			.method_rate_limits
+			.iter()
+			.map(|(method, rate)| (method.clone(), Mutex::new(TokenBucket::new(*rate))))
+			.collect();
+		Self {
+			requests: RequestLimiter::new(config.ip_rate_limit, config.max_batch_size),
+			evm_calls: config
+				.max_concurrent_evm_calls
+				.map(|max| Arc::new(Semaphore::new(max))),
+			config,
+			method_buckets,
+			spawn_handle,
+		}
+	}
+
+	/// Wrap the methods of `io` in the per-method limits and the EVM timeout.
+	pub fn guard<M: Metadata>(self: &Arc<Self>, mut io: IoHandler<M>) -> IoHandler<M> {
+		let guarded: Vec<_> = io
+			.iter()
+			.filter_map(|(name, procedure)| match procedure {
+				RemoteProcedure::Method(method) => Some((
+					name.clone(),
+					RemoteProcedure::Method(Arc::new(GuardedMethod {
+						name: name.clone(),
+						method: method.clone(),
+						guard: self.clone(),
+					}) as Arc<dyn RpcMethod<M>>),
+				)),
+				_ => None,
+			})
+			.collect();
+		io.extend_with(guarded);
+		io
+	}
+
+	/// Admit a request of `calls` calls from `ip`, consuming one token of its bucket per call.
+	pub fn admit_request(&self, ip: IpAddr, calls: usize) -> Result<()> {
+		self.requests.admit(ip, calls, Instant::now())
+	}
+
+	/// Admit an EVM execution of `method` made outside of the methods `guard` wraps, such as by
+	/// GraphQL, applying its rate limit and the bound on concurrent EVM executions. Its slot is
+	/// released when the permit is dropped.
+	pub fn admit_evm_call(&self, method: &str) -> Result<EvmCallPermit> {
+		self.admit_call(method)?;
+		let permit = match &self.evm_calls {
+			Some(evm_calls) => Some(
+				evm_calls
+					.clone()
+					.try_acquire_owned()
+					.map_err(|_| limit_err("too many concurrent EVM calls, try again later"))?,
+			),
+			None => None,
+		};
+		Ok(EvmCallPermit { _permit: permit })
+	}
+
+	/// Read the body of `request`, failing with `413 Payload Too Large` past the maximum request
+	/// size.
+	pub async fn read_body(
+		&self,
+		request: Request<Body>,
+	) -> std::result::Result<Vec<u8>, StatusCode> {
+		read_body(request, self.config.max_request_size.unwrap_or(usize::MAX)).await
+	}
+
+	fn admit_call(&self, method: &str) -> Result<()> {
+		if let Some(bucket) = self.method_buckets.get(method) {
+			let mut bucket = bucket.lock().expect("method bucket lock poisoned; qed");
+			if !bucket.try_take(1, Instant::now()) {
+				return Err(limit_err(format!("rate limit of {} exceeded", method)));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Slot of an EVM execution admitted by `RpcGuard`, released when dropped.
+pub struct EvmCallPermit {
+	_permit: Option<OwnedSemaphorePermit>,
+}
+
+struct GuardedMethod<M: Metadata> {
+	name: String,
+	method: Arc<dyn RpcMethod<M>>,
+	guard: Arc<RpcGuard>,
+}
+
+impl<M: Metadata> RpcMethod<M> for GuardedMethod<M> {
+	fn call(&self, params: Params, meta: M) -> BoxFuture<Result<Value>> {
+		if !EVM_METHODS.contains(&self.name.as_str()) {
+			if let Err(err) = self.guard.admit_call(&self.name) {
+				return Box::pin(future::err(err));
+			}
+			return self.method.call(params, meta);
+		}
+
+		// Held until the execution ends, even past the timeout.
+		let permit = match self.guard.admit_evm_call(&self.name) {
+			Ok(permit) => permit,
+			Err(err) => return Box::pin(future::err(err)),
+		};
+
+		let timeout = match self.guard.config.evm_timeout {
+			Some(timeout) => timeout,
+			None => {
+				let call = self.method.call(params, meta);
+				return Box::pin(async move {
+					let result = call.await;
+					drop(permit);
+					result
+				});
+			}
+		};
+
+		// The EVM cannot be interrupted, so the call runs on a blocking task and is given up on
+		// when it outlives the timeout. The execution still runs to completion.
+		let (result_tx, result_rx) = oneshot::channel();
+		let method = self.method.clone();
+		self.guard
+			.spawn_handle
+			.spawn_blocking("frontier-rpc-evm-call", None, async move {
+				let _ = result_tx.send(method.call(params, meta).await);
+				drop(permit);
+			});
+
+		Box::pin(async move {
+			match future::select(result_rx, Delay::new(timeout)).await {
+				Either::Left((Ok(result), _)) => result,
+				Either::Left((Err(_), _)) => Err(Error::internal_error()),
+				Either::Right(_) => Err(Error {
+					code: ErrorCode::ServerError(TIMEOUT_ERROR_CODE),
+					message: format!("execution aborted (timeout = {:?})", timeout),
+					data: None,
+				}),
+			}
+		})
+	}
+}
+
+/// Bind `addr` and return the future serving `io` over HTTP, with the per-IP limit, the batch
+/// size and the request size of `guard` enforced. `GET /health` answers while the server runs,
+/// and `GET /ready` only while `readiness` passes. The standard RPC servers of Substrate do not
+/// serve these paths.
+pub fn start_guarded_server<M: Metadata + Default>(
+	addr: SocketAddr,
+	io: IoHandler<M>,
+	guard: Arc<RpcGuard>,
+	readiness: Arc<dyn Readiness>,
+) -> std::result::Result<impl Future<Output = ()>, String> {
+	let builder = Server::try_bind(&addr)
+		.map_err(|err| format!("Failed to bind guarded RPC server on {}: {:?}", addr, err))?;
+	let io = Arc::new(io);
+
+	let make_service = make_service_fn(move |conn: &AddrStream| {
+		let ip = conn.remote_addr().ip();
+		let io = io.clone();
+		let guard = guard.clone();
+		let readiness = readiness.clone();
+		future::ok::<_, hyper::Error>(service_fn(move |request| {
+			handle(ip, io.clone(), guard.clone(), readiness.clone(), request)
+				.map(Ok::<_, hyper::Error>)
+		}))
+	});
+
+	let server = builder.serve(make_service);
+	log::info!(target: "rpc", "Guarded RPC server listening on {}", server.local_addr());
+
+	Ok(async move {
+		if let Err(err) = server.await {
+			log::error!(target: "rpc", "Guarded RPC server error: {:?}", err);
+		}
+	})
+}
+
+async fn handle<M: Metadata + Default>(
+	ip: IpAddr,
+	io: Arc<IoHandler<M>>,
+	guard: Arc<RpcGuard>,
+	readiness: Arc<dyn Readiness>,
+	request: Request<Body>,
+) -> Response<Body> {
+	if request.method() == Method::GET {
+		return match request.uri().path() {
+			"/health" => response(StatusCode::OK, Body::empty()),
+			"/ready" => match readiness.check() {
+				Ok(()) => response(StatusCode::OK, Body::empty()),
+				Err(reason) => response(StatusCode::SERVICE_UNAVAILABLE, Body::from(reason)),
+			},
+			_ => response(StatusCode::NOT_FOUND, Body::empty()),
+		};
+	}
+	if request.method() != Method::POST {
+		return response(StatusCode::METHOD_NOT_ALLOWED, Body::empty());
+	}
+	let body = match guard.read_body(request).await {
+		Ok(body) => body,
+		Err(status) => return response(status, Body::empty()),
+	};
+	let body = match std::str::from_utf8(&body) {
+		Ok(body) => body,
+		Err(_) => return response(StatusCode::BAD_REQUEST, Body::empty()),
+	};
+
+	let calls = count_calls(body, guard.config.max_batch_size.unwrap_or(usize::MAX));
+	if let Err(err) = guard.admit_request(ip, calls) {
+		let output = jsonrpc_core::Response::from(err, Some(jsonrpc_core::Version::V2));
+		return json_response(StatusCode::TOO_MANY_REQUESTS, &output);
+	}
+
+	match io.handle_request(body, M::default()).await {
+		Some(output) => {
+			let mut ok = response(StatusCode::OK, Body::from(output));
+			ok.headers_mut().insert(
+				CONTENT_TYPE,
+				"application/json".parse().expect("valid header"),
+			);
+			ok
+		}
+		None => response(StatusCode::OK, Body::empty()),
+	}
+}
+
+/// Read the body of `request`, failing with `413 Payload Too Large` past `max_size` bytes.
+async fn read_body(
+	request: Request<Body>,
+	max_size: usize,
+) -> std::result::Result<Vec<u8>, StatusCode> {
+	let declared_size = request
+		.headers()
+		.get(CONTENT_LENGTH)
+		.and_then(|size| size.to_str().ok()?.parse::<usize>().ok());
+	if declared_size.map_or(false, |size| size > max_size) {
+		return Err(StatusCode::PAYLOAD_TOO_LARGE);
+	}
+
+	let mut body = request.into_body();
+	let mut bytes = Vec::with_capacity(declared_size.unwrap_or(0));
+	while let Some(chunk) = body.data().await {
+		let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+		if bytes.len() + chunk.len() > max_size {
+			return Err(StatusCode::PAYLOAD_TOO_LARGE);
+		}
+		bytes.extend_from_slice(&chunk);
+	}
+	Ok(bytes)
+}
+
+/// Number of calls in a request body, counted without deserializing them, up to one past
+/// `limit`. Bodies that are not a batch count as one call, and unparsable ones are answered
+/// with a parse error by the handler.
+fn count_calls(body: &str, limit: usize) -> usize {
+	let calls = Cell::new(0);
+	let counter = CallCounter {
+		calls: &calls,
+		limit,
+	};
+	match counter.deserialize(&mut serde_json::Deserializer::from_str(body)) {
+		Ok(()) => calls.get(),
+		Err(_) if calls.get() > limit => calls.get(),
+		Err(_) => 1,
+	}
+}
+
+struct CallCounter<'a> {
+	calls: &'a Cell<usize>,
+	limit: usize,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for CallCounter<'a> {
+	type Value = ();
+
+	fn deserialize<D: Deserializer<'de>>(
+		self,
+		deserializer: D,
+	) -> std::result::Result<(), D::Error> {
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'de, 'a> Visitor<'de> for CallCounter<'a> {
+	type Value = ();
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a batch of calls")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+		while seq.next_element::<IgnoredAny>()?.is_some() {
+			self.calls.set(self.calls.get() + 1);
+			if self.calls.get() > self.limit {
+				return Err(A::Error::custom("batch too large"));
+			}
+		}
+		Ok(())
+	}
+}
+
+fn json_response(status: StatusCode, output: &jsonrpc_core::Response) -> Response<Body> {
+	let mut json = response(
+		status,
+		Body::from(serde_json::to_string(output).unwrap_or_default()),
+	);
+	json.headers_mut().insert(
+		CONTENT_TYPE,
+		"application/json".parse().expect("valid header"),
+	);
+	json
+}
+
+fn response(status: StatusCode, body: Body) -> Response<Body> {
+	let mut response = Response::new(body);
+	*response.status_mut() = status;
+	response
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::Ipv4Addr;
+
+	fn ip(n: u32) -> IpAddr {
+		IpAddr::V4(Ipv4Addr::from(n))
+	}
+
+	#[test]
+	fn token_bucket_refills_at_its_rate() {
+		let mut bucket = TokenBucket::new(10);
+		let start = bucket.last_refill;
+		assert!(bucket.is_full(start));
+		assert!(bucket.try_take(10, start));
+		assert!(!bucket.try_take(1, start));
+
+		// Half a second refills half the bucket.
+		let later = start + Duration::from_millis(500);
+		assert!(!bucket.try_take(6, later));
+		assert!(bucket.try_take(5, later));
+		assert!(!bucket.is_full(later));
+	}
+
+	#[test]
+	fn token_bucket_is_capped_at_its_rate() {
+		let mut bucket = TokenBucket::new(10);
+		let later = bucket.last_refill + Duration::from_secs(60);
+		assert!(bucket.is_full(later));
+		assert!(!bucket.try_take(11, later));
+		assert!(bucket.try_take(10, later));
+	}
+
+	#[test]
+	fn admit_rejects_large_batches() {
+		let limiter = RequestLimiter::new(None, Some(3));
+		let now = Instant::now();
+		assert!(limiter.admit(ip(1), 3, now).is_ok());
+		assert!(limiter.admit(ip(1), 4, now).is_err());
+	}
+
+	#[test]
+	fn admit_limits_each_ip() {
+		let limiter = RequestLimiter::new(Some(2), None);
+		let now = Instant::now();
+		assert!(limiter.admit(ip(1), 2, now).is_ok());
+		assert!(limiter.admit(ip(1), 1, now).is_err());
+		assert!(limiter.admit(ip(2), 1, now).is_ok());
+		assert!(limiter
+			.admit(ip(1), 1, now + Duration::from_secs(1))
+			.is_ok());
+	}
+
+	#[test]
+	fn admit_bounds_tracked_ips() {
+		let limiter = RequestLimiter::new(Some(2), None);
+		let now = Instant::now();
+		for n in 0..MAX_TRACKED_IPS as u32 {
+			assert!(limiter.admit(ip(n), 2, now).is_ok());
+		}
+		// No bucket is full, so one is evicted to make room.
+		assert!(limiter.admit(ip(u32::MAX), 1, now).is_ok());
+		let buckets = limiter.ip_buckets.lock().unwrap();
+		assert_eq!(buckets.len(), MAX_TRACKED_IPS);
+		assert!(buckets.contains_key(&ip(u32::MAX)));
+	}
+
+	#[test]
+	fn count_calls_of_requests() {
+		let call = r#"{"jsonrpc":"2.0","id":1,"method":"eth_chainId","params":[]}"#;
+		assert_eq!(count_calls(call, 10), 1);
+		assert_eq!(count_calls(&format!("[{},{}]", call, call), 10), 2);
+		assert_eq!(count_calls("[]", 10), 0);
+		assert_eq!(count_calls("not json", 10), 1);
+	}
+
+	#[test]
+	fn count_calls_stops_past_the_limit() {
+		let batch = format!("[{}1, {{\"unterminated\"", "1, ".repeat(99));
+		assert_eq!(count_calls(&batch, 3), 4);
+	}
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use ethereum_types::H256;
+use sc_network::{ExHashT, NetworkService};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+
+/// Decides whether the node should be served traffic.
+pub trait Readiness: Send + Sync {
+	/// `Err` with the reason when the node is not ready.
+	fn check(&self) -> Result<(), String>;
+}
+
+/// Thresholds of `EthReadiness`.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadinessThresholds {
+	/// Maximum number of best chain blocks above the highest mapped one.
+	pub max_mapping_lag: u64,
+	/// Minimum number of connected peers.
+	pub min_peers: usize,
+}
+
+/// The node is ready to answer Ethereum RPC requests once it is neither major syncing nor
+/// behind in mapping blocks, and has enough peers.
+pub struct EthReadiness<B: BlockT, C, H: ExHashT> {
+	client: Arc<C>,
+	backend: Arc<fc_db::Backend<B>>,
+	network: Arc<NetworkService<B, H>>,
+	thresholds: ReadinessThresholds,
+}
+
+impl<B: BlockT, C, H: ExHashT> EthReadiness<B, C, H> {
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<fc_db::Backend<B>>,
+		network: Arc<NetworkService<B, H>>,
+		thresholds: ReadinessThresholds,
+	) -> Self {
+		Self {
+			client,
+			backend,
+			network,
+			thresholds,
+		}
+	}
+}
+
+impl<B, C, H> Readiness for EthReadiness<B, C, H>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	H: ExHashT,
+{
+	fn check(&self) -> Result<(), String> {
+		self.thresholds.check(
+			self.network.is_major_syncing(),
+			self.network.num_connected(),
+			|max_lag| {
+				fc_mapping_sync::mapping_lag(
+					self.client.as_ref(),
+					self.backend.as_ref(),
+					self.client.info().best_hash,
+					max_lag,
+				)
+			},
+		)
+	}
+}
+
+impl ReadinessThresholds {
+	/// `mapping_lag` returns the mapping lag, walking at most the given number of blocks. It is
+	/// only called once syncing and peers pass.
+	fn check(
+		&self,
+		is_major_syncing: bool,
+		peers: usize,
+		mapping_lag: impl FnOnce(u64) -> Result<u64, String>,
+	) -> Result<(), String> {
+		if is_major_syncing {
+			return Err("major syncing".to_string());
+		}
+
+		if peers < self.min_peers {
+			return Err(format!(
+				"{} connected peers, below the minimum of {}",
+				peers, self.min_peers
+			));
+		}
+
+		// Walking one block past the threshold is enough to know it is exceeded.
+		let max_lag = self.max_mapping_lag;
+		let lag = mapping_lag(max_lag.saturating_add(1))?;
+		if lag > max_lag {
+			return Err(format!(
+				"mapping more than {} blocks behind the best block",
+				max_lag
+			));
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const THRESHOLDS: ReadinessThresholds = ReadinessThresholds {
+		max_mapping_lag: 16,
+		min_peers: 1,
+	};
+
+	#[test]
+	fn ready_when_synced_with_peers_and_mapped() {
+		assert_eq!(THRESHOLDS.check(false, 1, |_| Ok(0)), Ok(()));
+		assert_eq!(THRESHOLDS.check(false, 5, |_| Ok(16)), Ok(()));
+	}
+
+	#[test]
+	fn not_ready_while_major_syncing() {
+		assert_eq!(
+			THRESHOLDS.check(true, 5, |_| panic!("mapping lag checked while syncing")),
+			Err("major syncing".to_string())
+		);
+	}
+
+	#[test]
+	fn not_ready_below_the_minimum_peers() {
+		assert_eq!(
+			THRESHOLDS.check(false, 0, |_| Ok(0)),
+			Err("0 connected peers, below the minimum of 1".to_string())
+		);
+		let no_peers = ReadinessThresholds {
+			min_peers: 0,
+			..THRESHOLDS
+		};
+		assert_eq!(no_peers.check(false, 0, |_| Ok(0)), Ok(()));
+	}
+
+	#[test]
+	fn not_ready_past_the_mapping_lag() {
+		let mut walked = None;
+		assert_eq!(
+			THRESHOLDS.check(false, 1, |max_lag| {
+				walked = Some(max_lag);
+				Ok(max_lag)
+			}),
+			Err("mapping more than 16 blocks behind the best block".to_string())
+		);
+		assert_eq!(walked, Some(17));
+		assert_eq!(
+			THRESHOLDS.check(false, 1, |_| Err("database error".to_string())),
+			Err("database error".to_string())
+		);
+	}
+}
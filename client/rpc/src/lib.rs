@@ -18,30 +18,47 @@
 
 mod eth;
 mod eth_pubsub;
+mod guard;
+mod health;
+mod metrics;
 mod net;
+mod ots;
 mod overrides;
 mod signer;
+mod txpool;
 mod web3;
 
 pub use self::{
-	eth::{EthApi, EthBlockDataCache, EthFilterApi, EthTask},
+	eth::{
+		sync_progress_channel, CallOutcome, EthApi, EthBlockDataCache, EthFilterApi,
+		EthPendingBlockCache, EthTask, PendingBlock, PendingBlockInherents, SyncProgress,
+	},
 	eth_pubsub::{EthPubSubApi, HexEncodedIdProvider},
+	guard::{
+		start_guarded_server, EvmCallPermit, RpcGuard, RpcGuardConfig, LIMIT_EXCEEDED_ERROR_CODE,
+		TIMEOUT_ERROR_CODE,
+	},
+	health::{EthReadiness, Readiness, ReadinessThresholds},
+	metrics::RpcMetrics,
 	net::NetApi,
+	ots::OtsApi,
 	overrides::{
 		OverrideHandle, RuntimeApiStorageOverride, SchemaV1Override, SchemaV2Override,
 		SchemaV3Override, StorageOverride,
 	},
-	signer::{EthDevSigner, EthSigner},
+	signer::{EthDevSigner, EthImpersonator, EthSigner},
+	txpool::TxPool,
 	web3::Web3Api,
 };
 
 pub use ethereum::TransactionV2 as EthereumTransaction;
 pub use fc_rpc_core::{
-	EthApiServer, EthFilterApiServer, EthPubSubApiServer, NetApiServer, Web3ApiServer,
+	EthApiServer, EthFilterApiServer, EthPubSubApiServer, NetApiServer, OtsApiServer,
+	TxPoolApiServer, Web3ApiServer,
 };
 
 pub mod frontier_backend_client {
-	use super::internal_err;
+	use super::{internal_err, pruned_err, pruned_hash_err};
 
 	use codec::Decode;
 	use ethereum_types::H256;
@@ -68,14 +85,59 @@ pub mod frontier_backend_client {
 		C: HeaderBackend<B> + Send + Sync + 'static,
 	{
 		Ok(match number.unwrap_or(BlockNumber::Latest) {
-			BlockNumber::Hash { hash, .. } => load_hash::<B>(backend, hash).unwrap_or(None),
-			BlockNumber::Num(number) => Some(BlockId::Number(number.unique_saturated_into())),
+			BlockNumber::Hash { hash, .. } => match load_hash::<B>(backend, hash).unwrap_or(None) {
+				Some(id) => Some(id),
+				None => missing_or_pruned::<B, _>(backend)?,
+			},
+			BlockNumber::Num(number) => {
+				ensure_retained::<B>(backend, number)?;
+				Some(BlockId::Number(number.unique_saturated_into()))
+			}
 			BlockNumber::Latest => Some(BlockId::Hash(client.info().best_hash)),
 			BlockNumber::Earliest => Some(BlockId::Number(Zero::zero())),
 			BlockNumber::Pending => None,
+			// Grandpa finality is the only guarantee against reorgs.
+			BlockNumber::Safe | BlockNumber::Finalized => {
+				Some(BlockId::Hash(client.info().finalized_hash))
+			}
 		})
 	}
 
+	/// Fail with a "pruned" error if the mappings of block `number` were pruned.
+	pub fn ensure_retained<B: BlockT>(backend: &fc_db::Backend<B>, number: u64) -> RpcResult<()>
+	where
+		B: BlockT<Hash = H256> + Send + Sync + 'static,
+	{
+		let first_retained = backend
+			.meta()
+			.first_retained_block()
+			.map_err(|err| internal_err(format!("fetch backend failed: {:?}", err)))?;
+
+		match first_retained.map(UniqueSaturatedInto::<u64>::unique_saturated_into) {
+			Some(first_retained) if number < first_retained => {
+				Err(pruned_err(number, first_retained))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Result of a lookup by hash that missed the mappings: `None`, or a "pruned" error if older
+	/// mappings were pruned, as the hash may have been one of them.
+	pub fn missing_or_pruned<B: BlockT, T>(backend: &fc_db::Backend<B>) -> RpcResult<Option<T>>
+	where
+		B: BlockT<Hash = H256> + Send + Sync + 'static,
+	{
+		let first_retained = backend
+			.meta()
+			.first_retained_block()
+			.map_err(|err| internal_err(format!("fetch backend failed: {:?}", err)))?;
+
+		match first_retained.map(UniqueSaturatedInto::<u64>::unique_saturated_into) {
+			Some(first_retained) if first_retained > 0 => Err(pruned_hash_err(first_retained)),
+			_ => Ok(None),
+		}
+	}
+
 	pub fn load_hash<B: BlockT>(
 		backend: &fc_db::Backend<B>,
 		hash: H256,
@@ -194,6 +256,31 @@ pub fn internal_err<T: ToString>(message: T) -> jsonrpc_core::Error {
 	}
 }
 
+/// Error code of requests for blocks whose Frontier mappings were pruned.
+pub const PRUNED_ERROR_CODE: i64 = 4444;
+
+pub fn pruned_err(number: u64, first_retained: u64) -> jsonrpc_core::Error {
+	jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(PRUNED_ERROR_CODE),
+		message: format!(
+			"pruned: block {} is older than the retained history, which starts at block {}",
+			number, first_retained
+		),
+		data: None,
+	}
+}
+
+pub fn pruned_hash_err(first_retained: u64) -> jsonrpc_core::Error {
+	jsonrpc_core::Error {
+		code: jsonrpc_core::ErrorCode::ServerError(PRUNED_ERROR_CODE),
+		message: format!(
+			"pruned: not found in the retained history, which starts at block {}",
+			first_retained
+		),
+		data: None,
+	}
+}
+
 pub fn public_key(transaction: &EthereumTransaction) -> Result<[u8; 64], sp_io::EcdsaVerifyError> {
 	let mut sig = [0u8; 65];
 	let mut msg = [0u8; 32];
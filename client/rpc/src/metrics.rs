@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{sync::Arc, time::Instant};
+
+use futures::FutureExt;
+use jsonrpc_core::{
+	BoxFuture, IoHandler, Metadata, Params, RemoteProcedure, Result as RpcResult, RpcMethod, Value,
+};
+use prometheus_endpoint::{
+	register, CounterVec, Gauge, GaugeVec, HistogramOpts, HistogramVec, Opts, PrometheusError,
+	Registry, U64,
+};
+
+use fc_rpc_core::types::pubsub::Kind;
+
+/// Prometheus metrics of the Ethereum RPC.
+#[derive(Clone)]
+pub struct RpcMetrics {
+	calls: CounterVec<U64>,
+	call_duration: HistogramVec,
+	cache_lookups: CounterVec<U64>,
+	filter_pool_size: Gauge<U64>,
+	subscriptions: GaugeVec<U64>,
+	fee_history_cache_size: Gauge<U64>,
+}
+
+impl RpcMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			calls: register(
+				CounterVec::new(
+					Opts::new(
+						"frontier_rpc_calls_total",
+						"Number of calls of each eth_* method",
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+			call_duration: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"frontier_rpc_call_duration_seconds",
+						"Time taken to answer calls of each eth_* method",
+					),
+					&["method"],
+				)?,
+				registry,
+			)?,
+			cache_lookups: register(
+				CounterVec::new(
+					Opts::new(
+						"frontier_rpc_block_data_cache_lookups_total",
+						"Number of EthBlockDataCache lookups, by cache and hit or miss",
+					),
+					&["cache", "result"],
+				)?,
+				registry,
+			)?,
+			filter_pool_size: register(
+				Gauge::new(
+					"frontier_rpc_filter_pool_size",
+					"Number of installed filters, sampled at each imported block",
+				)?,
+				registry,
+			)?,
+			subscriptions: register(
+				GaugeVec::new(
+					Opts::new(
+						"frontier_rpc_subscriptions",
+						"Number of active eth_subscribe subscriptions of each kind",
+					),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+			fee_history_cache_size: register(
+				Gauge::new(
+					"frontier_rpc_fee_history_cache_size",
+					"Number of blocks held by the fee history cache",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Wrap the `eth_*` methods of `io` in the call count and duration metrics.
+	pub fn instrument<M: Metadata>(&self, mut io: IoHandler<M>) -> IoHandler<M> {
+		let instrumented: Vec<_> = io
+			.iter()
+			.filter_map(|(name, procedure)| match procedure {
+				RemoteProcedure::Method(method) if name.starts_with("eth_") => Some((
+					name.clone(),
+					RemoteProcedure::Method(Arc::new(InstrumentedMethod {
+						name: name.clone(),
+						method: method.clone(),
+						metrics: self.clone(),
+					}) as Arc<dyn RpcMethod<M>>),
+				)),
+				_ => None,
+			})
+			.collect();
+		io.extend_with(instrumented);
+		io
+	}
+
+	pub(crate) fn cache_lookup(&self, cache: &str, hit: bool) {
+		self.cache_lookups
+			.with_label_values(&[cache, if hit { "hit" } else { "miss" }])
+			.inc();
+	}
+
+	pub(crate) fn set_filter_pool_size(&self, size: usize) {
+		self.filter_pool_size.set(size as u64);
+	}
+
+	pub(crate) fn set_fee_history_cache_size(&self, size: usize) {
+		self.fee_history_cache_size.set(size as u64);
+	}
+
+	/// Count a subscription of `kind` as active until the returned guard is dropped.
+	pub(crate) fn subscription_started(&self, kind: &Kind) -> ActiveSubscription {
+		let gauge = self.subscriptions.with_label_values(&[match kind {
+			Kind::NewHeads => "newHeads",
+			Kind::Logs => "logs",
+			Kind::NewPendingTransactions => "newPendingTransactions",
+			Kind::Syncing => "syncing",
+		}]);
+		gauge.inc();
+		ActiveSubscription(gauge)
+	}
+}
+
+pub(crate) struct ActiveSubscription(Gauge<U64>);
+
+impl Drop for ActiveSubscription {
+	fn drop(&mut self) {
+		self.0.dec();
+	}
+}
+
+struct InstrumentedMethod<M: Metadata> {
+	name: String,
+	method: Arc<dyn RpcMethod<M>>,
+	metrics: RpcMetrics,
+}
+
+impl<M: Metadata> RpcMethod<M> for InstrumentedMethod<M> {
+	fn call(&self, params: Params, meta: M) -> BoxFuture<RpcResult<Value>> {
+		let started = Instant::now();
+		self.metrics.calls.with_label_values(&[&self.name]).inc();
+		let duration = self.metrics.call_duration.with_label_values(&[&self.name]);
+		Box::pin(
+			self.method
+				.call(params, meta)
+				.inspect(move |_| duration.observe(started.elapsed().as_secs_f64())),
+		)
+	}
+}
@@ -0,0 +1,529 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use ethereum::BlockV2 as EthereumBlock;
+use ethereum_types::{H160, H256, U256};
+use jsonrpc_core::{BoxFuture, Result};
+use sc_client_api::backend::{Backend, StateBackend, StorageProvider};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::hashing::keccak_256;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{BlakeTwo256, Block as BlockT},
+};
+
+use fc_rpc_core::{types::*, OtsApi as OtsApiT};
+use fp_rpc::{EthereumRuntimeRPCApi, TransactionStatus};
+
+use crate::{
+	eth::{receipts_build, rich_block_build, transaction_build, EthBlockDataCache},
+	frontier_backend_client, internal_err,
+	overrides::OverrideHandle,
+};
+
+/// Version of the Otterscan API implemented.
+const API_LEVEL: u8 = 8;
+
+/// Largest page of `ots_searchTransactionsBefore` and `ots_searchTransactionsAfter`, in
+/// transactions. A page still ends with the whole block of its last transaction.
+const MAX_PAGE_SIZE: usize = 25;
+
+pub struct OtsApi<B: BlockT, C, BE> {
+	client: Arc<C>,
+	backend: Arc<fc_db::Backend<B>>,
+	overrides: Arc<OverrideHandle<B>>,
+	block_data_cache: Arc<EthBlockDataCache<B>>,
+	_marker: PhantomData<BE>,
+}
+
+impl<B: BlockT, C, BE> OtsApi<B, C, BE> {
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<fc_db::Backend<B>>,
+		overrides: Arc<OverrideHandle<B>>,
+		block_data_cache: Arc<EthBlockDataCache<B>>,
+	) -> Self {
+		Self {
+			client,
+			backend,
+			overrides,
+			block_data_cache,
+			_marker: PhantomData,
+		}
+	}
+
+	fn index(&self) -> Result<Arc<fc_db::IndexDb>> {
+		self.backend
+			.index()
+			.cloned()
+			.ok_or_else(|| internal_err("transaction index is disabled"))
+	}
+}
+
+impl<B, C, BE> OtsApiT for OtsApi<B, C, BE>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	fn get_api_level(&self) -> Result<u8> {
+		Ok(API_LEVEL)
+	}
+
+	fn get_block_details(&self, number: BlockNumber) -> BoxFuture<Result<Option<BlockDetails>>> {
+		let client = Arc::clone(&self.client);
+		let backend = Arc::clone(&self.backend);
+		let overrides = Arc::clone(&self.overrides);
+		let block_data_cache = Arc::clone(&self.block_data_cache);
+
+		Box::pin(async move {
+			let id = match frontier_backend_client::native_block_id::<B, C>(
+				client.as_ref(),
+				backend.as_ref(),
+				Some(number),
+			)? {
+				Some(id) => id,
+				None => return Ok(None),
+			};
+			let data = match block_data::<B, C, BE>(
+				client.as_ref(),
+				overrides.as_ref(),
+				block_data_cache.as_ref(),
+				id,
+			)
+			.await?
+			{
+				Some(data) => data,
+				None => return Ok(None),
+			};
+
+			let mut block = data.rich_block();
+			block.inner.transactions = BlockTransactions::Hashes(Vec::new());
+			let total_fees = data.receipts.iter().fold(U256::zero(), |total, receipt| {
+				total.saturating_add(
+					receipt
+						.gas_used
+						.unwrap_or_default()
+						.saturating_mul(receipt.effective_gas_price),
+				)
+			});
+
+			Ok(Some(BlockDetails {
+				block: BlockSummary {
+					block,
+					transaction_count: data.block.transactions.len(),
+				},
+				issuance: Issuance::default(),
+				total_fees,
+			}))
+		})
+	}
+
+	fn get_block_transactions(
+		&self,
+		number: BlockNumber,
+		page_number: usize,
+		page_size: usize,
+	) -> BoxFuture<Result<Option<BlockTransactionsPage>>> {
+		let client = Arc::clone(&self.client);
+		let backend = Arc::clone(&self.backend);
+		let overrides = Arc::clone(&self.overrides);
+		let block_data_cache = Arc::clone(&self.block_data_cache);
+
+		Box::pin(async move {
+			let id = match frontier_backend_client::native_block_id::<B, C>(
+				client.as_ref(),
+				backend.as_ref(),
+				Some(number),
+			)? {
+				Some(id) => id,
+				None => return Ok(None),
+			};
+			let data = match block_data::<B, C, BE>(
+				client.as_ref(),
+				overrides.as_ref(),
+				block_data_cache.as_ref(),
+				id,
+			)
+			.await?
+			{
+				Some(data) => data,
+				None => return Ok(None),
+			};
+
+			let len = data.block.transactions.len();
+			let start = page_number.saturating_mul(page_size).min(len);
+			let end = start.saturating_add(page_size).min(len);
+
+			let mut fullblock = data.rich_block();
+			fullblock.inner.transactions = BlockTransactions::Full(
+				(start..end).map(|index| data.transaction(index)).collect(),
+			);
+
+			Ok(Some(BlockTransactionsPage {
+				fullblock,
+				receipts: data.receipts[start..end].to_vec(),
+			}))
+		})
+	}
+
+	fn has_code(&self, address: H160, number: Option<BlockNumber>) -> Result<bool> {
+		let id = match frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			number,
+		)? {
+			Some(id) => id,
+			None => return Ok(false),
+		};
+		let schema =
+			frontier_backend_client::onchain_storage_schema::<B, C, BE>(self.client.as_ref(), id);
+
+		Ok(self
+			.overrides
+			.schemas
+			.get(&schema)
+			.unwrap_or(&self.overrides.fallback)
+			.account_code_at(&id, address)
+			.map_or(false, |code| !code.is_empty()))
+	}
+
+	fn get_transaction_error(&self, hash: H256) -> Result<Bytes> {
+		let (ethereum_block_hash, index) = match frontier_backend_client::load_transactions::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			hash,
+			true,
+		)? {
+			Some(found) => found,
+			None => return Ok(Bytes(Vec::new())),
+		};
+		let id = match frontier_backend_client::load_hash::<B>(
+			self.backend.as_ref(),
+			ethereum_block_hash,
+		)? {
+			Some(id) => id,
+			None => return Ok(Bytes(Vec::new())),
+		};
+
+		let api = self.client.runtime_api();
+		match api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&id) {
+			Ok(Some(api_version)) if api_version >= 5 => (),
+			_ => return Ok(Bytes(Vec::new())),
+		}
+		let revert_data = api
+			.current_revert_data(&id)
+			.map_err(|err| internal_err(format!("fetch runtime revert data failed: {:?}", err)))?;
+
+		Ok(Bytes(
+			revert_data
+				.into_iter()
+				.find(|(transaction_index, _)| *transaction_index == index)
+				.map(|(_, data)| data)
+				.unwrap_or_default(),
+		))
+	}
+
+	fn search_transactions_before(
+		&self,
+		address: H160,
+		block_number: u64,
+		page_size: usize,
+	) -> BoxFuture<Result<TransactionsWithReceipts>> {
+		let client = Arc::clone(&self.client);
+		let backend = Arc::clone(&self.backend);
+		let overrides = Arc::clone(&self.overrides);
+		let block_data_cache = Arc::clone(&self.block_data_cache);
+		let index = self.index();
+
+		Box::pin(async move {
+			let from = if block_number == 0 {
+				u64::MAX
+			} else {
+				block_number
+			};
+			let (appearances, more) = index?
+				.appearances_before(&address, from, page_size.min(MAX_PAGE_SIZE))
+				.map_err(|err| {
+					internal_err(format!("fetch transaction index failed: {:?}", err))
+				})?;
+
+			let (txs, receipts) = transactions_with_receipts::<B, C, BE>(
+				client.as_ref(),
+				backend.as_ref(),
+				overrides.as_ref(),
+				block_data_cache.as_ref(),
+				appearances,
+			)
+			.await?;
+
+			Ok(TransactionsWithReceipts {
+				txs,
+				receipts,
+				first_page: block_number == 0,
+				last_page: !more,
+			})
+		})
+	}
+
+	fn search_transactions_after(
+		&self,
+		address: H160,
+		block_number: u64,
+		page_size: usize,
+	) -> BoxFuture<Result<TransactionsWithReceipts>> {
+		let client = Arc::clone(&self.client);
+		let backend = Arc::clone(&self.backend);
+		let overrides = Arc::clone(&self.overrides);
+		let block_data_cache = Arc::clone(&self.block_data_cache);
+		let index = self.index();
+
+		Box::pin(async move {
+			let (mut appearances, more) = index?
+				.appearances_after(&address, block_number, page_size.min(MAX_PAGE_SIZE))
+				.map_err(|err| {
+					internal_err(format!("fetch transaction index failed: {:?}", err))
+				})?;
+			// Pages are most recent first in both directions.
+			appearances.reverse();
+
+			let (txs, receipts) = transactions_with_receipts::<B, C, BE>(
+				client.as_ref(),
+				backend.as_ref(),
+				overrides.as_ref(),
+				block_data_cache.as_ref(),
+				appearances,
+			)
+			.await?;
+
+			Ok(TransactionsWithReceipts {
+				txs,
+				receipts,
+				first_page: !more,
+				last_page: block_number == 0,
+			})
+		})
+	}
+
+	fn get_transaction_by_sender_and_nonce(
+		&self,
+		sender: H160,
+		nonce: U256,
+	) -> Result<Option<H256>> {
+		let hashes = self
+			.index()?
+			.transactions_by_sender_and_nonce(&sender, nonce)
+			.map_err(|err| internal_err(format!("fetch transaction index failed: {:?}", err)))?;
+
+		for hash in hashes {
+			if self.is_canonical(hash)? {
+				return Ok(Some(hash));
+			}
+		}
+		Ok(None)
+	}
+
+	fn get_contract_creator(&self, address: H160) -> Result<Option<ContractCreator>> {
+		let creations = self
+			.index()?
+			.contract_creations(&address)
+			.map_err(|err| internal_err(format!("fetch transaction index failed: {:?}", err)))?;
+
+		for (hash, creator) in creations {
+			if self.is_canonical(hash)? {
+				return Ok(Some(ContractCreator { hash, creator }));
+			}
+		}
+		Ok(None)
+	}
+}
+
+impl<B, C, BE> OtsApi<B, C, BE>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+{
+	/// Whether a canonical block includes the transaction `hash`.
+	fn is_canonical(&self, hash: H256) -> Result<bool> {
+		Ok(frontier_backend_client::load_transactions::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			hash,
+			true,
+		)?
+		.is_some())
+	}
+}
+
+/// An Ethereum block with the statuses and receipts of its transactions.
+struct BlockData {
+	hash: H256,
+	block: EthereumBlock,
+	statuses: Vec<TransactionStatus>,
+	receipts: Vec<Receipt>,
+	base_fee: Option<U256>,
+	is_eip1559: bool,
+}
+
+impl BlockData {
+	/// The block with the hashes of its transactions.
+	fn rich_block(&self) -> RichBlock {
+		rich_block_build(
+			self.block.clone(),
+			self.statuses.iter().cloned().map(Some).collect(),
+			Some(self.hash),
+			false,
+			self.base_fee,
+			self.is_eip1559,
+		)
+	}
+
+	fn transaction(&self, index: usize) -> Transaction {
+		transaction_build(
+			self.block.transactions[index].clone(),
+			Some(self.block.clone()),
+			Some(self.statuses[index].clone()),
+			self.is_eip1559,
+			self.base_fee,
+		)
+	}
+}
+
+async fn block_data<B, C, BE>(
+	client: &C,
+	overrides: &OverrideHandle<B>,
+	block_data_cache: &EthBlockDataCache<B>,
+	id: BlockId<B>,
+) -> Result<Option<BlockData>>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let substrate_hash = client
+		.expect_block_hash_from_id(&id)
+		.map_err(|_| internal_err(format!("Expect block number from id: {}", id)))?;
+
+	let schema = frontier_backend_client::onchain_storage_schema::<B, C, BE>(client, id);
+	let handler = overrides
+		.schemas
+		.get(&schema)
+		.unwrap_or(&overrides.fallback);
+
+	let block = block_data_cache.current_block(schema, substrate_hash).await;
+	let statuses = block_data_cache
+		.current_transaction_statuses(schema, substrate_hash)
+		.await;
+	let receipts = handler.current_receipts(&id);
+
+	let (block, statuses, receipts) = match (block, statuses, receipts) {
+		(Some(block), Some(statuses), Some(receipts)) => (block, statuses, receipts),
+		_ => return Ok(None),
+	};
+
+	let hash = H256::from(keccak_256(&rlp::encode(&block.header)));
+	let base_fee = handler.base_fee(&id);
+	let is_eip1559 = handler.is_eip1559(&id);
+	let receipts = receipts_build(
+		client,
+		&id,
+		&block,
+		&receipts,
+		&statuses,
+		Some(hash),
+		base_fee,
+		is_eip1559,
+	)?;
+
+	Ok(Some(BlockData {
+		hash,
+		block,
+		statuses,
+		receipts,
+		base_fee,
+		is_eip1559,
+	}))
+}
+
+/// The transactions of `appearances` that are canonical, with their receipts, in the same order.
+async fn transactions_with_receipts<B, C, BE>(
+	client: &C,
+	backend: &fc_db::Backend<B>,
+	overrides: &OverrideHandle<B>,
+	block_data_cache: &EthBlockDataCache<B>,
+	appearances: Vec<fc_db::Appearance>,
+) -> Result<(Vec<Transaction>, Vec<Receipt>)>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + StorageProvider<B, BE>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	BE: Backend<B> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let mut txs = Vec::new();
+	let mut receipts = Vec::new();
+	// Appearances are grouped by block, so only the block of the last one is kept.
+	let mut current: Option<BlockData> = None;
+
+	for appearance in appearances {
+		let (ethereum_block_hash, index) = match frontier_backend_client::load_transactions::<B, C>(
+			client,
+			backend,
+			appearance.transaction_hash,
+			true,
+		)? {
+			Some(found) => found,
+			None => continue,
+		};
+		// Appearances recorded on a fork resolve to the canonical block of the transaction.
+		if index != appearance.transaction_index {
+			continue;
+		}
+
+		if current.as_ref().map(|data| data.hash) != Some(ethereum_block_hash) {
+			current = match frontier_backend_client::load_hash::<B>(backend, ethereum_block_hash)? {
+				Some(id) => block_data::<B, C, BE>(client, overrides, block_data_cache, id).await?,
+				None => None,
+			};
+		}
+		let data = match &current {
+			Some(data)
+				if data.block.header.number.low_u64() == appearance.block_number
+					&& (index as usize) < data.statuses.len() =>
+			{
+				data
+			}
+			_ => continue,
+		};
+
+		txs.push(data.transaction(index as usize));
+		receipts.push(data.receipts[index as usize].clone());
+	}
+
+	Ok((txs, receipts))
+}
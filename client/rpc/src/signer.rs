@@ -18,7 +18,7 @@
 
 use ethereum::TransactionV2 as EthereumTransaction;
 use ethereum_types::{H160, H256};
-use jsonrpc_core::Error;
+use jsonrpc_core::{BoxFuture, Error};
 
 use fc_rpc_core::types::TransactionMessage;
 use sp_core::hashing::keccak_256;
@@ -37,6 +37,15 @@ pub trait EthSigner: Send + Sync {
 	) -> Result<EthereumTransaction, Error>;
 }
 
+/// Submits transactions on behalf of accounts no signer holds a key for.
+pub trait EthImpersonator: Send + Sync {
+	/// Whether transactions from the given account are submitted through this impersonator.
+	fn is_impersonated(&self, address: &H160) -> bool;
+	/// Submit a transaction message on behalf of the given account, returning its hash.
+	fn submit(&self, message: TransactionMessage, address: &H160)
+		-> BoxFuture<Result<H256, Error>>;
+}
+
 pub struct EthDevSigner {
 	keys: Vec<libsecp256k1::SecretKey>,
 }
@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2022 Parity Technologies (UK) Ltd.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use ethereum::TransactionV2 as EthereumTransaction;
+use ethereum_types::{H160, H256, U256};
+use jsonrpc_core::Result;
+use serde::Serialize;
+
+use sc_transaction_pool::{ChainApi, Pool};
+use sc_transaction_pool_api::InPoolTransaction;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::hashing::keccak_256;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+use fc_rpc_core::{
+	types::{Summary, Transaction, TransactionMap, TxPoolGet, TxPoolResult},
+	TxPoolApi as TxPoolApiT,
+};
+use fp_rpc::EthereumRuntimeRPCApi;
+
+use crate::{internal_err, public_key};
+
+/// Ethereum transactions found in the ready and future queues of the pool.
+struct TxPoolResponse {
+	ready: Vec<EthereumTransaction>,
+	future: Vec<EthereumTransaction>,
+}
+
+pub struct TxPool<B: BlockT, C, A: ChainApi> {
+	client: Arc<C>,
+	graph: Arc<Pool<A>>,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C, A: ChainApi> TxPool<B, C, A> {
+	pub fn new(client: Arc<C>, graph: Arc<Pool<A>>) -> Self {
+		Self {
+			client,
+			graph,
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<B, C, A> TxPool<B, C, A>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	A: ChainApi<Block = B> + 'static,
+{
+	fn map_build<T>(&self) -> Result<TxPoolResult<TransactionMap<T>>>
+	where
+		T: TxPoolGet + Serialize,
+	{
+		// Get the pending and queued ethereum transactions.
+		let ethereum_txns = self.tx_pool_response()?;
+
+		Ok(TxPoolResult {
+			pending: group_by_sender(ethereum_txns.ready),
+			queued: group_by_sender(ethereum_txns.future),
+		})
+	}
+
+	fn tx_pool_response(&self) -> Result<TxPoolResponse> {
+		// Collect transactions in the ready validated pool.
+		let txs_ready = self
+			.graph
+			.validated_pool()
+			.ready()
+			.map(|in_pool_tx| in_pool_tx.data().clone())
+			.collect();
+
+		// Collect transactions in the future validated pool.
+		let txs_future = self
+			.graph
+			.validated_pool()
+			.futures()
+			.iter()
+			.map(|(_hash, extrinsic)| extrinsic.clone())
+			.collect();
+
+		// Use the runtime to match the (here) opaque extrinsics against ethereum transactions.
+		let best_block: BlockId<B> = BlockId::Hash(self.client.info().best_hash);
+		let api = self.client.runtime_api();
+		let api_version = if let Ok(Some(api_version)) =
+			api.api_version::<dyn EthereumRuntimeRPCApi<B>>(&best_block)
+		{
+			api_version
+		} else {
+			return Err(internal_err("failed to retrieve Runtime Api version"));
+		};
+		let ethereum_transactions = |xts: Vec<<B as BlockT>::Extrinsic>| {
+			if api_version > 1 {
+				api.extrinsic_filter(&best_block, xts).map_err(|err| {
+					internal_err(format!("fetch runtime extrinsic filter failed: {:?}", err))
+				})
+			} else {
+				#[allow(deprecated)]
+				let legacy = api.extrinsic_filter_before_version_2(&best_block, xts)
+					.map_err(|err| {
+						internal_err(format!("fetch runtime extrinsic filter failed: {:?}", err))
+					})?;
+				Ok(legacy.into_iter().map(|tx| tx.into()).collect())
+			}
+		};
+
+		Ok(TxPoolResponse {
+			ready: ethereum_transactions(txs_ready)?,
+			future: ethereum_transactions(txs_future)?,
+		})
+	}
+}
+
+impl<B, C, A> TxPoolApiT for TxPool<B, C, A>
+where
+	B: BlockT<Hash = H256> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<B>,
+	A: ChainApi<Block = B> + 'static,
+{
+	fn content(&self) -> Result<TxPoolResult<TransactionMap<Transaction>>> {
+		self.map_build::<Transaction>()
+	}
+
+	fn inspect(&self) -> Result<TxPoolResult<TransactionMap<Summary>>> {
+		self.map_build::<Summary>()
+	}
+
+	fn status(&self) -> Result<TxPoolResult<U256>> {
+		// Count the transactions `content` and `inspect` list.
+		let ethereum_txns = self.tx_pool_response()?;
+		let count = |txns: Vec<EthereumTransaction>| {
+			U256::from(txns.iter().filter(|txn| sender(txn).is_some()).count())
+		};
+		Ok(TxPoolResult {
+			pending: count(ethereum_txns.ready),
+			queued: count(ethereum_txns.future),
+		})
+	}
+}
+
+fn group_by_sender<T>(txns: Vec<EthereumTransaction>) -> TransactionMap<T>
+where
+	T: TxPoolGet + Serialize,
+{
+	let mut map = TransactionMap::<T>::new();
+	for txn in txns.iter() {
+		// Transactions whose sender cannot be recovered are left out rather than listed under a
+		// made-up address.
+		let from_address = match sender(txn) {
+			Some(from_address) => from_address,
+			None => continue,
+		};
+		let hash = txn.hash();
+		let nonce = match txn {
+			EthereumTransaction::Legacy(t) => t.nonce,
+			EthereumTransaction::EIP2930(t) => t.nonce,
+			EthereumTransaction::EIP1559(t) => t.nonce,
+		};
+		map.entry(from_address)
+			.or_insert_with(HashMap::new)
+			.insert(nonce, T::get(hash, from_address, txn));
+	}
+	map
+}
+
+fn sender(txn: &EthereumTransaction) -> Option<H160> {
+	public_key(txn)
+		.ok()
+		.map(|pk| H160::from(H256::from(keccak_256(&pk))))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fc_rpc_core::types::Summary;
+
+	#[test]
+	fn transactions_without_a_sender_are_left_out() {
+		let transaction = EthereumTransaction::EIP2930(ethereum::EIP2930Transaction {
+			chain_id: 42,
+			nonce: U256::zero(),
+			gas_price: U256::one(),
+			gas_limit: U256::from(21000),
+			action: ethereum::TransactionAction::Create,
+			value: U256::zero(),
+			input: Vec::new(),
+			access_list: Vec::new(),
+			odd_y_parity: false,
+			r: H256::zero(),
+			s: H256::zero(),
+		});
+
+		assert_eq!(sender(&transaction), None);
+		assert!(group_by_sender::<Summary>(vec![transaction]).is_empty());
+	}
+}
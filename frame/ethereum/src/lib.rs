@@ -25,6 +25,7 @@
 
 #[cfg(all(feature = "std", test))]
 mod mock;
+mod state_root;
 #[cfg(all(feature = "std", test))]
 mod tests;
 
@@ -61,6 +62,11 @@ pub use ethereum::{
 };
 pub use fp_rpc::TransactionStatus;
 
+pub use self::state_root::{EthereumStateRoot, StateTrieAccountStore, EMPTY_TRIE_ROOT};
+
+/// Maximum number of bytes of revert data kept for a reverted call.
+pub const MAX_REVERT_DATA_LEN: usize = 1024;
+
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
 pub enum RawOrigin {
 	EthereumTransaction(H160),
@@ -208,7 +214,10 @@ pub mod pallet {
 		}
 
 		fn on_initialize(_: T::BlockNumber) -> Weight {
-			let mut weight = T::SystemWeightInfo::kill_storage(1);
+			let mut weight = T::SystemWeightInfo::kill_storage(2);
+			// Revert data is kept until the next block, so that it can be read at the block
+			// that produced it.
+			CurrentRevertData::<T>::kill();
 
 			// If the digest contain an existing ethereum block(encoded as PreLog), If contains,
 			// execute the imported block firstly and disable transact dispatch function.
@@ -223,7 +232,7 @@ pub mod pallet {
 					Self::validate_transaction_in_block(source, &transaction).expect(
 						"pre-block transaction verification failed; the block cannot be built",
 					);
-					let r = Self::apply_validated_transaction(source, transaction);
+					let r = Self::apply_validated_transaction(source, transaction, false);
 					weight = weight.saturating_add(r.actual_weight.unwrap_or(0 as Weight));
 				}
 			}
@@ -253,7 +262,7 @@ pub mod pallet {
 		/// Transact an Ethereum transaction.
 		#[pallet::weight(<T as pallet_evm::Config>::GasWeightMapping::gas_to_weight(
 			Pallet::<T>::transaction_data(transaction).gas_limit.unique_saturated_into()
-		))]
+		).saturating_add(Pallet::<T>::revert_data_weight()))]
 		pub fn transact(
 			origin: OriginFor<T>,
 			transaction: Transaction,
@@ -265,7 +274,34 @@ pub mod pallet {
 				"pre log already exists; block is invalid",
 			);
 
-			Ok(Self::apply_validated_transaction(source, transaction))
+			Ok(Self::apply_validated_transaction(
+				source,
+				transaction,
+				false,
+			))
+		}
+
+		/// Transact an Ethereum transaction on behalf of `source`, ignoring its signature.
+		///
+		/// Development nodes use this to impersonate accounts they hold no key for, including
+		/// contracts, so `source` is exempt from the EIP-3607 check.
+		#[pallet::weight(<T as pallet_evm::Config>::GasWeightMapping::gas_to_weight(
+			Pallet::<T>::transaction_data(transaction).gas_limit.unique_saturated_into()
+		).saturating_add(Pallet::<T>::revert_data_weight()))]
+		pub fn force_transact(
+			origin: OriginFor<T>,
+			source: H160,
+			transaction: Transaction,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ensure!(
+				fp_consensus::find_pre_log(&frame_system::Pallet::<T>::digest()).is_err(),
+				Error::<T>::PreLogExists
+			);
+			Self::validate_in_block(source, &transaction, true)
+				.map_err(|_| Error::<T>::InvalidTransaction)?;
+
+			Ok(Self::apply_validated_transaction(source, transaction, true))
 		}
 	}
 
@@ -282,6 +318,8 @@ pub mod pallet {
 		InvalidSignature,
 		/// Pre-log is present, therefore transact is not allowed.
 		PreLogExists,
+		/// Transaction failed validation for its source account.
+		InvalidTransaction,
 	}
 
 	/// Current building block's transactions and receipts.
@@ -301,17 +339,62 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(super) type CurrentTransactionStatuses<T: Config> = StorageValue<_, Vec<TransactionStatus>>;
 
+	/// Revert data of the calls of the current block that reverted, by transaction index.
+	/// Truncated to `MAX_REVERT_DATA_LEN` bytes.
+	#[pallet::storage]
+	pub(super) type CurrentRevertData<T: Config> = StorageValue<_, Vec<(u32, Vec<u8>)>, ValueQuery>;
+
 	// Mapping for block number and hashes.
 	#[pallet::storage]
 	pub(super) type BlockHash<T: Config> = StorageMap<_, Twox64Concat, U256, H256, ValueQuery>;
 
+	/// Reference count and encoding of the nodes of the tries maintained by
+	/// `EthereumStateRoot`, keyed by node hash.
+	#[pallet::storage]
+	pub(super) type StateTrieNodes<T: Config> = StorageMap<_, Identity, H256, (u32, Vec<u8>)>;
+
+	/// Root of the Ethereum state trie maintained by `EthereumStateRoot`.
+	#[pallet::storage]
+	pub(super) type StateTrieRoot<T: Config> = StorageValue<_, H256>;
+
+	/// Storage trie root of each account in the Ethereum state trie.
+	#[pallet::storage]
+	pub(super) type StateTrieAccounts<T: Config> = StorageMap<_, Blake2_128Concat, H160, H256>;
+
+	/// Accounts touched in the current block, applied to the state trie on finalization, and
+	/// whether their whole storage may have been removed.
+	#[pallet::storage]
+	pub(super) type StateTrieTouchedAccounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, H160, bool>;
+
+	/// Storage slots touched in the current block, applied to the state trie on finalization.
+	#[pallet::storage]
+	pub(super) type StateTrieTouchedStorage<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, H160, Blake2_128Concat, H256, ()>;
+
+	/// Address of the accounts of the state trie, by account id, so that `StateTrieAccountStore`
+	/// can report their balance changes.
+	#[pallet::storage]
+	pub(super) type StateTrieAddresses<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, H160>;
+
+	/// Whether the chain maintains the Ethereum state trie and commits to typed receipts with
+	/// their EIP-2718 envelope. Only set at genesis, since the trie has to hold every account.
+	#[pallet::storage]
+	pub(super) type StateTrieEnabled<T: Config> = StorageValue<_, bool, ValueQuery>;
+
 	#[pallet::genesis_config]
 	#[derive(Default)]
-	pub struct GenesisConfig {}
+	pub struct GenesisConfig {
+		/// Maintain the Ethereum state trie with `EthereumStateRoot`, and commit to typed
+		/// receipts with their EIP-2718 envelope.
+		pub state_trie: bool,
+	}
 
 	#[pallet::genesis_build]
 	impl<T: Config> GenesisBuild<T> for GenesisConfig {
 		fn build(&self) {
+			StateTrieEnabled::<T>::put(self.state_trie);
 			<Pallet<T>>::store_block(false, U256::zero());
 			frame_support::storage::unhashed::put::<EthereumStorageSchema>(
 				&PALLET_ETHEREUM_SCHEMA,
@@ -426,8 +509,17 @@ impl<T: Config> Pallet<T> {
 		}
 
 		let ommers = Vec::<ethereum::Header>::new();
-		let receipts_root =
-			ethereum::util::ordered_trie_root(receipts.iter().map(|r| rlp::encode(r)));
+		// Chains maintaining the state trie commit to typed receipts with their EIP-2718
+		// envelope, as in Ethereum.
+		let receipts_root = if StateTrieEnabled::<T>::get() {
+			ethereum::util::ordered_trie_root(
+				receipts
+					.iter()
+					.map(|r| ethereum::EnvelopedEncodable::encode(r)),
+			)
+		} else {
+			ethereum::util::ordered_trie_root(receipts.iter().map(|r| rlp::encode(r)))
+		};
 		let partial_header = ethereum::PartialHeader {
 			parent_hash: if block_number > U256::zero() {
 				BlockHash::<T>::get(block_number - 1)
@@ -480,6 +572,7 @@ impl<T: Config> Pallet<T> {
 	fn validate_transaction_common(
 		origin: H160,
 		transaction_data: &TransactionData,
+		impersonated: bool,
 	) -> Result<(U256, u64), TransactionValidityError> {
 		let gas_limit = transaction_data.gas_limit;
 
@@ -522,6 +615,17 @@ impl<T: Config> Pallet<T> {
 			.into());
 		}
 
+		// EIP-3607: reject transactions whose sender has deployed code.
+		if <T as pallet_evm::Config>::eip_3607_3541_checks()
+			&& !impersonated
+			&& <pallet_evm::AccountCodes<T>>::decode_len(&origin).unwrap_or(0) != 0
+		{
+			return Err(InvalidTransaction::Custom(
+				TransactionValidationError::TransactionMustComeFromEOA as u8,
+			)
+			.into());
+		}
+
 		let base_fee = T::FeeCalculator::min_gas_price();
 		let mut priority = 0;
 
@@ -575,7 +679,7 @@ impl<T: Config> Pallet<T> {
 		let transaction_nonce = transaction_data.nonce;
 
 		let (account_nonce, priority) =
-			Self::validate_transaction_common(origin, &transaction_data)?;
+			Self::validate_transaction_common(origin, &transaction_data, false)?;
 
 		if transaction_nonce < account_nonce {
 			return Err(InvalidTransaction::Stale.into());
@@ -597,17 +701,21 @@ impl<T: Config> Pallet<T> {
 		builder.build()
 	}
 
-	fn apply_validated_transaction(source: H160, transaction: Transaction) -> PostDispatchInfo {
+	fn apply_validated_transaction(
+		source: H160,
+		transaction: Transaction,
+		impersonated: bool,
+	) -> PostDispatchInfo {
 		let pending = Pending::<T>::get();
 		let transaction_hash = transaction.hash();
 		let transaction_index = pending.len() as u32;
 
-		let (to, _, info) = Self::execute(source, &transaction, None)
+		let (to, _, info) = Self::execute_as(source, &transaction, None, impersonated)
 			.expect("transaction is already validated; error indicates that the block is invalid");
 
-		let (reason, status, used_gas, dest) = match info {
+		let (reason, status, used_gas, dest, revert_data) = match info {
 			CallOrCreateInfo::Call(info) => (
-				info.exit_reason,
+				info.exit_reason.clone(),
 				TransactionStatus {
 					transaction_hash,
 					transaction_index,
@@ -623,6 +731,14 @@ impl<T: Config> Pallet<T> {
 				},
 				info.used_gas,
 				to,
+				match info.exit_reason {
+					ExitReason::Revert(_) => {
+						let mut data = info.value;
+						data.truncate(MAX_REVERT_DATA_LEN);
+						Some(data)
+					}
+					_ => None,
+				},
 			),
 			CallOrCreateInfo::Create(info) => (
 				info.exit_reason,
@@ -641,6 +757,7 @@ impl<T: Config> Pallet<T> {
 				},
 				info.used_gas,
 				Some(info.value),
+				None,
 			),
 		};
 
@@ -683,6 +800,12 @@ impl<T: Config> Pallet<T> {
 		};
 
 		Pending::<T>::append((transaction, status, receipt));
+		let mut actual_weight =
+			T::GasWeightMapping::gas_to_weight(used_gas.unique_saturated_into());
+		if let Some(revert_data) = revert_data {
+			CurrentRevertData::<T>::append((transaction_index, revert_data));
+			actual_weight = actual_weight.saturating_add(Self::revert_data_weight());
+		}
 
 		Self::deposit_event(Event::Executed(
 			source,
@@ -692,18 +815,27 @@ impl<T: Config> Pallet<T> {
 		));
 
 		PostDispatchInfo {
-			actual_weight: Some(T::GasWeightMapping::gas_to_weight(
-				used_gas.unique_saturated_into(),
-			)),
+			actual_weight: Some(actual_weight),
 			pays_fee: Pays::No,
 		}
 	}
 
+	/// Weight of appending the revert data of a reverted call to `CurrentRevertData`. The
+	/// data is truncated to `MAX_REVERT_DATA_LEN` bytes, so a single write bounds it.
+	pub fn revert_data_weight() -> Weight {
+		T::DbWeight::get().writes(1)
+	}
+
 	/// Get the transaction status with given index.
 	pub fn current_transaction_statuses() -> Option<Vec<TransactionStatus>> {
 		CurrentTransactionStatuses::<T>::get()
 	}
 
+	/// Get the revert data of the reverted calls of the current block, by transaction index.
+	pub fn current_revert_data() -> Vec<(u32, Vec<u8>)> {
+		CurrentRevertData::<T>::get()
+	}
+
 	/// Get current block.
 	pub fn current_block() -> Option<ethereum::BlockV2> {
 		CurrentBlock::<T>::get()
@@ -724,6 +856,17 @@ impl<T: Config> Pallet<T> {
 		from: H160,
 		transaction: &Transaction,
 		config: Option<evm::Config>,
+	) -> Result<(Option<H160>, Option<H160>, CallOrCreateInfo), DispatchError> {
+		Self::execute_as(from, transaction, config, false)
+	}
+
+	/// Execute an Ethereum transaction, impersonating `from` when `impersonated` is set.
+	/// The runner skips EIP-3607 for impersonated transactions.
+	fn execute_as(
+		from: H160,
+		transaction: &Transaction,
+		config: Option<evm::Config>,
+		impersonated: bool,
 	) -> Result<(Option<H160>, Option<H160>, CallOrCreateInfo), DispatchError> {
 		let (
 			input,
@@ -797,6 +940,8 @@ impl<T: Config> Pallet<T> {
 					max_priority_fee_per_gas,
 					nonce,
 					access_list,
+					true,
+					impersonated,
 					config.as_ref().unwrap_or(T::config()),
 				)
 				.map_err(Into::into)?;
@@ -813,6 +958,8 @@ impl<T: Config> Pallet<T> {
 					max_priority_fee_per_gas,
 					nonce,
 					access_list,
+					true,
+					impersonated,
 					config.as_ref().unwrap_or(T::config()),
 				)
 				.map_err(Into::into)?;
@@ -829,10 +976,19 @@ impl<T: Config> Pallet<T> {
 	pub fn validate_transaction_in_block(
 		origin: H160,
 		transaction: &Transaction,
+	) -> Result<(), TransactionValidityError> {
+		Self::validate_in_block(origin, transaction, false)
+	}
+
+	fn validate_in_block(
+		origin: H160,
+		transaction: &Transaction,
+		impersonated: bool,
 	) -> Result<(), TransactionValidityError> {
 		let transaction_data = Pallet::<T>::transaction_data(&transaction);
 		let transaction_nonce = transaction_data.nonce;
-		let (account_nonce, _) = Self::validate_transaction_common(origin, &transaction_data)?;
+		let (account_nonce, _) =
+			Self::validate_transaction_common(origin, &transaction_data, impersonated)?;
 
 		// In the context of the block, a transaction with a nonce that is
 		// too high should be considered invalid and make the whole block invalid.
@@ -941,4 +1097,5 @@ enum TransactionValidationError {
 	InvalidChainId,
 	InvalidSignature,
 	InvalidGasLimit,
+	TransactionMustComeFromEOA,
 }
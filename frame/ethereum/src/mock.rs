@@ -268,6 +268,134 @@ pub fn new_test_ext(accounts_len: usize) -> (Vec<AccountInfo>, sp_io::TestExtern
 	(pairs, ext.into())
 }
 
+/// Runtime committing to the Ethereum state trie with `EthereumStateRoot`.
+pub mod state_trie {
+	use super::{
+		address_build, AccountInfo, BlockGasLimit, BlockHashCount, ChainId, ExistentialDeposit,
+		FindAuthorTruncated, FixedGasPrice, HashedAddressMapping, MaxLocks, MinimumPeriod,
+	};
+	use crate::{EthereumStateRoot, StateTrieAccountStore};
+	use frame_support::traits::ConstU32;
+	use pallet_evm::EnsureAddressTruncated;
+	use sp_core::H256;
+	use sp_runtime::{
+		testing::Header,
+		traits::{BlakeTwo256, IdentityLookup},
+		AccountId32,
+	};
+
+	type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	frame_support::construct_runtime! {
+		pub enum Test where
+			Block = Block,
+			NodeBlock = Block,
+			UncheckedExtrinsic = UncheckedExtrinsic,
+		{
+			System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+			Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+			Timestamp: pallet_timestamp::{Pallet, Call, Storage},
+			EVM: pallet_evm::{Pallet, Call, Storage, Config, Event<T>},
+			Ethereum: crate::{Pallet, Call, Storage, Event, Origin},
+		}
+	}
+
+	impl frame_system::Config for Test {
+		type BaseCallFilter = frame_support::traits::Everything;
+		type BlockWeights = ();
+		type BlockLength = ();
+		type DbWeight = ();
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = AccountId32;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = Event;
+		type BlockHashCount = BlockHashCount;
+		type Version = ();
+		type PalletInfo = PalletInfo;
+		type AccountData = pallet_balances::AccountData<u64>;
+		type OnNewAccount = ();
+		type OnKilledAccount = ();
+		type SystemWeightInfo = ();
+		type SS58Prefix = ();
+		type OnSetCode = ();
+		type MaxConsumers = ConstU32<16>;
+	}
+
+	impl pallet_balances::Config for Test {
+		type MaxLocks = MaxLocks;
+		type Balance = u64;
+		type Event = Event;
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type AccountStore = StateTrieAccountStore<Self, System>;
+		type WeightInfo = ();
+		type MaxReserves = ();
+		type ReserveIdentifier = ();
+	}
+
+	impl pallet_timestamp::Config for Test {
+		type Moment = u64;
+		type OnTimestampSet = ();
+		type MinimumPeriod = MinimumPeriod;
+		type WeightInfo = ();
+	}
+
+	impl pallet_evm::Config for Test {
+		type FeeCalculator = FixedGasPrice;
+		type GasWeightMapping = ();
+		type CallOrigin = EnsureAddressTruncated;
+		type WithdrawOrigin = EnsureAddressTruncated;
+		type AddressMapping = HashedAddressMapping;
+		type Currency = Balances;
+		type Event = Event;
+		type PrecompilesType = ();
+		type PrecompilesValue = ();
+		type Runner = pallet_evm::runner::stack::Runner<Self>;
+		type ChainId = ChainId;
+		type BlockGasLimit = BlockGasLimit;
+		type OnChargeTransaction = ();
+		type FindAuthor = FindAuthorTruncated;
+		type BlockHashMapping = crate::EthereumBlockHashMapping<Self>;
+
+		fn on_state_touched(touched: &pallet_evm::TouchedState) {
+			EthereumStateRoot::<Self>::touch(touched)
+		}
+	}
+
+	impl crate::Config for Test {
+		type Event = Event;
+		type StateRoot = EthereumStateRoot<Self>;
+	}
+
+	pub fn new_test_ext(accounts_len: usize) -> (Vec<AccountInfo>, sp_io::TestExternalities) {
+		let mut ext = frame_system::GenesisConfig::default()
+			.build_storage::<Test>()
+			.unwrap();
+
+		let pairs = (0..accounts_len)
+			.map(|i| address_build(i as u8))
+			.collect::<Vec<_>>();
+
+		let balances: Vec<_> = pairs
+			.iter()
+			.map(|pair| (pair.account_id.clone(), 10_000_000))
+			.collect();
+
+		pallet_balances::GenesisConfig::<Test> { balances }
+			.assimilate_storage(&mut ext)
+			.unwrap();
+
+		(pairs, ext.into())
+	}
+}
+
 pub fn contract_address(sender: H160, nonce: u64) -> H160 {
 	let mut rlp = RlpStream::new_list(2);
 	rlp.append(&sender);
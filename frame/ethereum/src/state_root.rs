@@ -0,0 +1,680 @@
+// SPDX-License-Identifier: Apache-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2020-2022 Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum-compatible state root.
+//!
+//! [`EthereumStateRoot`] maintains the secure Merkle-Patricia trie of EVM accounts that
+//! Ethereum commits to in the `stateRoot` header field. The trie nodes are kept in the
+//! pallet storage and only the accounts touched in a block are updated when it is finalized.
+//!
+//! Accounts are added to the trie when the EVM pallet reports them through
+//! `pallet_evm::Config::on_state_touched`. Balance changes made outside the EVM, such as
+//! Substrate transfers, fees and rewards, are reported by [`StateTrieAccountStore`] for the
+//! accounts already in the trie. Accounts the EVM never touched are not in the trie, since
+//! their address cannot be derived from their account id. Changes made after this pallet is
+//! finalized are applied in the next block.
+//! [`EthereumStateRoot::recompute`] rebuilds the root from scratch to check the maintained one.
+//!
+//! The trie is only maintained on chains enabling it at genesis, through
+//! `GenesisConfig::state_trie`, since the accounts of an existing chain cannot all be listed to
+//! seed it. Other chains keep committing to [`IntermediateStateRoot`].
+
+use crate::{
+	Config, IntermediateStateRoot, StateTrieAccounts, StateTrieAddresses, StateTrieEnabled,
+	StateTrieNodes, StateTrieRoot, StateTrieTouchedAccounts, StateTrieTouchedStorage,
+};
+use ethereum_types::{H160, H256, U256};
+use frame_support::{
+	traits::{Get, StoredMap},
+	weights::{DispatchClass, Weight},
+};
+use pallet_evm::{AccountCodes, AccountStorages, AddressMapping, TouchedState};
+use rlp::{DecoderError, Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+use sp_runtime::{traits::Zero, DispatchError};
+use sp_std::{
+	boxed::Box,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	marker::PhantomData,
+	prelude::*,
+};
+
+/// Root of the empty trie, `keccak256(rlp(""))`.
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+	0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+	0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Trie nodes read and written to update one entry of a trie, enough for tries of up to 16^8
+/// entries. Each entry also costs the reads of the account or slot, the removal of its touched
+/// entry and the writes of its trie root and address.
+const TRIE_UPDATE_DEPTH: u64 = 8;
+
+/// `StateRoot` implementation committing to the EVM accounts like Ethereum does, on chains
+/// enabling it at genesis.
+///
+/// To use it, set `type StateRoot = EthereumStateRoot<Runtime>` and forward
+/// `pallet_evm::Config::on_state_touched` to [`EthereumStateRoot::touch`].
+pub struct EthereumStateRoot<T>(PhantomData<T>);
+
+impl<T: Config> EthereumStateRoot<T> {
+	/// Whether the chain maintains the state trie.
+	pub fn is_enabled() -> bool {
+		StateTrieEnabled::<T>::get()
+	}
+
+	/// Record EVM state modified in the current block, and charge the weight of applying it
+	/// to the trie when the block is finalized. Each account and storage slot is recorded under
+	/// its own key, and charged once per block.
+	///
+	/// State touched by the genesis build is recorded before the pallet genesis enables the
+	/// trie, and applied in the first block.
+	pub fn touch(touched: &TouchedState) {
+		if touched.is_empty()
+			|| !(Self::is_enabled() || frame_system::Pallet::<T>::block_number().is_zero())
+		{
+			return;
+		}
+
+		let mut added = 0u64;
+		for address in &touched.accounts {
+			let reset_storage = touched.reset_storage.contains(address);
+			StateTrieTouchedAccounts::<T>::mutate(address, |entry| {
+				if entry.is_none() {
+					added += 1;
+				}
+				*entry = Some(entry.unwrap_or_default() || reset_storage);
+			});
+		}
+		for (address, index) in &touched.storage {
+			if !StateTrieTouchedStorage::<T>::contains_key(address, index) {
+				StateTrieTouchedStorage::<T>::insert(address, index, ());
+				added += 1;
+			}
+		}
+
+		let entries = (touched.accounts.len() + touched.storage.len()) as u64;
+		frame_system::Pallet::<T>::register_extra_weight_unchecked(
+			T::DbWeight::get()
+				.reads_writes(entries, entries)
+				.saturating_add(Self::update_weight(added)),
+			DispatchClass::Mandatory,
+		);
+	}
+
+	/// Weight of applying `entries` touched accounts and storage slots to the trie.
+	pub fn update_weight(entries: u64) -> Weight {
+		T::DbWeight::get()
+			.reads_writes(TRIE_UPDATE_DEPTH + 3, TRIE_UPDATE_DEPTH + 3)
+			.saturating_mul(entries)
+	}
+
+	/// The maintained state root, as of the last finalized block.
+	pub fn root() -> H256 {
+		StateTrieRoot::<T>::get().unwrap_or(EMPTY_TRIE_ROOT)
+	}
+
+	/// Recompute the state root from scratch from the current state of the accounts in the
+	/// maintained trie and of every contract.
+	pub fn recompute() -> H256 {
+		let mut addresses: BTreeSet<H160> = StateTrieAccounts::<T>::iter_keys().collect();
+		addresses.extend(AccountCodes::<T>::iter_keys());
+
+		trie_root(addresses.into_iter().filter_map(|address| {
+			let storage_root = trie_root(
+				AccountStorages::<T>::iter_prefix(address)
+					.map(|(index, value)| (keccak(index), storage_value(value))),
+			);
+			account_leaf::<T>(address, storage_root).map(|leaf| (keccak(address), leaf))
+		}))
+	}
+}
+
+impl<T: Config> Get<H256> for EthereumStateRoot<T> {
+	fn get() -> H256 {
+		if !Self::is_enabled() {
+			let _ = StateTrieTouchedAccounts::<T>::remove_all(None);
+			let _ = StateTrieTouchedStorage::<T>::remove_all(None);
+			return IntermediateStateRoot::<T>::get();
+		}
+
+		let mut touched = TouchedState::default();
+		for (address, reset_storage) in StateTrieTouchedAccounts::<T>::drain() {
+			if reset_storage {
+				touched.reset_storage(address);
+			} else {
+				touched.touch_account(address);
+			}
+		}
+		for (address, index, ()) in StateTrieTouchedStorage::<T>::drain() {
+			touched.storage.insert((address, index));
+		}
+		if touched.is_empty() {
+			return Self::root();
+		}
+
+		let mut storage_changes = BTreeMap::<H160, Vec<_>>::new();
+		for (address, index) in touched.storage {
+			if touched.reset_storage.contains(&address) {
+				continue;
+			}
+			let value = AccountStorages::<T>::get(address, index);
+			storage_changes.entry(address).or_default().push((
+				keccak(index),
+				Some(value).filter(|v| !v.is_zero()).map(storage_value),
+			));
+		}
+
+		let mut account_changes = Vec::new();
+		for address in touched.accounts {
+			let old_storage_root = StateTrieAccounts::<T>::get(address).unwrap_or(EMPTY_TRIE_ROOT);
+			let storage_root = if touched.reset_storage.contains(&address) {
+				let storage_root = NodeStore::<T>::update(
+					EMPTY_TRIE_ROOT,
+					AccountStorages::<T>::iter_prefix(address)
+						.map(|(index, value)| (keccak(index), Some(storage_value(value))))
+						.collect(),
+				);
+				NodeStore::<T>::release(old_storage_root);
+				storage_root
+			} else if let Some(changes) = storage_changes.remove(&address) {
+				NodeStore::<T>::update(old_storage_root, changes)
+			} else {
+				old_storage_root
+			};
+
+			// Kept once the account is emptied, so that a later deposit is still reported.
+			StateTrieAddresses::<T>::insert(
+				<T as pallet_evm::Config>::AddressMapping::into_account_id(address),
+				address,
+			);
+			let leaf = account_leaf::<T>(address, storage_root);
+			if leaf.is_some() {
+				StateTrieAccounts::<T>::insert(address, storage_root);
+			} else {
+				StateTrieAccounts::<T>::remove(address);
+			}
+			account_changes.push((keccak(address), leaf));
+		}
+
+		let root = NodeStore::<T>::update(Self::root(), account_changes);
+		StateTrieRoot::<T>::put(root);
+		root
+	}
+}
+
+/// `pallet_balances::Config::AccountStore` reporting the balance changes of the accounts of the
+/// state trie to [`EthereumStateRoot`], so that transfers, fees and rewards made outside the
+/// EVM are committed to. Wraps the store `S`, usually `frame_system::Pallet`.
+pub struct StateTrieAccountStore<T, S>(PhantomData<(T, S)>);
+
+impl<T, S, D> StoredMap<T::AccountId, D> for StateTrieAccountStore<T, S>
+where
+	T: Config,
+	S: StoredMap<T::AccountId, D>,
+	D: Default,
+{
+	fn get(who: &T::AccountId) -> D {
+		S::get(who)
+	}
+
+	fn try_mutate_exists<R, E: From<DispatchError>>(
+		who: &T::AccountId,
+		f: impl FnOnce(&mut Option<D>) -> Result<R, E>,
+	) -> Result<R, E> {
+		let result = S::try_mutate_exists(who, f)?;
+		if EthereumStateRoot::<T>::is_enabled() {
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::DbWeight::get().reads(1),
+				DispatchClass::Mandatory,
+			);
+			if let Some(address) = StateTrieAddresses::<T>::get(who) {
+				let mut touched = TouchedState::default();
+				touched.touch_account(address);
+				EthereumStateRoot::<T>::touch(&touched);
+			}
+		}
+		Ok(result)
+	}
+}
+
+/// The RLP-encoded account, or `None` for an empty account (EIP-161).
+fn account_leaf<T: Config>(address: H160, storage_root: H256) -> Option<Vec<u8>> {
+	let account = pallet_evm::Pallet::<T>::account_basic(&address);
+	let code = AccountCodes::<T>::get(address);
+	if account.nonce.is_zero()
+		&& account.balance.is_zero()
+		&& code.is_empty()
+		&& storage_root == EMPTY_TRIE_ROOT
+	{
+		return None;
+	}
+
+	let mut stream = RlpStream::new_list(4);
+	stream.append(&account.nonce);
+	stream.append(&account.balance);
+	stream.append(&storage_root);
+	stream.append(&keccak(&code));
+	Some(stream.out().to_vec())
+}
+
+fn storage_value(value: H256) -> Vec<u8> {
+	rlp::encode(&U256::from_big_endian(value.as_bytes())).to_vec()
+}
+
+fn keccak(data: impl AsRef<[u8]>) -> H256 {
+	H256::from_slice(Keccak256::digest(data.as_ref()).as_slice())
+}
+
+/// Root of the secure trie holding `entries`, computed in memory.
+fn trie_root(entries: impl Iterator<Item = (H256, Vec<u8>)>) -> H256 {
+	let mut root = None;
+	for (key, value) in entries {
+		root = Some(insert::<Memory>(root, &nibbles(&key), value));
+	}
+	root.map_or(EMPTY_TRIE_ROOT, |node| {
+		keccak(encode_node::<Memory>(node).0)
+	})
+}
+
+enum Node {
+	/// Remaining key nibbles and value.
+	Leaf(Vec<u8>, Vec<u8>),
+	/// Key nibbles shared by every entry below, and the branch holding them.
+	Extension(Vec<u8>, Child),
+	/// One child per nibble. Keys of secure tries have a fixed length, so branches never
+	/// hold a value.
+	Branch(Box<[Option<Child>; 16]>),
+}
+
+enum Child {
+	/// Node in the node store.
+	Hash(H256),
+	/// Node held in memory, either modified or inlined in its parent.
+	Node(Box<Node>),
+}
+
+/// How a parent refers to a node: by hash, or by its encoding if shorter than 32 bytes.
+enum NodeRef {
+	Hash(H256),
+	Inline(Vec<u8>),
+}
+
+trait Backend {
+	/// Bring `child` in memory.
+	fn load(child: Child) -> Node;
+	/// Encode `node` and its in-memory descendants, returning the reference to it.
+	fn commit(node: Node) -> NodeRef;
+}
+
+/// Backend of the tries computed from scratch, which only hold in-memory nodes.
+struct Memory;
+
+impl Backend for Memory {
+	fn load(child: Child) -> Node {
+		match child {
+			Child::Node(node) => *node,
+			Child::Hash(_) => unreachable!("in-memory tries only hold in-memory nodes; qed"),
+		}
+	}
+
+	fn commit(node: Node) -> NodeRef {
+		let (encoded, _) = encode_node::<Memory>(node);
+		if encoded.len() < 32 {
+			NodeRef::Inline(encoded)
+		} else {
+			NodeRef::Hash(keccak(encoded))
+		}
+	}
+}
+
+/// Backend of the maintained tries. Nodes are shared between tries and counted once per
+/// stored parent node or root referring to them.
+struct NodeStore<T>(PhantomData<T>);
+
+impl<T: Config> Backend for NodeStore<T> {
+	fn load(child: Child) -> Node {
+		match child {
+			Child::Node(node) => *node,
+			Child::Hash(hash) => {
+				let (_, encoded) = StateTrieNodes::<T>::get(hash)
+					.expect("nodes are kept while they are referenced; qed");
+				decode_node(&encoded).expect("nodes are encoded by this module; qed")
+			}
+		}
+	}
+
+	fn commit(node: Node) -> NodeRef {
+		let (encoded, children) = encode_node::<Self>(node);
+		if encoded.len() < 32 {
+			NodeRef::Inline(encoded)
+		} else {
+			NodeRef::Hash(Self::store(encoded, &children))
+		}
+	}
+}
+
+impl<T: Config> NodeStore<T> {
+	/// Apply `changes` to the trie at `root`, removing the entries set to `None`, and return
+	/// the new root. The reference held on `root` moves to the new root.
+	fn update(root: H256, changes: Vec<(H256, Option<Vec<u8>>)>) -> H256 {
+		let mut node = (root != EMPTY_TRIE_ROOT).then(|| Self::load(Child::Hash(root)));
+		for (key, value) in changes {
+			let path = nibbles(&key);
+			node = match value {
+				Some(value) => Some(insert::<Self>(node, &path, value)),
+				None => node.and_then(|node| remove::<Self>(node, &path)),
+			};
+		}
+
+		let new_root = match node {
+			Some(node) => {
+				// The root is stored even when short enough to be inlined.
+				let (encoded, children) = encode_node::<Self>(node);
+				let hash = Self::store(encoded, &children);
+				Self::retain(hash);
+				hash
+			}
+			None => EMPTY_TRIE_ROOT,
+		};
+		Self::release(root);
+		new_root
+	}
+
+	/// Store a node if it is new, adding a reference to each of its stored children. The
+	/// node itself is unreferenced until its parent or root is stored.
+	fn store(encoded: Vec<u8>, children: &[H256]) -> H256 {
+		let hash = keccak(&encoded);
+		if !StateTrieNodes::<T>::contains_key(hash) {
+			for child in children {
+				Self::retain(*child);
+			}
+			StateTrieNodes::<T>::insert(hash, (0, encoded));
+		}
+		hash
+	}
+
+	fn retain(hash: H256) {
+		StateTrieNodes::<T>::mutate(hash, |node| {
+			if let Some((references, _)) = node {
+				*references += 1;
+			}
+		});
+	}
+
+	/// Drop a reference to a node, removing it and releasing its children once unreferenced.
+	fn release(hash: H256) {
+		if hash == EMPTY_TRIE_ROOT {
+			return;
+		}
+		let (references, encoded) = match StateTrieNodes::<T>::get(hash) {
+			Some(node) => node,
+			None => return,
+		};
+		if references > 1 {
+			StateTrieNodes::<T>::insert(hash, (references - 1, encoded));
+			return;
+		}
+
+		StateTrieNodes::<T>::remove(hash);
+		if let Ok(node) = decode_node(&encoded) {
+			Self::release_children(node);
+		}
+	}
+
+	fn release_children(node: Node) {
+		let children: Vec<Child> = match node {
+			Node::Leaf(..) => Vec::new(),
+			Node::Extension(_, child) => sp_std::vec![child],
+			Node::Branch(children) => IntoIterator::into_iter(*children).flatten().collect(),
+		};
+		for child in children {
+			match child {
+				Child::Hash(hash) => Self::release(hash),
+				Child::Node(node) => Self::release_children(*node),
+			}
+		}
+	}
+}
+
+fn insert<B: Backend>(node: Option<Node>, path: &[u8], value: Vec<u8>) -> Node {
+	let node = match node {
+		Some(node) => node,
+		None => return Node::Leaf(path.to_vec(), value),
+	};
+
+	match node {
+		Node::Leaf(key, _) if key == path => Node::Leaf(key, value),
+		Node::Leaf(key, old_value) => {
+			let common = common_prefix(&key, path);
+			let mut children = Box::<[Option<Child>; 16]>::default();
+			children[key[common] as usize] =
+				Some(in_memory(Node::Leaf(key[common + 1..].to_vec(), old_value)));
+			children[path[common] as usize] =
+				Some(in_memory(Node::Leaf(path[common + 1..].to_vec(), value)));
+			with_prefix(&path[..common], Node::Branch(children))
+		}
+		Node::Extension(key, child) => {
+			let common = common_prefix(&key, path);
+			if common == key.len() {
+				let child = insert::<B>(Some(B::load(child)), &path[common..], value);
+				return Node::Extension(key, in_memory(child));
+			}
+
+			let mut children = Box::<[Option<Child>; 16]>::default();
+			children[key[common] as usize] = Some(if key.len() == common + 1 {
+				child
+			} else {
+				in_memory(Node::Extension(key[common + 1..].to_vec(), child))
+			});
+			children[path[common] as usize] =
+				Some(in_memory(Node::Leaf(path[common + 1..].to_vec(), value)));
+			with_prefix(&path[..common], Node::Branch(children))
+		}
+		Node::Branch(mut children) => {
+			let index = path[0] as usize;
+			let child = children[index].take().map(B::load);
+			children[index] = Some(in_memory(insert::<B>(child, &path[1..], value)));
+			Node::Branch(children)
+		}
+	}
+}
+
+fn remove<B: Backend>(node: Node, path: &[u8]) -> Option<Node> {
+	match node {
+		Node::Leaf(key, _) if key == path => None,
+		Node::Leaf(key, value) => Some(Node::Leaf(key, value)),
+		Node::Extension(key, child) => {
+			if !path.starts_with(&key) {
+				return Some(Node::Extension(key, child));
+			}
+			let child = remove::<B>(B::load(child), &path[key.len()..])?;
+			Some(join(key, child))
+		}
+		Node::Branch(mut children) => {
+			let index = path[0] as usize;
+			let child = match children[index].take() {
+				Some(child) => child,
+				None => return Some(Node::Branch(children)),
+			};
+			children[index] = remove::<B>(B::load(child), &path[1..]).map(in_memory);
+
+			let remaining: Vec<usize> = (0..16).filter(|i| children[*i].is_some()).collect();
+			match remaining[..] {
+				[] => None,
+				// A branch with a single child collapses into its parent path.
+				[only] => {
+					let child = children[only].take().expect("child is present; qed");
+					Some(join(sp_std::vec![only as u8], B::load(child)))
+				}
+				_ => Some(Node::Branch(children)),
+			}
+		}
+	}
+}
+
+/// `node` with `prefix` prepended to its path.
+fn join(mut prefix: Vec<u8>, node: Node) -> Node {
+	match node {
+		Node::Leaf(key, value) => {
+			prefix.extend(key);
+			Node::Leaf(prefix, value)
+		}
+		Node::Extension(key, child) => {
+			prefix.extend(key);
+			Node::Extension(prefix, child)
+		}
+		branch @ Node::Branch(_) => Node::Extension(prefix, in_memory(branch)),
+	}
+}
+
+fn with_prefix(prefix: &[u8], node: Node) -> Node {
+	if prefix.is_empty() {
+		node
+	} else {
+		Node::Extension(prefix.to_vec(), in_memory(node))
+	}
+}
+
+fn in_memory(node: Node) -> Child {
+	Child::Node(Box::new(node))
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+	a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn nibbles(key: &H256) -> Vec<u8> {
+	key.as_bytes()
+		.iter()
+		.flat_map(|byte| [byte >> 4, byte & 0x0f])
+		.collect()
+}
+
+/// Encode `node`, committing its in-memory children, and return the encoding along with
+/// the hashes of the children it refers to.
+fn encode_node<B: Backend>(node: Node) -> (Vec<u8>, Vec<H256>) {
+	let mut children = Vec::new();
+	let mut stream = RlpStream::new();
+	match node {
+		Node::Leaf(key, value) => {
+			stream.begin_list(2);
+			stream.append(&hex_prefix(&key, true));
+			stream.append(&value);
+		}
+		Node::Extension(key, child) => {
+			stream.begin_list(2);
+			stream.append(&hex_prefix(&key, false));
+			append_child::<B>(&mut stream, child, &mut children);
+		}
+		Node::Branch(branch) => {
+			stream.begin_list(17);
+			for child in *branch {
+				match child {
+					Some(child) => append_child::<B>(&mut stream, child, &mut children),
+					None => {
+						stream.append_empty_data();
+					}
+				}
+			}
+			stream.append_empty_data();
+		}
+	}
+	(stream.out().to_vec(), children)
+}
+
+fn append_child<B: Backend>(stream: &mut RlpStream, child: Child, children: &mut Vec<H256>) {
+	let reference = match child {
+		Child::Hash(hash) => NodeRef::Hash(hash),
+		Child::Node(node) => B::commit(*node),
+	};
+	match reference {
+		NodeRef::Hash(hash) => {
+			stream.append(&hash);
+			children.push(hash);
+		}
+		NodeRef::Inline(encoded) => {
+			stream.append_raw(&encoded, 1);
+		}
+	}
+}
+
+fn decode_node(encoded: &[u8]) -> Result<Node, DecoderError> {
+	let rlp = Rlp::new(encoded);
+	match rlp.item_count()? {
+		17 => {
+			let mut children = Box::<[Option<Child>; 16]>::default();
+			for (i, child) in children.iter_mut().enumerate() {
+				*child = decode_child(rlp.at(i)?)?;
+			}
+			Ok(Node::Branch(children))
+		}
+		2 => {
+			let (key, leaf) = decode_hex_prefix(rlp.at(0)?.data()?)?;
+			if leaf {
+				Ok(Node::Leaf(key, rlp.at(1)?.data()?.to_vec()))
+			} else {
+				let child = decode_child(rlp.at(1)?)?.ok_or(DecoderError::RlpIsTooShort)?;
+				Ok(Node::Extension(key, child))
+			}
+		}
+		_ => Err(DecoderError::RlpIncorrectListLen),
+	}
+}
+
+fn decode_child(rlp: Rlp) -> Result<Option<Child>, DecoderError> {
+	if rlp.is_empty() {
+		Ok(None)
+	} else if rlp.is_list() {
+		Ok(Some(in_memory(decode_node(rlp.as_raw())?)))
+	} else {
+		let data = rlp.data()?;
+		if data.len() != 32 {
+			return Err(DecoderError::RlpInvalidLength);
+		}
+		Ok(Some(Child::Hash(H256::from_slice(data))))
+	}
+}
+
+/// Compact encoding of key nibbles, flagging odd lengths and leaves.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+	let flag = if leaf { 2 } else { 0 };
+	let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+	let rest = if nibbles.len() % 2 == 1 {
+		encoded.push(((flag + 1) << 4) | nibbles[0]);
+		&nibbles[1..]
+	} else {
+		encoded.push(flag << 4);
+		nibbles
+	};
+	encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+	encoded
+}
+
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecoderError> {
+	let first = *encoded.first().ok_or(DecoderError::RlpIsTooShort)?;
+	let flag = first >> 4;
+	let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+	if flag & 1 == 1 {
+		nibbles.push(first & 0x0f);
+	}
+	for byte in &encoded[1..] {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+	Ok((nibbles, flag & 2 == 2))
+}
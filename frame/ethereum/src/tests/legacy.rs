@@ -313,3 +313,273 @@ fn call_should_handle_errors() {
 		Ethereum::execute(alice.address, &t3, None).ok().unwrap();
 	});
 }
+
+#[test]
+fn reverted_call_should_store_revert_data() {
+	use frame_support::traits::OnInitialize;
+
+	// The `Test` contract of `call_should_handle_errors`.
+	let contract: &str = "608060405234801561001057600080fd5b50610113806100206000396000f3fe6080604052348015600f57600080fd5b506004361060325760003560e01c8063c2985578146037578063febb0f7e146057575b600080fd5b603d605f565b604051808215151515815260200191505060405180910390f35b605d6068565b005b60006001905090565b600060db576040517f08c379a00000000000000000000000000000000000000000000000000000000081526004018080602001828103825260098152602001807f6572726f725f6d7367000000000000000000000000000000000000000000000081525060200191505060405180910390fd5b56fea2646970667358221220fde68a3968e0e99b16fabf9b2997a78218b32214031f8e07e2c502daf603a69e64736f6c63430006060033";
+
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		let create = LegacyUnsignedTransaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(1),
+			gas_limit: U256::from(0x100000),
+			action: ethereum::TransactionAction::Create,
+			value: U256::zero(),
+			input: FromHex::from_hex(contract).unwrap(),
+		}
+		.sign(&alice.private_key);
+		assert_ok!(Ethereum::transact(
+			RawOrigin::EthereumTransaction(alice.address).into(),
+			create,
+		));
+
+		let bar = LegacyUnsignedTransaction {
+			nonce: U256::from(1),
+			gas_price: U256::from(1),
+			gas_limit: U256::from(0x100000),
+			action: TransactionAction::Call(contract_address(alice.address, 0)),
+			value: U256::zero(),
+			input: FromHex::from_hex("febb0f7e").unwrap(),
+		}
+		.sign(&alice.private_key);
+		assert_ok!(Ethereum::transact(
+			RawOrigin::EthereumTransaction(alice.address).into(),
+			bar,
+		));
+
+		let revert_data = Ethereum::current_revert_data();
+		assert_eq!(revert_data.len(), 1);
+		assert_eq!(revert_data[0].0, 1);
+		// `Error(string)` with the "error_msg" reason.
+		assert_eq!(&revert_data[0].1[..4], &[0x08, 0xc3, 0x79, 0xa0]);
+		assert_eq!(&revert_data[0].1[68..77], b"error_msg");
+
+		// Cleared when the next block starts.
+		Ethereum::on_initialize(2);
+		assert!(Ethereum::current_revert_data().is_empty());
+	});
+}
+
+#[test]
+fn transaction_from_contract_should_not_work() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		pallet_evm::AccountCodes::<Test>::insert(alice.address, vec![0x00]);
+
+		let call = crate::Call::<Test>::transact {
+			transaction: legacy_erc20_creation_transaction(alice),
+		};
+		let source = call.check_self_contained().unwrap().unwrap();
+
+		assert_err!(
+			call.validate_self_contained(&source).unwrap(),
+			InvalidTransaction::Custom(
+				crate::TransactionValidationError::TransactionMustComeFromEOA as u8
+			)
+		);
+	});
+}
+
+#[test]
+fn transaction_from_contract_should_fail_in_block() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		pallet_evm::AccountCodes::<Test>::insert(alice.address, vec![0x00]);
+
+		let call = crate::Call::<Test>::transact {
+			transaction: legacy_erc20_creation_transaction(alice),
+		};
+		let source = call.check_self_contained().unwrap().unwrap();
+		let extrinsic = fp_self_contained::CheckedExtrinsic::<_, _, SignedExtra, _> {
+			signed: fp_self_contained::CheckedSignature::SelfContained(source),
+			function: Call::Ethereum(call),
+		};
+		use frame_support::weights::GetDispatchInfo as _;
+		let dispatch_info = extrinsic.get_dispatch_info();
+		assert_err!(
+			extrinsic.apply::<Test>(&dispatch_info, 0),
+			TransactionValidityError::Invalid(InvalidTransaction::Custom(
+				crate::TransactionValidationError::TransactionMustComeFromEOA as u8,
+			))
+		);
+	});
+}
+
+#[test]
+fn contract_creation_from_contract_should_not_be_executed() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		pallet_evm::AccountCodes::<Test>::insert(alice.address, vec![0x00]);
+
+		let t = legacy_erc20_creation_transaction(alice);
+		assert_err!(
+			Ethereum::execute(alice.address, &t, None),
+			pallet_evm::Error::<Test>::TransactionMustComeFromEOA
+		);
+		assert_eq!(EVM::account_basic(&alice.address).nonce, U256::zero());
+	});
+}
+
+#[test]
+fn contract_code_starting_with_ef_should_not_be_deployed() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		// PUSH1 0xEF PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+		let t = LegacyUnsignedTransaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(1),
+			gas_limit: U256::from(0x100000),
+			action: ethereum::TransactionAction::Create,
+			value: U256::zero(),
+			input: FromHex::from_hex("60ef60005360016000f3").unwrap(),
+		}
+		.sign(&alice.private_key);
+
+		let (_, _, info) = Ethereum::execute(alice.address, &t, None).unwrap();
+
+		match info {
+			CallOrCreateInfo::Create(info) => {
+				assert!(matches!(info.exit_reason, evm::ExitReason::Error(_)));
+			}
+			CallOrCreateInfo::Call(_) => panic!("expected create info"),
+		}
+		assert_eq!(
+			EVM::account_codes(contract_address(alice.address, 0)).len(),
+			0
+		);
+	});
+}
+
+#[test]
+fn force_transact_should_execute_on_behalf_of_source() {
+	let (pairs, mut ext) = new_test_ext(2);
+	let alice = &pairs[0];
+	let bob = &pairs[1];
+
+	ext.execute_with(|| {
+		// Signed by bob, but applied as alice.
+		let transaction = legacy_erc20_creation_transaction(bob);
+
+		assert_err!(
+			Ethereum::force_transact(
+				Origin::signed(alice.account_id.clone()),
+				alice.address,
+				transaction.clone(),
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		assert_ok!(Ethereum::force_transact(
+			Origin::root(),
+			alice.address,
+			transaction,
+		));
+		assert_eq!(EVM::account_basic(&alice.address).nonce, U256::from(1));
+		assert_eq!(EVM::account_basic(&bob.address).nonce, U256::zero());
+		assert_eq!(crate::Pending::<Test>::get()[0].1.from, alice.address);
+		assert_ne!(
+			EVM::account_codes(contract_address(alice.address, 0)).len(),
+			0
+		);
+	});
+}
+
+#[test]
+fn force_transact_should_check_nonce() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		let mut transaction = legacy_erc20_creation_unsigned_transaction();
+		transaction.nonce = U256::from(1);
+
+		assert_err!(
+			Ethereum::force_transact(
+				Origin::root(),
+				alice.address,
+				transaction.sign(&alice.private_key),
+			),
+			crate::Error::<Test>::InvalidTransaction
+		);
+	});
+}
+
+#[test]
+fn force_transact_should_impersonate_a_contract() {
+	let (pairs, mut ext) = new_test_ext(2);
+	let alice = &pairs[0];
+	let bob = &pairs[1];
+
+	ext.execute_with(|| {
+		pallet_evm::AccountCodes::<Test>::insert(alice.address, vec![0x00]);
+
+		assert_ok!(Ethereum::force_transact(
+			Origin::root(),
+			alice.address,
+			legacy_erc20_creation_transaction(bob),
+		));
+		assert_eq!(EVM::account_basic(&alice.address).nonce, U256::from(1));
+		assert_ne!(
+			EVM::account_codes(contract_address(alice.address, 0)).len(),
+			0
+		);
+	});
+}
+
+#[test]
+fn placeholder_signature_should_not_transact_as_the_impersonated_account() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		// The signature development nodes give the transactions of impersonated accounts.
+		let mut r = H256::from(alice.address);
+		r.0[0] = 1;
+		let unsigned = legacy_erc20_creation_unsigned_transaction();
+		let transaction = Transaction::Legacy(ethereum::LegacyTransaction {
+			nonce: unsigned.nonce,
+			gas_price: unsigned.gas_price,
+			gas_limit: unsigned.gas_limit,
+			action: unsigned.action,
+			value: unsigned.value,
+			input: unsigned.input,
+			signature: ethereum::TransactionSignature::new(27, r, H256::from_low_u64_be(1))
+				.unwrap(),
+		});
+		let call = crate::Call::<Test>::transact { transaction };
+
+		// Outside of `force_transact` the sender is recovered from the signature, which is
+		// either invalid or the one of an unknown and unfunded account.
+		match call.check_self_contained().unwrap() {
+			Ok(source) => {
+				assert_ne!(source, alice.address);
+				assert_eq!(
+					call.validate_self_contained(&source).unwrap(),
+					Err(TransactionValidityError::Invalid(
+						InvalidTransaction::Payment
+					))
+				);
+			}
+			Err(err) => assert_eq!(
+				err,
+				TransactionValidityError::Invalid(InvalidTransaction::Custom(
+					crate::TransactionValidationError::InvalidSignature as u8
+				))
+			),
+		}
+	});
+}
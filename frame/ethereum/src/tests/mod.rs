@@ -30,6 +30,7 @@ use crate::{
 mod eip1559;
 mod eip2930;
 mod legacy;
+mod state_root;
 
 // This ERC-20 contract mints the maximum amount of tokens to the contract creator.
 // pragma solidity ^0.5.0;`
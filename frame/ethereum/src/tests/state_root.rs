@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: Apache-2.0
+// This file is part of Frontier.
+//
+// Copyright (c) 2020-2022 Parity Technologies (UK) Ltd.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum-compatible state root tests.
+
+use super::{
+	assert_ok, FromHex, FromStr, ToHex, Transaction, TransactionAction, ERC20_CONTRACT_BYTECODE,
+	H160, H256, U256,
+};
+use crate::{
+	mock::{
+		contract_address,
+		state_trie::{new_test_ext, Balances, Ethereum, Origin, System, Test},
+		AccountInfo, HashedAddressMapping, LegacyUnsignedTransaction,
+	},
+	EthereumStateRoot, IntermediateStateRoot, StateTrieEnabled, StateTrieNodes,
+	StateTrieTouchedAccounts, StateTrieTouchedStorage, EMPTY_TRIE_ROOT,
+};
+use frame_support::traits::{Currency, Get};
+use pallet_evm::{AddressMapping, TouchedState};
+
+fn touched_entries() -> usize {
+	StateTrieTouchedAccounts::<Test>::iter().count()
+		+ StateTrieTouchedStorage::<Test>::iter().count()
+}
+
+fn legacy_transaction(
+	account: &AccountInfo,
+	nonce: u64,
+	action: TransactionAction,
+	input: Vec<u8>,
+) -> Transaction {
+	LegacyUnsignedTransaction {
+		nonce: U256::from(nonce),
+		gas_price: U256::from(1),
+		gas_limit: U256::from(0x100000),
+		action,
+		value: U256::zero(),
+		input,
+	}
+	.sign(&account.private_key)
+}
+
+#[test]
+fn state_root_should_match_recomputed_root() {
+	let (pairs, mut ext) = new_test_ext(2);
+	let alice = &pairs[0];
+	let bob = &pairs[1];
+
+	ext.execute_with(|| {
+		StateTrieEnabled::<Test>::put(true);
+
+		let t = legacy_transaction(
+			alice,
+			0,
+			TransactionAction::Create,
+			FromHex::from_hex(ERC20_CONTRACT_BYTECODE).unwrap(),
+		);
+		assert_ok!(Ethereum::execute(alice.address, &t, None));
+
+		let root = EthereumStateRoot::<Test>::get();
+		assert_ne!(root, EMPTY_TRIE_ROOT);
+		assert_eq!(root, EthereumStateRoot::<Test>::recompute());
+
+		// transfer(bob, 1000)
+		let input: Vec<u8> = FromHex::from_hex(&format!(
+			"a9059cbb000000000000000000000000{}{:064x}",
+			bob.address.as_bytes().to_hex::<String>(),
+			1000
+		))
+		.unwrap();
+		let t = legacy_transaction(
+			alice,
+			1,
+			TransactionAction::Call(contract_address(alice.address, 0)),
+			input,
+		);
+		assert_ok!(Ethereum::execute(alice.address, &t, None));
+
+		let new_root = EthereumStateRoot::<Test>::get();
+		assert_ne!(new_root, root);
+		assert_eq!(new_root, EthereumStateRoot::<Test>::recompute());
+	});
+}
+
+#[test]
+fn state_trie_nodes_should_be_released_when_accounts_are_emptied() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		StateTrieEnabled::<Test>::put(true);
+		assert_eq!(EthereumStateRoot::<Test>::recompute(), EMPTY_TRIE_ROOT);
+
+		let mut touched = TouchedState::default();
+		touched.touch_account(alice.address);
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_ne!(EthereumStateRoot::<Test>::get(), EMPTY_TRIE_ROOT);
+		assert_ne!(StateTrieNodes::<Test>::iter().count(), 0);
+
+		Balances::make_free_balance_be(&alice.account_id, 0);
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_eq!(EthereumStateRoot::<Test>::get(), EMPTY_TRIE_ROOT);
+		assert_eq!(StateTrieNodes::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn state_root_should_match_known_answer() {
+	let (_, mut ext) = new_test_ext(0);
+	let alice = H160::repeat_byte(0x11);
+	let bob = H160::repeat_byte(0x22);
+	let contract = H160::repeat_byte(0x33);
+	let slot = H256::from_low_u64_be(1);
+
+	ext.execute_with(|| {
+		StateTrieEnabled::<Test>::put(true);
+
+		// Balances are reported without the existential deposit of 500.
+		let alice_id = HashedAddressMapping::into_account_id(alice);
+		let bob_id = HashedAddressMapping::into_account_id(bob);
+		Balances::make_free_balance_be(&alice_id, 1_000_500);
+		Balances::make_free_balance_be(&bob_id, 2_500);
+		System::inc_account_nonce(&bob_id);
+		pallet_evm::AccountCodes::<Test>::insert(contract, vec![0x60, 0x00]);
+		pallet_evm::AccountStorages::<Test>::insert(contract, slot, H256::from_low_u64_be(42));
+
+		let mut touched = TouchedState::default();
+		touched.touch_account(alice);
+		touched.touch_account(bob);
+		touched.touch_storage(contract, slot);
+		EthereumStateRoot::<Test>::touch(&touched);
+
+		// Roots of the same accounts computed with a reference Merkle-Patricia trie
+		// implementation, checked against the go-ethereum trie test vectors.
+		let root =
+			H256::from_str("d93cb99806c5e601aa93001195fda697cd5267e395c355df4afb85fda1572403")
+				.unwrap();
+		assert_eq!(EthereumStateRoot::<Test>::get(), root);
+		assert_eq!(EthereumStateRoot::<Test>::recompute(), root);
+
+		Balances::make_free_balance_be(&alice_id, 0);
+		EthereumStateRoot::<Test>::touch(&touched);
+		let root =
+			H256::from_str("a8609a38e8cc898e15c2f33f189a7e256d475c86f1a84a850d194f6ac24a1179")
+				.unwrap();
+		assert_eq!(EthereumStateRoot::<Test>::get(), root);
+		assert_eq!(EthereumStateRoot::<Test>::recompute(), root);
+	});
+}
+
+#[test]
+fn state_root_should_fall_back_to_intermediate_root_when_disabled() {
+	let (pairs, mut ext) = new_test_ext(1);
+	let alice = &pairs[0];
+
+	ext.execute_with(|| {
+		let mut touched = TouchedState::default();
+		touched.touch_account(alice.address);
+
+		// State touched by the genesis build is kept for the first block.
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_ne!(touched_entries(), 0);
+
+		let root = EthereumStateRoot::<Test>::get();
+		assert_eq!(root, IntermediateStateRoot::<Test>::get());
+		assert_eq!(touched_entries(), 0);
+		assert_eq!(EthereumStateRoot::<Test>::root(), EMPTY_TRIE_ROOT);
+		assert_eq!(StateTrieNodes::<Test>::iter().count(), 0);
+
+		System::set_block_number(1);
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_eq!(touched_entries(), 0);
+	});
+}
+
+#[test]
+fn state_trie_touched_entries_should_be_recorded_once() {
+	let (_, mut ext) = new_test_ext(0);
+	let contract = H160::repeat_byte(0x33);
+	let slot = H256::from_low_u64_be(1);
+
+	ext.execute_with(|| {
+		StateTrieEnabled::<Test>::put(true);
+
+		let mut touched = TouchedState::default();
+		touched.touch_storage(contract, slot);
+		EthereumStateRoot::<Test>::touch(&touched);
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_eq!(touched_entries(), 2);
+
+		let mut reset = TouchedState::default();
+		reset.reset_storage(contract);
+		EthereumStateRoot::<Test>::touch(&reset);
+		EthereumStateRoot::<Test>::touch(&touched);
+		assert_eq!(StateTrieTouchedAccounts::<Test>::get(contract), Some(true));
+
+		EthereumStateRoot::<Test>::get();
+		assert_eq!(touched_entries(), 0);
+	});
+}
+
+#[test]
+fn state_root_should_follow_balance_changes_outside_the_evm() {
+	let (pairs, mut ext) = new_test_ext(2);
+	let alice = &pairs[0];
+	let bob = &pairs[1];
+
+	ext.execute_with(|| {
+		StateTrieEnabled::<Test>::put(true);
+
+		let mut touched = TouchedState::default();
+		touched.touch_account(alice.address);
+		EthereumStateRoot::<Test>::touch(&touched);
+		let root = EthereumStateRoot::<Test>::get();
+		assert_eq!(root, EthereumStateRoot::<Test>::recompute());
+
+		// A Substrate transfer, without any EVM execution.
+		assert_ok!(Balances::transfer(
+			Origin::signed(alice.account_id.clone()),
+			bob.account_id.clone(),
+			1_000,
+		));
+		let new_root = EthereumStateRoot::<Test>::get();
+		assert_ne!(new_root, root);
+		assert_eq!(new_root, EthereumStateRoot::<Test>::recompute());
+
+		// Emptied accounts are still followed.
+		Balances::make_free_balance_be(&alice.account_id, 0);
+		assert_eq!(EthereumStateRoot::<Test>::get(), EMPTY_TRIE_ROOT);
+		Balances::make_free_balance_be(&alice.account_id, 1_000_000);
+		let root = EthereumStateRoot::<Test>::get();
+		assert_ne!(root, EMPTY_TRIE_ROOT);
+		assert_eq!(root, EthereumStateRoot::<Test>::recompute());
+	});
+}
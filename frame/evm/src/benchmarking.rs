@@ -85,6 +85,8 @@ benchmarks! {
 			None,
 			Some(nonce_as_u256),
 			Vec::new(),
+			true,
+			false,
 			T::config(),
 		);
 		assert_eq!(create_runner_results.is_ok(), true, "create() failed");
@@ -116,6 +118,8 @@ benchmarks! {
 			None,
 			Some(nonce_as_u256),
 			Vec::new(),
+			true,
+			false,
 			T::config(),
 		);
 		assert_eq!(call_runner_results.is_ok(), true, "call() failed");
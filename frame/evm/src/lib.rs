@@ -61,8 +61,10 @@ pub mod runner;
 #[cfg(test)]
 mod tests;
 
+use codec::{Decode, Encode};
 use frame_support::{
 	dispatch::DispatchResultWithPostInfo,
+	ensure,
 	traits::{
 		tokens::fungible::Inspect, Currency, ExistenceRequirement, FindAuthor, Get, Imbalance,
 		OnUnbalanced, SignedImbalance, WithdrawReasons,
@@ -70,12 +72,13 @@ use frame_support::{
 	weights::{Pays, PostDispatchInfo, Weight},
 };
 use frame_system::RawOrigin;
+use scale_info::TypeInfo;
 use sp_core::{Hasher, H160, H256, U256};
 use sp_runtime::{
 	traits::{BadOrigin, Saturating, UniqueSaturatedInto, Zero},
 	AccountId32,
 };
-use sp_std::vec::Vec;
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
 
 pub use evm::{
 	Config as EvmConfig, Context, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed,
@@ -83,8 +86,9 @@ pub use evm::{
 #[cfg(feature = "std")]
 use fp_evm::GenesisAccount;
 pub use fp_evm::{
-	Account, CallInfo, CreateInfo, ExecutionInfo, FeeCalculator, LinearCostPrecompile, Log,
-	Precompile, PrecompileFailure, PrecompileOutput, PrecompileResult, PrecompileSet, Vicinity,
+	AccessListInfo, Account, CallInfo, CreateInfo, ExecutionInfo, FeeCalculator,
+	LinearCostPrecompile, Log, Precompile, PrecompileFailure, PrecompileOutput, PrecompileResult,
+	PrecompileSet, Vicinity,
 };
 
 pub use self::{pallet::*, runner::Runner};
@@ -145,6 +149,16 @@ pub mod pallet {
 		fn config() -> &'static EvmConfig {
 			&LONDON_CONFIG
 		}
+
+		/// Whether to reject transactions sent from an address with deployed code (EIP-3607)
+		/// and contract deployments whose code starts with the `0xEF` byte (EIP-3541).
+		fn eip_3607_3541_checks() -> bool {
+			true
+		}
+
+		/// Called with the accounts and storage slots that an EVM execution, a withdrawal or
+		/// the genesis build may have modified.
+		fn on_state_touched(_touched: &TouchedState) {}
 	}
 
 	#[pallet::call]
@@ -166,6 +180,10 @@ pub mod pallet {
 				ExistenceRequirement::AllowDeath,
 			)?;
 
+			let mut touched = TouchedState::default();
+			touched.touch_account(address);
+			T::on_state_touched(&touched);
+
 			Ok(())
 		}
 
@@ -195,6 +213,8 @@ pub mod pallet {
 				max_priority_fee_per_gas,
 				nonce,
 				access_list,
+				true,
+				false,
 				T::config(),
 			)?;
 
@@ -240,6 +260,8 @@ pub mod pallet {
 				max_priority_fee_per_gas,
 				nonce,
 				access_list,
+				true,
+				false,
 				T::config(),
 			)?;
 
@@ -294,6 +316,8 @@ pub mod pallet {
 				max_priority_fee_per_gas,
 				nonce,
 				access_list,
+				true,
+				false,
 				T::config(),
 			)?;
 
@@ -321,6 +345,78 @@ pub mod pallet {
 				pays_fee: Pays::No,
 			})
 		}
+
+		/// Set the balance of an EVM account, as reported by `account_basic`.
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn force_set_balance(
+			origin: OriginFor<T>,
+			address: H160,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			let account_id = T::AddressMapping::into_account_id(address);
+
+			// `account_basic` reports the balance above the existential deposit.
+			T::Currency::make_free_balance_be(
+				&account_id,
+				balance.saturating_add(T::Currency::minimum_balance()),
+			);
+
+			let mut touched = TouchedState::default();
+			touched.touch_account(address);
+			T::on_state_touched(&touched);
+
+			Ok(())
+		}
+
+		/// Replace the code of an EVM account. Empty code removes it.
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 2))]
+		pub fn force_set_code(
+			origin: OriginFor<T>,
+			address: H160,
+			code: Vec<u8>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			if code.is_empty() {
+				if <AccountCodes<T>>::contains_key(&address) {
+					let account_id = T::AddressMapping::into_account_id(address);
+					let _ = frame_system::Pallet::<T>::dec_sufficients(&account_id);
+					<AccountCodes<T>>::remove(&address);
+				}
+			} else {
+				Pallet::<T>::create_account(address, code)?;
+			}
+
+			let mut touched = TouchedState::default();
+			touched.touch_account(address);
+			T::on_state_touched(&touched);
+
+			Ok(())
+		}
+
+		/// Set a storage slot of an EVM account. A zero value removes it.
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn force_set_storage(
+			origin: OriginFor<T>,
+			address: H160,
+			index: H256,
+			value: H256,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			if value == H256::default() {
+				<AccountStorages<T>>::remove(address, index);
+			} else {
+				<AccountStorages<T>>::insert(address, index, value);
+			}
+
+			let mut touched = TouchedState::default();
+			touched.touch_storage(address, index);
+			T::on_state_touched(&touched);
+
+			Ok(())
+		}
 	}
 
 	#[pallet::event]
@@ -356,6 +452,10 @@ pub mod pallet {
 		GasPriceTooLow,
 		/// Nonce is invalid
 		InvalidNonce,
+		/// Transaction sender has deployed code (EIP-3607).
+		TransactionMustComeFromEOA,
+		/// Code starts with the `0xEF` byte (EIP-3541).
+		InvalidCode,
 	}
 
 	#[pallet::genesis_config]
@@ -375,26 +475,29 @@ pub mod pallet {
 	#[pallet::genesis_build]
 	impl<T: Config> GenesisBuild<T> for GenesisConfig {
 		fn build(&self) {
+			let mut touched = TouchedState::default();
 			for (address, account) in &self.accounts {
+				touched.touch_account(*address);
 				let account_id = T::AddressMapping::into_account_id(*address);
 
-				// ASSUME: in one single EVM transaction, the nonce will not increase more than
-				// `u128::max_value()`.
-				for _ in 0..account.nonce.low_u128() {
-					frame_system::Pallet::<T>::inc_account_nonce(&account_id);
-				}
+				frame_system::Account::<T>::mutate(&account_id, |info| {
+					info.nonce = account.nonce.low_u128().unique_saturated_into();
+				});
 
 				T::Currency::deposit_creating(
 					&account_id,
 					account.balance.low_u128().unique_saturated_into(),
 				);
 
-				Pallet::<T>::create_account(*address, account.code.clone());
+				Pallet::<T>::create_account(*address, account.code.clone())
+					.expect("genesis code must not start with 0xEF when EIP-3541 is enabled");
 
 				for (index, value) in &account.storage {
 					<AccountStorages<T>>::insert(address, index, value);
+					touched.touch_storage(*address, *index);
 				}
 			}
+			T::on_state_touched(&touched);
 		}
 	}
 
@@ -559,6 +662,49 @@ impl GasWeightMapping for () {
 
 static LONDON_CONFIG: EvmConfig = EvmConfig::london();
 
+/// Accounts and storage slots that may have been modified, reported through
+/// [`Config::on_state_touched`].
+#[derive(Clone, Default, Eq, PartialEq, Debug, Encode, Decode, TypeInfo)]
+pub struct TouchedState {
+	/// Accounts whose nonce, balance, code or storage may have changed.
+	pub accounts: BTreeSet<H160>,
+	/// Storage slots that may have been written.
+	pub storage: BTreeSet<(H160, H256)>,
+	/// Accounts whose whole storage may have been removed.
+	pub reset_storage: BTreeSet<H160>,
+}
+
+impl TouchedState {
+	/// Record a change to the nonce, balance or code of `address`.
+	pub fn touch_account(&mut self, address: H160) {
+		self.accounts.insert(address);
+	}
+
+	/// Record a write to the storage slot `index` of `address`.
+	pub fn touch_storage(&mut self, address: H160, index: H256) {
+		self.accounts.insert(address);
+		self.storage.insert((address, index));
+	}
+
+	/// Record the removal of all the storage of `address`.
+	pub fn reset_storage(&mut self, address: H160) {
+		self.accounts.insert(address);
+		self.reset_storage.insert(address);
+	}
+
+	/// Add the changes recorded in `other`.
+	pub fn merge(&mut self, other: TouchedState) {
+		self.accounts.extend(other.accounts);
+		self.storage.extend(other.storage);
+		self.reset_storage.extend(other.reset_storage);
+	}
+
+	/// Whether no change was recorded.
+	pub fn is_empty(&self) -> bool {
+		self.accounts.is_empty()
+	}
+}
+
 impl<T: Config> Pallet<T> {
 	/// Check whether an account is empty.
 	pub fn is_account_empty(address: &H160) -> bool {
@@ -586,18 +732,25 @@ impl<T: Config> Pallet<T> {
 		<AccountStorages<T>>::remove_prefix(address, None);
 	}
 
-	/// Create an account.
-	pub fn create_account(address: H160, code: Vec<u8>) {
+	/// Create an account. Code starting with the `0xEF` byte is rejected when the EIP-3541
+	/// check is enabled.
+	pub fn create_account(address: H160, code: Vec<u8>) -> Result<(), Error<T>> {
 		if code.is_empty() {
-			return;
+			return Ok(());
 		}
 
+		ensure!(
+			!(T::eip_3607_3541_checks() && code[0] == 0xEF),
+			Error::<T>::InvalidCode
+		);
+
 		if !<AccountCodes<T>>::contains_key(&address) {
 			let account_id = T::AddressMapping::into_account_id(address);
 			let _ = frame_system::Pallet::<T>::inc_sufficients(&account_id);
 		}
 
 		<AccountCodes<T>>::insert(address, code);
+		Ok(())
 	}
 
 	/// Get the account basic in EVM format.
@@ -22,6 +22,11 @@ use fp_evm::{CallInfo, CreateInfo};
 use sp_core::{H160, H256, U256};
 use sp_std::vec::Vec;
 
+/// Executes EVM calls and creates.
+///
+/// `is_transactional` tells whether the execution is a transaction, as opposed to a read-only
+/// call such as `eth_call` or gas estimation. Transactions from senders with deployed code are
+/// rejected (EIP-3607) when the runtime enables the check, unless `skip_eip3607` is set.
 pub trait Runner<T: Config> {
 	type Error: Into<sp_runtime::DispatchError>;
 
@@ -35,6 +40,8 @@ pub trait Runner<T: Config> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CallInfo, Self::Error>;
 
@@ -47,6 +54,8 @@ pub trait Runner<T: Config> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CreateInfo, Self::Error>;
 
@@ -60,6 +69,8 @@ pub trait Runner<T: Config> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CreateInfo, Self::Error>;
 }
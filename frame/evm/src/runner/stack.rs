@@ -19,14 +19,17 @@
 
 use crate::{
 	runner::Runner as RunnerT, AccountCodes, AccountStorages, AddressMapping, BlockHashMapping,
-	Config, Error, Event, FeeCalculator, OnChargeEVMTransaction, Pallet,
+	Config, Error, Event, FeeCalculator, OnChargeEVMTransaction, Pallet, TouchedState,
 };
+use core::cell::RefCell;
 use evm::{
 	backend::Backend as BackendT,
-	executor::stack::{Accessed, StackExecutor, StackState as StackStateT, StackSubstateMetadata},
+	executor::stack::{
+		Accessed, PrecompileSet, StackExecutor, StackState as StackStateT, StackSubstateMetadata,
+	},
 	ExitError, ExitReason, Transfer,
 };
-use fp_evm::{CallInfo, CreateInfo, ExecutionInfo, Log, Vicinity};
+use fp_evm::{AccessListInfo, CallInfo, CreateInfo, ExecutionInfo, Log, Vicinity};
 use frame_support::{
 	ensure,
 	traits::{Currency, ExistenceRequirement, Get},
@@ -34,7 +37,13 @@ use frame_support::{
 use sha3::{Digest, Keccak256};
 use sp_core::{H160, H256, U256};
 use sp_runtime::traits::UniqueSaturatedInto;
-use sp_std::{boxed::Box, collections::btree_set::BTreeSet, marker::PhantomData, mem, vec::Vec};
+use sp_std::{
+	boxed::Box,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	marker::PhantomData,
+	mem,
+	vec::Vec,
+};
 
 #[derive(Default)]
 pub struct Runner<T: Config> {
@@ -50,10 +59,129 @@ impl<T: Config> Runner<T> {
 		max_fee_per_gas: Option<U256>,
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &'config evm::Config,
 		precompiles: &'precompiles T::PrecompilesType,
 		f: F,
 	) -> Result<ExecutionInfo<R>, Error<T>>
+	where
+		F: FnOnce(
+			&mut StackExecutor<
+				'config,
+				'precompiles,
+				SubstrateStackState<'_, 'config, T>,
+				T::PrecompilesType,
+			>,
+		) -> (ExitReason, R),
+	{
+		Self::execute_inner(
+			source,
+			value,
+			gas_limit,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			nonce,
+			is_transactional,
+			skip_eip3607,
+			config,
+			precompiles,
+			false,
+			f,
+		)
+		.map(|(info, _)| info)
+	}
+
+	/// Execute a call, or a create when `target` is `None`, recording the addresses and storage
+	/// keys accessed by the execution. The sender, the recipient (or the created contract) and
+	/// the precompiles are only part of the returned access list for the storage keys accessed
+	/// on them.
+	pub fn create_access_list(
+		source: H160,
+		target: Option<H160>,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
+		config: &evm::Config,
+	) -> Result<AccessListInfo, Error<T>> {
+		let precompiles = T::PrecompilesValue::get();
+		let config = Self::checked_config(config);
+		let (info, accessed) = Self::execute_inner(
+			source,
+			value,
+			gas_limit,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			nonce,
+			is_transactional,
+			skip_eip3607,
+			&config,
+			&precompiles,
+			true,
+			|executor| match target {
+				Some(target) => {
+					let (reason, retv) = executor.transact_call(
+						source,
+						target,
+						value,
+						input,
+						gas_limit,
+						access_list,
+					);
+					(reason, (target, retv))
+				}
+				None => {
+					let address =
+						executor.create_address(evm::CreateScheme::Legacy { caller: source });
+					let (reason, retv) =
+						executor.transact_create(source, value, input, gas_limit, access_list);
+					(reason, (address, retv))
+				}
+			},
+		)?;
+
+		// The sender, the recipient and the precompiles are warm from the start, but the storage
+		// keys accessed on them are not.
+		let (recipient, value) = info.value;
+		let access_list = accessed
+			.into_iter()
+			.filter(|(address, keys)| {
+				!keys.is_empty()
+					|| (*address != source
+						&& *address != recipient
+						&& !precompiles.is_precompile(*address))
+			})
+			.map(|(address, keys)| (address, keys.into_iter().collect()))
+			.collect();
+
+		Ok(AccessListInfo {
+			exit_reason: info.exit_reason,
+			value,
+			used_gas: info.used_gas,
+			access_list,
+		})
+	}
+
+	fn execute_inner<'config, 'precompiles, F, R>(
+		source: H160,
+		value: U256,
+		gas_limit: u64,
+		max_fee_per_gas: Option<U256>,
+		max_priority_fee_per_gas: Option<U256>,
+		nonce: Option<U256>,
+		is_transactional: bool,
+		skip_eip3607: bool,
+		config: &'config evm::Config,
+		precompiles: &'precompiles T::PrecompilesType,
+		record_accesses: bool,
+		f: F,
+	) -> Result<(ExecutionInfo<R>, BTreeMap<H160, BTreeSet<H256>>), Error<T>>
 	where
 		F: FnOnce(
 			&mut StackExecutor<
@@ -100,6 +228,16 @@ impl<T: Config> Runner<T> {
 		if let Some(nonce) = nonce {
 			ensure!(source_account.nonce == nonce, Error::<T>::InvalidNonce);
 		}
+
+		// EIP-3607: only externally owned accounts can originate transactions. Read-only calls,
+		// such as `eth_call` and gas estimation, are still allowed from contracts.
+		if T::eip_3607_3541_checks() && is_transactional && !skip_eip3607 {
+			ensure!(
+				<AccountCodes<T>>::decode_len(&source).unwrap_or(0) == 0,
+				Error::<T>::TransactionMustComeFromEOA
+			);
+		}
+
 		// Deduct fee from the `source` account.
 		let fee = T::OnChargeTransaction::withdraw_fee(&source, total_fee)?;
 
@@ -110,7 +248,10 @@ impl<T: Config> Runner<T> {
 		};
 
 		let metadata = StackSubstateMetadata::new(gas_limit, &config);
-		let state = SubstrateStackState::new(&vicinity, metadata);
+		let mut state = SubstrateStackState::new(&vicinity, metadata);
+		if record_accesses {
+			state = state.with_access_recording();
+		}
 		let mut executor = StackExecutor::new_with_precompiles(state, config, precompiles);
 
 		let (reason, retv) = f(&mut executor);
@@ -167,7 +308,7 @@ impl<T: Config> Runner<T> {
 			T::OnChargeTransaction::pay_priority_fee(actual_priority_fee);
 		}
 
-		let state = executor.into_state();
+		let mut state = executor.into_state();
 
 		for address in state.substate.deletes {
 			log::debug!(
@@ -175,9 +316,15 @@ impl<T: Config> Runner<T> {
 				"Deleting account at {:?}",
 				address
 			);
-			Pallet::<T>::remove_account(&address)
+			Pallet::<T>::remove_account(&address);
+			state.touched.reset_storage(address);
 		}
 
+		// Fees are paid by the source and tipped to the block author.
+		state.touched.touch_account(source);
+		state.touched.touch_account(Pallet::<T>::find_author());
+		T::on_state_touched(&state.touched);
+
 		for log in &state.substate.logs {
 			log::trace!(
 				target: "evm",
@@ -195,12 +342,24 @@ impl<T: Config> Runner<T> {
 			}));
 		}
 
-		Ok(ExecutionInfo {
-			value: retv,
-			exit_reason: reason,
-			used_gas,
-			logs: state.substate.logs,
-		})
+		Ok((
+			ExecutionInfo {
+				value: retv,
+				exit_reason: reason,
+				used_gas,
+				logs: state.substate.logs,
+			},
+			state.accessed.map(RefCell::into_inner).unwrap_or_default(),
+		))
+	}
+
+	/// The EVM config to execute with. EIP-3541 follows the runtime check rather than
+	/// `config`, so deployed code starting with `0xEF` is rejected exactly when the check is
+	/// enabled.
+	fn checked_config(config: &evm::Config) -> evm::Config {
+		let mut config = config.clone();
+		config.disallow_executable_format = T::eip_3607_3541_checks();
+		config
 	}
 }
 
@@ -217,9 +376,12 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CallInfo, Self::Error> {
 		let precompiles = T::PrecompilesValue::get();
+		let config = Self::checked_config(config);
 		Self::execute(
 			source,
 			value,
@@ -227,7 +389,9 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 			max_fee_per_gas,
 			max_priority_fee_per_gas,
 			nonce,
-			config,
+			is_transactional,
+			skip_eip3607,
+			&config,
 			&precompiles,
 			|executor| executor.transact_call(source, target, value, input, gas_limit, access_list),
 		)
@@ -242,9 +406,12 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CreateInfo, Self::Error> {
 		let precompiles = T::PrecompilesValue::get();
+		let config = Self::checked_config(config);
 		Self::execute(
 			source,
 			value,
@@ -252,7 +419,9 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 			max_fee_per_gas,
 			max_priority_fee_per_gas,
 			nonce,
-			config,
+			is_transactional,
+			skip_eip3607,
+			&config,
 			&precompiles,
 			|executor| {
 				let address = executor.create_address(evm::CreateScheme::Legacy { caller: source });
@@ -273,9 +442,12 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 		max_priority_fee_per_gas: Option<U256>,
 		nonce: Option<U256>,
 		access_list: Vec<(H160, Vec<H256>)>,
+		is_transactional: bool,
+		skip_eip3607: bool,
 		config: &evm::Config,
 	) -> Result<CreateInfo, Self::Error> {
 		let precompiles = T::PrecompilesValue::get();
+		let config = Self::checked_config(config);
 		let code_hash = H256::from_slice(Keccak256::digest(&init).as_slice());
 		Self::execute(
 			source,
@@ -284,7 +456,9 @@ impl<T: Config> RunnerT<T> for Runner<T> {
 			max_fee_per_gas,
 			max_priority_fee_per_gas,
 			nonce,
-			config,
+			is_transactional,
+			skip_eip3607,
+			&config,
 			&precompiles,
 			|executor| {
 				let address = executor.create_address(evm::CreateScheme::Create2 {
@@ -401,6 +575,11 @@ impl<'config> SubstrateStackSubstate<'config> {
 pub struct SubstrateStackState<'vicinity, 'config, T> {
 	vicinity: &'vicinity Vicinity,
 	substate: SubstrateStackSubstate<'config>,
+	/// State possibly modified by the execution, including in reverted substates.
+	touched: TouchedState,
+	/// Addresses and storage keys accessed by the execution, when recording is enabled. Reverted
+	/// substates are included, since their accesses still cost gas.
+	accessed: Option<RefCell<BTreeMap<H160, BTreeSet<H256>>>>,
 	_marker: PhantomData<T>,
 }
 
@@ -415,9 +594,27 @@ impl<'vicinity, 'config, T: Config> SubstrateStackState<'vicinity, 'config, T> {
 				logs: Vec::new(),
 				parent: None,
 			},
+			touched: TouchedState::default(),
+			accessed: None,
 			_marker: PhantomData,
 		}
 	}
+
+	/// Record the addresses and storage keys accessed by the execution.
+	pub fn with_access_recording(mut self) -> Self {
+		self.accessed = Some(RefCell::new(BTreeMap::new()));
+		self
+	}
+
+	fn record_access(&self, address: H160, key: Option<H256>) {
+		if let Some(accessed) = self.accessed.as_ref() {
+			let mut accessed = accessed.borrow_mut();
+			let keys = accessed.entry(address).or_default();
+			if let Some(key) = key {
+				keys.insert(key);
+			}
+		}
+	}
 }
 
 impl<'vicinity, 'config, T: Config> BackendT for SubstrateStackState<'vicinity, 'config, T> {
@@ -528,11 +725,13 @@ impl<'vicinity, 'config, T: Config> StackStateT<'config>
 	}
 
 	fn inc_nonce(&mut self, address: H160) {
+		self.touched.touch_account(address);
 		let account_id = T::AddressMapping::into_account_id(address);
 		frame_system::Pallet::<T>::inc_account_nonce(&account_id);
 	}
 
 	fn set_storage(&mut self, address: H160, index: H256, value: H256) {
+		self.touched.touch_storage(address, index);
 		if value == H256::default() {
 			log::debug!(
 				target: "evm",
@@ -554,6 +753,7 @@ impl<'vicinity, 'config, T: Config> StackStateT<'config>
 	}
 
 	fn reset_storage(&mut self, address: H160) {
+		self.touched.reset_storage(address);
 		<AccountStorages<T>>::remove_prefix(address, None);
 	}
 
@@ -572,10 +772,14 @@ impl<'vicinity, 'config, T: Config> StackStateT<'config>
 			code.len(),
 			address
 		);
-		Pallet::<T>::create_account(address, code);
+		self.touched.touch_account(address);
+		// The executor already rejects `0xEF` code with the checked config.
+		let _ = Pallet::<T>::create_account(address, code);
 	}
 
 	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError> {
+		self.touched.touch_account(transfer.source);
+		self.touched.touch_account(transfer.target);
 		let source = T::AddressMapping::into_account_id(transfer.source);
 		let target = T::AddressMapping::into_account_id(transfer.target);
 
@@ -605,11 +809,13 @@ impl<'vicinity, 'config, T: Config> StackStateT<'config>
 	}
 
 	fn is_cold(&self, address: H160) -> bool {
+		self.record_access(address, None);
 		self.substate
 			.recursive_is_cold(&|a| a.accessed_addresses.contains(&address))
 	}
 
 	fn is_storage_cold(&self, address: H160, key: H256) -> bool {
+		self.record_access(address, Some(key));
 		self.substate
 			.recursive_is_cold(&|a: &Accessed| a.accessed_storage.contains(&(address, key)))
 	}
@@ -21,9 +21,10 @@ use super::*;
 use crate::mock::*;
 
 use frame_support::{
-	assert_ok,
+	assert_err, assert_noop, assert_ok,
 	traits::{GenesisBuild, LockIdentifier, LockableCurrency, WithdrawReasons},
 };
+use sp_runtime::DispatchError;
 use std::{collections::BTreeMap, str::FromStr};
 
 type Balances = pallet_balances::Pallet<Test>;
@@ -80,6 +81,29 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	t.into()
 }
 
+#[test]
+fn genesis_sets_the_nonce() {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let address = H160::repeat_byte(0x42);
+	let mut accounts = BTreeMap::new();
+	accounts.insert(
+		address,
+		GenesisAccount {
+			nonce: U256::from(u32::MAX),
+			balance: U256::from(1000000),
+			storage: Default::default(),
+			code: Vec::new(),
+		},
+	);
+	GenesisBuild::<Test>::assimilate_storage(&crate::GenesisConfig { accounts }, &mut t).unwrap();
+
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		assert_eq!(EVM::account_basic(&address).nonce, U256::from(u32::MAX));
+	});
+}
+
 #[test]
 fn fail_call_return_ok() {
 	new_test_ext().execute_with(|| {
@@ -399,7 +423,7 @@ fn handle_sufficient_reference() {
 		assert_eq!(account.sufficients, 0);
 
 		// Using the create / remove account functions is the correct way to handle it.
-		EVM::create_account(addr_2, vec![1, 2, 3]);
+		assert_ok!(EVM::create_account(addr_2, vec![1, 2, 3]));
 		let account_2 = frame_system::Account::<Test>::get(substrate_addr_2);
 		// We increased the sufficient reference by 1.
 		assert_eq!(account_2.sufficients, 1);
@@ -409,3 +433,156 @@ fn handle_sufficient_reference() {
 		assert_eq!(account_2.sufficients, 0);
 	});
 }
+
+#[test]
+fn create_access_list_records_accessed_state() {
+	new_test_ext().execute_with(|| {
+		let contract = H160::from_str("1000000000000000000000000000000000000005").unwrap();
+		let mut code = vec![
+			0x60, 0x01, 0x54, 0x50, // POP(SLOAD(1))
+			0x33, 0x31, 0x50, // POP(BALANCE(CALLER))
+			0x73, // PUSH20
+		];
+		code.extend_from_slice(
+			H160::from_str("1000000000000000000000000000000000000001")
+				.unwrap()
+				.as_bytes(),
+		);
+		code.extend_from_slice(&[
+			0x31, 0x50, // POP(BALANCE)
+			0x00, // STOP
+		]);
+		assert_ok!(EVM::create_account(contract, code));
+
+		let info = runner::stack::Runner::<Test>::create_access_list(
+			H160::default(),
+			Some(contract),
+			Vec::new(),
+			U256::default(),
+			1000000,
+			None,
+			None,
+			None,
+			Vec::new(),
+			false,
+			false,
+			<Test as Config>::config(),
+		)
+		.unwrap();
+		assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		// The sender and the recipient are left out, but not the storage key read on the
+		// recipient.
+		assert_eq!(
+			info.access_list,
+			vec![
+				(
+					H160::from_str("1000000000000000000000000000000000000001").unwrap(),
+					vec![]
+				),
+				(contract, vec![H256::from_low_u64_be(1)]),
+			]
+		);
+	});
+}
+
+#[test]
+fn force_setters_require_root() {
+	new_test_ext().execute_with(|| {
+		let addr = H160::from_str("1230000000000000000000000000000000000001").unwrap();
+		let who = <Test as Config>::AddressMapping::into_account_id(addr);
+
+		assert_noop!(
+			EVM::force_set_balance(Origin::signed(who.clone()), addr, 100),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			EVM::force_set_code(Origin::signed(who.clone()), addr, vec![0]),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			EVM::force_set_storage(Origin::signed(who), addr, H256::zero(), H256::zero()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn force_setters_should_work() {
+	new_test_ext().execute_with(|| {
+		let addr = H160::from_str("1230000000000000000000000000000000000001").unwrap();
+		let who = <Test as Config>::AddressMapping::into_account_id(addr);
+		let index = H256::from_low_u64_be(1);
+		let value = H256::from_low_u64_be(2);
+
+		assert_ok!(EVM::force_set_balance(Origin::root(), addr, 100));
+		assert_eq!(EVM::account_basic(&addr).balance, U256::from(100));
+
+		assert_ok!(EVM::force_set_code(Origin::root(), addr, vec![0]));
+		assert_eq!(EVM::account_codes(addr), vec![0]);
+		assert_eq!(frame_system::Account::<Test>::get(&who).sufficients, 1);
+
+		assert_ok!(EVM::force_set_storage(Origin::root(), addr, index, value));
+		assert_eq!(EVM::account_storages(addr, index), value);
+
+		// Empty code and zero values clear the entries again.
+		assert_ok!(EVM::force_set_code(Origin::root(), addr, vec![]));
+		assert!(!<crate::AccountCodes<Test>>::contains_key(addr));
+		assert_eq!(frame_system::Account::<Test>::get(&who).sufficients, 0);
+
+		assert_ok!(EVM::force_set_storage(
+			Origin::root(),
+			addr,
+			index,
+			H256::zero()
+		));
+		assert!(!<crate::AccountStorages<Test>>::contains_key(addr, index));
+	});
+}
+
+#[test]
+fn force_set_code_rejects_code_starting_with_ef() {
+	new_test_ext().execute_with(|| {
+		let addr = H160::from_str("1230000000000000000000000000000000000001").unwrap();
+
+		assert_noop!(
+			EVM::force_set_code(Origin::root(), addr, vec![0xEF, 0x00]),
+			Error::<Test>::InvalidCode
+		);
+		assert_noop!(
+			EVM::create_account(addr, vec![0xEF]).map_err(DispatchError::from),
+			Error::<Test>::InvalidCode
+		);
+	});
+}
+
+#[test]
+fn only_transactional_executions_reject_a_sender_with_code() {
+	new_test_ext().execute_with(|| {
+		let contract = H160::from_str("1000000000000000000000000000000000000005").unwrap();
+		assert_ok!(EVM::create_account(contract, vec![0x00]));
+
+		let call = |is_transactional, skip_eip3607| {
+			<Test as Config>::Runner::call(
+				contract,
+				H160::default(),
+				Vec::new(),
+				U256::default(),
+				1000000,
+				None,
+				None,
+				None,
+				Vec::new(),
+				is_transactional,
+				skip_eip3607,
+				<Test as Config>::config(),
+			)
+		};
+
+		assert_ok!(call(false, false));
+		assert_ok!(call(true, true));
+		assert_err!(
+			call(true, false).map(|_| ()).map_err(DispatchError::from),
+			Error::<Test>::TransactionMustComeFromEOA
+		);
+	});
+}
@@ -23,7 +23,7 @@ use codec::{Decode, Encode};
 pub use evm::ExitReason;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
-use sp_core::{H160, U256};
+use sp_core::{H160, H256, U256};
 use sp_std::vec::Vec;
 
 pub use evm::backend::{Basic as Account, Log};
@@ -62,6 +62,19 @@ pub enum CallOrCreateInfo {
 	Create(CreateInfo),
 }
 
+/// Outcome of an execution that recorded the state it accessed, as used to build an EIP-2930
+/// access list.
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct AccessListInfo {
+	pub exit_reason: ExitReason,
+	/// Return data of the execution, or its revert data.
+	pub value: Vec<u8>,
+	pub used_gas: U256,
+	/// Accessed addresses and storage keys, without the sender, the recipient and precompiles.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+}
+
 /// Account definition used for genesis block construction.
 #[cfg(feature = "std")]
 #[derive(Clone, Eq, PartialEq, Encode, Decode, Debug, Serialize, Deserialize)]
@@ -51,7 +51,7 @@ impl Default for TransactionStatus {
 
 sp_api::decl_runtime_apis! {
 	/// API necessary for Ethereum-compatibility layer.
-	#[api_version(4)]
+	#[api_version(6)]
 	pub trait EthereumRuntimeRPCApi {
 		/// Returns runtime defined pallet_evm::ChainId.
 		fn chain_id() -> u64;
@@ -176,6 +176,22 @@ sp_api::decl_runtime_apis! {
 		) -> Vec<ethereum::TransactionV2>;
 		/// Return the elasticity multiplier.
 		fn elasticity() -> Option<Permill>;
+		/// Return the revert data of the reverted calls of the current block, by transaction
+		/// index.
+		fn current_revert_data() -> Vec<(u32, Vec<u8>)>;
+		/// Execute a call, or a create when `to` is `None`, and return the addresses and storage
+		/// keys it accessed along with the gas it used.
+		fn create_access_list(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			access_list: Option<Vec<(H160, Vec<H256>)>>,
+		) -> Result<fp_evm::AccessListInfo, sp_runtime::DispatchError>;
 	}
 
 	#[api_version(2)]
@@ -184,6 +200,13 @@ sp_api::decl_runtime_apis! {
 		#[changed_in(2)]
 		fn convert_transaction(transaction: ethereum::TransactionV0) -> <Block as BlockT>::Extrinsic;
 	}
+
+	/// API to check the Ethereum-compatible state root maintained by the runtime.
+	pub trait EthereumStateRootApi {
+		/// Returns the maintained state root and the root recomputed from scratch from the
+		/// current EVM state, or `None` if the chain does not maintain the state trie.
+		fn check_state_root() -> Option<(H256, H256)>;
+	}
 }
 
 pub trait ConvertTransaction<E> {